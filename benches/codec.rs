@@ -0,0 +1,46 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use leapsecs::*;
+use std::str::FromStr;
+
+const TEXT: &str = "6+6+12+12+12+12+12+12+12+18+12+12+24+30+24+\
+                    12+18+12+12+18+18+18+84+36+42+36+18+999?";
+
+fn bench(c: &mut Criterion) {
+    let list = LeapSecs::from_str(TEXT).unwrap();
+    let bytes = Vec::from(&list);
+
+    c.bench_function("canonical_bytes", |b| {
+        b.iter(|| black_box(&list).canonical_bytes())
+    });
+
+    c.bench_function("iter_bytes", |b| {
+        b.iter(|| black_box(&list).iter_bytes().fold(0u8, |a, b| a ^ b))
+    });
+
+    c.bench_function("len_bytes", |b| b.iter(|| black_box(&list).len_bytes()));
+
+    c.bench_function("try_from_bytes", |b| {
+        b.iter(|| LeapSecs::try_from_bytes_lenient(black_box(&bytes)))
+    });
+
+    c.bench_function("decoder", |b| {
+        b.iter(|| {
+            let mut decoder = bin::Decoder::new();
+            for chunk in black_box(&bytes).chunks(4) {
+                decoder.feed(chunk).unwrap();
+            }
+            decoder.into_builder().unwrap()
+        })
+    });
+
+    c.bench_function("borrowed", |b| {
+        b.iter(|| {
+            let list =
+                bin::LeapSecsRef::try_from_bytes_lenient(black_box(&bytes));
+            list.unwrap().expires()
+        })
+    });
+}
+
+criterion_group!(benches, bench);
+criterion_main!(benches);
@@ -0,0 +1,47 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use leapsecs::*;
+use std::str::FromStr;
+
+const TEXT: &str = "6+6+12+12+12+12+12+12+12+18+12+12+24+30+24+\
+                    12+18+12+12+18+18+18+84+36+42+36+18+999?";
+
+// timestamps spread pseudo-randomly between 1972 and 2030
+fn stamps(n: usize) -> Vec<i64> {
+    let mut x: u64 = 0x2545_F491_4F6C_DD1D;
+    (0..n)
+        .map(|_| {
+            x ^= x << 13;
+            x ^= x >> 7;
+            x ^= x << 17;
+            63_072_000 + (x % 1_830_000_000) as i64
+        })
+        .collect()
+}
+
+fn bench(c: &mut Criterion) {
+    let list = LeapSecs::from_str(TEXT).unwrap();
+    let lookup = list.compile();
+    let input = stamps(100_000);
+
+    c.bench_function("dtai_at", |b| {
+        b.iter(|| {
+            let mut sum = 0;
+            for &stamp in &input {
+                let mjd = UNIX_EPOCH_MJD + stamp.div_euclid(86400) as i32;
+                sum += list.dtai_at(Gregorian::from(mjd)).unwrap() as i64;
+            }
+            black_box(sum)
+        })
+    });
+
+    c.bench_function("convert_slice", |b| {
+        let mut stamps = input.clone();
+        b.iter(|| {
+            stamps.copy_from_slice(&input);
+            lookup.convert_slice(black_box(&mut stamps)).unwrap();
+        })
+    });
+}
+
+criterion_group!(benches, bench);
+criterion_main!(benches);
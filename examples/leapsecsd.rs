@@ -0,0 +1,247 @@
+// leapsecsd: keep a leap second list up to date and serve it over HTTP
+//
+//     cargo run --example leapsecsd -- [--listen ADDR] [--cache FILE]
+//                                      [--refresh SECONDS]
+//
+// The list is cached in a file (leap-seconds.list in the current
+// directory by default) using nist::FileStore. A background thread
+// refreshes it from the IERS and NIST servers using read_best_with(),
+// and only replaces the cached list with one that extends it, so a
+// corrupt or replayed list is never installed.
+//
+// It serves,
+//
+//   /leap-seconds.list  the NIST format, for ntpd and chrony
+//   /leapsecs.txt       the compact text format
+//   /leapsecs.bin       the compact binary format
+//   /metrics            Prometheus metrics about the list and refreshes
+//   /audit              where the list came from and when it expires
+//
+// This is a reference deployment, not a production web server: it
+// handles one request per connection and ignores request headers.
+
+use leapsecs::*;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime};
+
+struct Config {
+    listen: String,
+    cache: String,
+    refresh: Duration,
+}
+
+// everything the refresh thread tells the HTTP server
+struct State {
+    list: LeapSecs,
+    // seconds since 1970 of the last successful refresh
+    refreshed: Option<u64>,
+    failures: u64,
+    last_error: Option<String>,
+}
+
+type Shared = Arc<RwLock<State>>;
+
+fn config() -> anyhow::Result<Config> {
+    let mut config = Config {
+        listen: "127.0.0.1:8123".to_string(),
+        cache: nist::NIST_FILE.to_string(),
+        refresh: Duration::from_secs(86400),
+    };
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || match args.next() {
+            Some(value) => Ok(value),
+            None => Err(anyhow::anyhow!("{} needs a value", arg)),
+        };
+        match arg.as_str() {
+            "--listen" => config.listen = value()?,
+            "--cache" => config.cache = value()?,
+            "--refresh" => {
+                config.refresh = Duration::from_secs(value()?.parse()?)
+            }
+            _ => anyhow::bail!("unknown argument {}", arg),
+        }
+    }
+    Ok(config)
+}
+
+fn now() -> u64 {
+    let now = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH);
+    now.map(|now| now.as_secs()).unwrap_or_default()
+}
+
+// fetch the best list, and install it if it extends the current one
+fn refresh(state: &Shared, store: &nist::FileStore) -> anyhow::Result<()> {
+    let sources = nist::Mirrors::default().sources();
+    let best = nist::read_best_with(&sources, nist::Policy::default())?;
+    for (source, err) in &best.failures {
+        eprintln!("leapsecsd: {}: {:#}", source, err);
+    }
+    let current = state.read().unwrap().list.clone();
+    if !best.list.extends(&current) {
+        best.list.verify_history(&current)?;
+        // an older list from a lagging mirror
+        return Ok(());
+    }
+    if best.list.expires() > current.expires() {
+        let provenance = best.list.provenance().cloned();
+        nist::Store::store(store, nist::format(&best.list)?.as_bytes())?;
+        if let Some(provenance) = &provenance {
+            nist::Store::store_provenance(store, provenance)?;
+        }
+        eprintln!(
+            "leapsecsd: installed list from {}, expires {}",
+            best.source,
+            Gregorian::from(best.list.expires())
+        );
+        state.write().unwrap().list = best.list;
+    }
+    Ok(())
+}
+
+fn refresh_loop(state: Shared, store: nist::FileStore, every: Duration) {
+    loop {
+        let result = refresh(&state, &store);
+        let mut state = state.write().unwrap();
+        match result {
+            Ok(()) => {
+                state.refreshed = Some(now());
+                state.last_error = None;
+            }
+            Err(err) => {
+                eprintln!("leapsecsd: refresh failed: {:#}", err);
+                state.failures += 1;
+                state.last_error = Some(format!("{:#}", err));
+            }
+        }
+        drop(state);
+        std::thread::sleep(every);
+    }
+}
+
+fn metrics(state: &State) -> String {
+    let list = &state.list;
+    let today = MJD::today();
+    let mut out = String::new();
+    let mut metric = |name: &str, help: &str, value: String| {
+        out += &format!("# HELP leapsecs_{} {}\n", name, help);
+        out += &format!("# TYPE leapsecs_{} gauge\n", name);
+        out += &format!("leapsecs_{} {}\n", name, value);
+    };
+    metric(
+        "expiry_timestamp_seconds",
+        "Start of the expiry date of the list.",
+        Unix::from(list.expires()).to_string(),
+    );
+    metric(
+        "expires_in_days",
+        "Days until the list expires.",
+        (list.expires() - today).to_string(),
+    );
+    if let Ok(dtai) = list.dtai_at(Gregorian::from(today)) {
+        metric("dtai_seconds", "TAI - UTC today.", dtai.to_string());
+    }
+    metric("entries", "Entries in the list.", list.len().to_string());
+    metric(
+        "refresh_success_timestamp_seconds",
+        "Time of the last successful refresh.",
+        state.refreshed.unwrap_or_default().to_string(),
+    );
+    metric(
+        "refresh_failures",
+        "Failed refreshes since startup.",
+        state.failures.to_string(),
+    );
+    out
+}
+
+fn audit(state: &State) -> String {
+    let list = &state.list;
+    let mut out = format!("list      {}\n", list);
+    match list.provenance() {
+        Some(provenance) => out += &format!("source    {}\n", provenance),
+        None => out += "source    unknown\n",
+    }
+    out += &format!("expires   {}\n", Gregorian::from(list.expires()));
+    let horizon = ExpiryHorizon::default();
+    let status = if list.expires_within(horizon.fail_days) {
+        "EXPIRED".to_string()
+    } else if list.expires_within(horizon.warn_days) {
+        format!("expires within {} days", horizon.warn_days)
+    } else {
+        "ok".to_string()
+    };
+    out += &format!("status    {}\n", status);
+    if let Some(err) = &state.last_error {
+        out += &format!("refresh   {}\n", err);
+    }
+    out
+}
+
+fn respond(stream: TcpStream, state: &Shared) -> anyhow::Result<()> {
+    let mut line = String::new();
+    BufReader::new(&stream).read_line(&mut line)?;
+    let path = line.split_whitespace().nth(1).unwrap_or("/");
+    let state = state.read().unwrap();
+    let (status, kind, body) = match path {
+        "/leap-seconds.list" => {
+            ("200 OK", "text/plain", nist::format(&state.list)?.into_bytes())
+        }
+        "/leapsecs.txt" => {
+            ("200 OK", "text/plain", format!("{}\n", state.list).into_bytes())
+        }
+        "/leapsecs.bin" => {
+            ("200 OK", "application/octet-stream", Vec::from(&state.list))
+        }
+        "/metrics" => ("200 OK", "text/plain", metrics(&state).into_bytes()),
+        "/audit" => ("200 OK", "text/plain", audit(&state).into_bytes()),
+        _ => ("404 Not Found", "text/plain", b"not found\n".to_vec()),
+    };
+    drop(state);
+    let mut stream = stream;
+    write!(
+        stream,
+        "HTTP/1.0 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\n\r\n",
+        status,
+        kind,
+        body.len()
+    )?;
+    stream.write_all(&body)?;
+    Ok(())
+}
+
+fn main() -> anyhow::Result<()> {
+    let config = config()?;
+    let store = nist::FileStore::new(&config.cache);
+    let list = nist::read_with(&store)?;
+    eprintln!(
+        "leapsecsd: loaded list from {}, expires {}",
+        list.provenance().map(|p| p.to_string()).unwrap_or_default(),
+        Gregorian::from(list.expires())
+    );
+    let state = Arc::new(RwLock::new(State {
+        list,
+        refreshed: None,
+        failures: 0,
+        last_error: None,
+    }));
+
+    let refresher = Arc::clone(&state);
+    let every = config.refresh;
+    std::thread::spawn(move || refresh_loop(refresher, store, every));
+
+    let listener = TcpListener::bind(&config.listen)?;
+    eprintln!("leapsecsd: listening on {}", config.listen);
+    for stream in listener.incoming() {
+        let stream = stream?;
+        let state = Arc::clone(&state);
+        std::thread::spawn(move || {
+            if let Err(err) = respond(stream, &state) {
+                eprintln!("leapsecsd: {:#}", err);
+            }
+        });
+    }
+    Ok(())
+}
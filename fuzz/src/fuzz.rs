@@ -17,7 +17,9 @@ fn fuzz_bin(data: &[u8]) {
     // the data is not going to be in canonical form, so we can't just
     // output the list in binary format and expect it to match, so
     // let's check a round-trip via text format
-    let out1: &[u8] = &Vec::<u8>::from(&parse1);
+    let out1: &[u8] = &parse1.canonical_bytes();
+    assert!(bin::is_canonical(out1));
+    assert_eq!(bin::is_canonical(data), data == out1);
     let text = format!("{}", parse1);
     let parse2 = LeapSecs::from_str(&text).unwrap();
     let out2: &[u8] = &Vec::<u8>::from(&parse2);
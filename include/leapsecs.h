@@ -0,0 +1,40 @@
+/* C bindings for the leapsecs crate, see src/ffi.rs */
+/* Written by hand: keep it in step with the functions in src/ffi.rs */
+
+#ifndef LEAPSECS_H
+#define LEAPSECS_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* An opaque leap second list */
+typedef struct LeapSecs LeapSecs;
+
+/* Parse a leap second list in compact binary format.
+ * Returns NULL if the list is invalid. Otherwise the list must be
+ * released with leapsecs_free(). */
+LeapSecs *leapsecs_from_bytes(const uint8_t *bytes, size_t len);
+
+/* Release a list returned by leapsecs_from_bytes(). Does nothing if
+ * list is NULL. */
+void leapsecs_free(LeapSecs *list);
+
+/* Get the difference between UTC and TAI at a Unix timestamp and
+ * store it in *dtai. Returns 0 on success, or -1 if the timestamp is
+ * before 1972 or after the list's expiry date. */
+int32_t leapsecs_dtai_at_unix(const LeapSecs *list, int64_t unix, int16_t *dtai);
+
+/* Get the Unix timestamp at the start of the list's expiry date. The
+ * list remains valid until the end of that day. Returns INT64_MIN if
+ * list is NULL. */
+int64_t leapsecs_expires(const LeapSecs *list);
+
+#ifdef __cplusplus
+} /* extern "C" */
+#endif
+
+#endif /* LEAPSECS_H */
@@ -0,0 +1,253 @@
+//! Archive format for fast startup
+//! ===============================
+//!
+//! A service that runs on a large fleet of machines should not need
+//! to parse and check the NIST `leap-seconds.list` every time it
+//! starts. Instead, [`write()`][] can prepare an archive once, which
+//! is the list in compact binary format after a small header, and
+//! [`open_at()`][] checks the archive and returns a
+//! [`LeapSecsRef`][] that reads the list directly from its bytes,
+//! for example,
+//!
+//!     # use leapsecs::*;
+//!     # use std::str::FromStr;
+//!     let list = LeapSecs::from_str("6+6+12+999?")?;
+//!     let bytes = archive::write(&list);
+//!     assert_eq!(&bytes[..8], archive::MAGIC);
+//!     let view = archive::open_at(&bytes, Gregorian(2021, 1, 1).mjd())?;
+//!     assert_eq!(view.dtai_at(Gregorian(2020, 1, 1))?, 13);
+//!     assert_eq!(view.expires(), list.expires());
+//!     # Ok::<(), Error>(())
+//!
+//! The header is 16 bytes long, and all its numbers are big-endian:
+//!
+//!   * 8 bytes: the magic string [`MAGIC`][];
+//!
+//!   * 1 byte: the format [`VERSION`][], which is 1;
+//!
+//!   * 1 byte: zero;
+//!
+//!   * 2 bytes: the CRC-16/CCITT-FALSE of the compact binary format,
+//!     as in [`LeapSecs::text_checksum()`][];
+//!
+//!   * 4 bytes: the list's expiry date as a signed modified Julian
+//!     day number, so that an expired archive can be rejected without
+//!     decoding the list.
+//!
+//! The rest of the archive is the compact binary format, and opening
+//! it checks the list as thoroughly as [`LeapSecsRef`][] does. A
+//! header that does not match the list fails with
+//! [`Error::Archive`][].
+//!
+//! With the `mmap` feature, [`Mapped`][] opens an archive file by
+//! mapping it into memory, and with `std`, [`write_file()`][]
+//! replaces an archive file without disturbing processes that have
+//! mapped the old one.
+//!
+//! This module requires the `alloc` feature.
+
+use crate::bin::LeapSecsRef;
+use crate::*;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+
+/// The magic string at the start of an archive
+pub const MAGIC: &[u8; 8] = b"leapsecs";
+
+/// The version of the archive format written by [`write()`][]
+pub const VERSION: u8 = 1;
+
+/// The length of an archive's header, before the compact binary format
+pub const HEADER: usize = 16;
+
+/// Make an archive of a list, with a header followed by the compact
+/// binary format.
+///
+pub fn write(list: &LeapSecs) -> Vec<u8> {
+    let binary = Vec::from(list);
+    let expires = list.expires() - MJD::from(0);
+    let mut out = Vec::with_capacity(HEADER + binary.len());
+    out.extend_from_slice(MAGIC);
+    out.push(VERSION);
+    out.push(0);
+    out.extend_from_slice(&txt::crc16(binary.iter().copied()).to_be_bytes());
+    out.extend_from_slice(&expires.to_be_bytes());
+    out.extend_from_slice(&binary);
+    out
+}
+
+/// Check an archive, and for expiry using the given [`Clock`][],
+/// returning a view of the list that borrows the archive's bytes.
+///
+/// Fails with [`Error::Expired`][] if the archive has expired, before
+/// looking at the list itself.
+///
+pub fn open_at<C: Clock>(bytes: &[u8], clock: C) -> Result<LeapSecsRef<'_>> {
+    let expires = header(bytes)?;
+    if expires < clock.today() {
+        return Err(Error::Expired(Gregorian::from(expires)));
+    }
+    open_lenient(bytes)
+}
+
+/// Check an archive without checking for expiry, returning a view of
+/// the list that borrows the archive's bytes.
+///
+pub fn open_lenient(bytes: &[u8]) -> Result<LeapSecsRef<'_>> {
+    let expires = header(bytes)?;
+    let binary = &bytes[HEADER..];
+    let sum = u16::from_be_bytes([bytes[10], bytes[11]]);
+    if txt::crc16(binary.iter().copied()) != sum {
+        return Err(Error::Archive("checksum failed"));
+    }
+    let list = LeapSecsRef::try_from_bytes_lenient(binary)?;
+    if list.expires() != expires {
+        return Err(Error::Archive("expiry date does not match list"));
+    }
+    Ok(list)
+}
+
+// check the fixed part of the header and get the expiry date
+fn header(bytes: &[u8]) -> Result<MJD> {
+    if bytes.len() < HEADER {
+        return Err(Error::Archive("truncated header"));
+    }
+    if &bytes[..8] != MAGIC {
+        return Err(Error::Archive("bad magic string"));
+    }
+    if bytes[8] != VERSION || bytes[9] != 0 {
+        return Err(Error::Archive("unsupported version"));
+    }
+    // the length was checked above
+    let expires = <[u8; 4]>::try_from(&bytes[12..HEADER]).unwrap();
+    Ok(MJD::from(i32::from_be_bytes(expires)))
+}
+
+/// Write an archive of a list to a file, by writing a temporary file
+/// and renaming it, so that a process that has mapped the old file
+/// keeps a complete copy of the old list.
+///
+/// This requires the `std` feature.
+///
+#[cfg(feature = "std")]
+pub fn write_file<P: AsRef<std::path::Path>>(
+    path: P,
+    list: &LeapSecs,
+) -> Result<()> {
+    let path = path.as_ref();
+    let mut temp = path.as_os_str().to_owned();
+    temp.push(".tmp");
+    std::fs::write(&temp, write(list))?;
+    std::fs::rename(&temp, path)?;
+    Ok(())
+}
+
+/// An archive file mapped into memory, from [`Mapped::open_at()`][].
+///
+/// The file is checked once when it is opened, and after that
+/// [`Mapped::list()`][] is free. The file must not be modified while
+/// it is mapped, which [`write_file()`][] ensures by replacing it
+/// instead. If it is truncated or overwritten anyway, lookups can
+/// return wrong answers, or the process can crash.
+///
+/// This requires the `mmap` feature.
+///
+#[cfg(feature = "mmap")]
+#[derive(Debug)]
+pub struct Mapped {
+    map: memmap2::Mmap,
+    expires: MJD,
+}
+
+#[cfg(feature = "mmap")]
+impl Mapped {
+    /// Map an archive file and check it as if by [`open_at()`][].
+    ///
+    pub fn open_at<P, C>(path: P, clock: C) -> Result<Mapped>
+    where
+        P: AsRef<std::path::Path>,
+        C: Clock,
+    {
+        let file = std::fs::File::open(path)?;
+        // SAFETY: the file is only ever replaced, never modified in
+        // place, as described above
+        let map = unsafe { memmap2::Mmap::map(&file)? };
+        let expires = open_at(&map, clock)?.expires();
+        Ok(Mapped { map, expires })
+    }
+
+    /// Map an archive file and check it as if by [`open_at()`][],
+    /// using the [`SystemClock`][].
+    ///
+    pub fn open<P: AsRef<std::path::Path>>(path: P) -> Result<Mapped> {
+        Mapped::open_at(path, SystemClock)
+    }
+
+    /// Get a view of the list, which borrows the mapped file.
+    pub fn list(&self) -> LeapSecsRef<'_> {
+        LeapSecsRef::from_checked(&self.map[HEADER..], self.expires)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod test {
+    use crate::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test() {
+        let text = history!("999?");
+        let list = LeapSecs::from_str(text).unwrap();
+        let bytes = archive::write(&list);
+        assert_eq!(bytes.len(), archive::HEADER + list.binary_len());
+        let view = archive::open_lenient(&bytes).unwrap();
+        assert!(view.iter().eq(list.iter().copied()));
+        let today = Gregorian(2100, 4, 29).mjd();
+        let err = archive::open_at(&bytes, today).unwrap_err();
+        assert!(matches!(err, Error::Expired(Gregorian(2100, 4, 28))));
+
+        for (offset, want) in &[
+            (0, "bad magic string"),
+            (8, "unsupported version"),
+            (9, "unsupported version"),
+            (10, "checksum failed"),
+            (13, "expiry date does not match list"),
+        ] {
+            let mut bad = bytes.clone();
+            bad[*offset] ^= 1;
+            let err = archive::open_lenient(&bad).unwrap_err();
+            assert!(matches!(err, Error::Archive(what) if what == *want));
+        }
+        let err = archive::open_lenient(&bytes[..15]).unwrap_err();
+        assert!(matches!(err, Error::Archive("truncated header")));
+        // a consistent header does not hide a truncated list
+        let short = &list.canonical_bytes()[..10];
+        let mut bad = bytes[..10].to_vec();
+        bad.extend_from_slice(&txt::crc16(short.iter().copied()).to_be_bytes());
+        bad.extend_from_slice(&bytes[12..16]);
+        bad.extend_from_slice(short);
+        assert!(archive::open_lenient(&bad).is_err());
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn mapped() {
+        let list = LeapSecs::from_str("6+6+12+999?").unwrap();
+        let path = temp_path("test.archive");
+        archive::write_file(&path, &list).unwrap();
+        let mapped = archive::Mapped::open(&path).unwrap();
+        assert_eq!(mapped.list().expires(), list.expires());
+        assert_eq!(mapped.list().dtai_at(Gregorian(2000, 1, 1)).unwrap(), 13);
+
+        // replacing the file does not disturb the old mapping
+        let new = LeapSecs::from_str("6+6+12+12+999?").unwrap();
+        archive::write_file(&path, &new).unwrap();
+        assert_eq!(mapped.list().iter().count(), 5);
+        let remapped = archive::Mapped::open(&path).unwrap();
+        assert_eq!(remapped.list().iter().count(), 6);
+
+        let today = Gregorian(2100, 1, 1).mjd();
+        assert!(archive::Mapped::open_at(&path, today).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,194 @@
+//! Checking many copies of the list
+//! ================================
+//!
+//! A registry that collects copies of `leap-seconds.list` from many
+//! mirrors needs to find the ones that are out of date or corrupt.
+//! [`validate_all()`][] reads every source and compares the lists
+//! with each other, for example,
+//!
+//!     # use leapsecs::*;
+//!     # use leapsecs::batch::Status;
+//!     # let dir = std::env::temp_dir();
+//!     # let mirror = |name: &str, list: &str| -> anyhow::Result<_> {
+//!     #     let pid = std::process::id();
+//!     #     let path = format!("leapsecs-batch-doc-{}-{}.list", pid, name);
+//!     #     let path = dir.join(path);
+//!     #     let path = path.to_str().unwrap().to_string();
+//!     #     let list: LeapSecs = list.parse()?;
+//!     #     std::fs::write(&path, nist::format(&list)?)?;
+//!     #     Ok(nist::Source::File(path))
+//!     # };
+//!     let sources = vec![
+//!         mirror("a", "6+6+12+999?")?,
+//!         mirror("b", "6+6+12+998?")?,
+//!         nist::Source::File("/nonexistent".to_string()),
+//!     ];
+//!     let reports = batch::validate_all(sources);
+//!     assert_eq!(reports[0].status, Status::Current);
+//!     assert_eq!(reports[1].status, Status::Stale);
+//!     assert_eq!(reports[2].status, Status::Failed);
+//!     # Ok::<(), anyhow::Error>(())
+//!
+//! The sources are read one at a time, or in parallel using `rayon`'s
+//! thread pool when the `rayon` feature is enabled, which makes a big
+//! difference when there are hundreds of mirrors.
+//!
+//! This requires the `fetch` feature.
+
+use crate::nist::Source;
+use crate::*;
+
+/// How a list compares with the other lists in the batch.
+///
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub enum Status {
+    /// The list agrees with the reference list and expires on the
+    /// same date.
+    Current,
+    /// The list could not be read because it has expired.
+    Expired,
+    /// The list could not be read, for example because it is missing
+    /// or its checksum is wrong.
+    Failed,
+    /// The list disagrees with the reference list about which leap
+    /// seconds there have been.
+    Inconsistent,
+    /// The list agrees with the reference list but expires earlier.
+    Stale,
+}
+
+/// The result of checking one source.
+///
+#[derive(Debug)]
+pub struct Report {
+    /// Where the list came from
+    pub source: Source,
+    /// The list, or why it could not be read
+    pub result: anyhow::Result<LeapSecs>,
+    /// How the list compares with the others
+    pub status: Status,
+}
+
+/// Read and check all the `sources`, returning a [`Report`][] for
+/// each of them in the same order.
+///
+/// The lists are compared with a reference list, which is the one
+/// that the most other lists are a prefix of, as checked by
+/// [`LeapSecs::is_prefix_of()`][]; if there is a tie, the latest
+/// expiry date wins, then the most secure source, then the earliest.
+/// So a corrupt list that claims to be newer than all the others
+/// does not make them look inconsistent.
+///
+pub fn validate_all<I>(sources: I) -> Vec<Report>
+where
+    I: IntoIterator<Item = Source>,
+{
+    let sources: Vec<Source> = sources.into_iter().collect();
+    let results = read_all(&sources);
+    let lists: Vec<(&LeapSecs, &Source)> = results
+        .iter()
+        .zip(&sources)
+        .filter_map(|(result, source)| Some((result.as_ref().ok()?, source)))
+        .collect();
+    let mut reference: Option<(usize, &LeapSecs, &Source)> = None;
+    for &(list, source) in &lists {
+        let support = lists.iter().filter(|(l, _)| l.is_prefix_of(list));
+        let rank = (support.count(), list.expires(), source.security());
+        match reference {
+            Some((n, prev, old))
+                if (n, prev.expires(), old.security()) >= rank => {}
+            _ => reference = Some((rank.0, list, source)),
+        }
+    }
+    let reference = reference.map(|(_, list, _)| list.clone());
+    sources
+        .into_iter()
+        .zip(results)
+        .map(|(source, result)| {
+            let status = match &result {
+                Ok(list) => reference
+                    .as_ref()
+                    .map_or(Status::Current, |r| status(list, r)),
+                Err(err) => match err.downcast_ref() {
+                    Some(Error::Expired(_)) => Status::Expired,
+                    _ => Status::Failed,
+                },
+            };
+            Report { source, result, status }
+        })
+        .collect()
+}
+
+fn status(list: &LeapSecs, reference: &LeapSecs) -> Status {
+    if !list.is_prefix_of(reference) {
+        Status::Inconsistent
+    } else if list.expires() < reference.expires() {
+        Status::Stale
+    } else {
+        Status::Current
+    }
+}
+
+#[cfg(feature = "rayon")]
+fn read_all(sources: &[Source]) -> Vec<anyhow::Result<LeapSecs>> {
+    use rayon::prelude::*;
+    sources.par_iter().map(Source::read).collect()
+}
+
+#[cfg(not(feature = "rayon"))]
+fn read_all(sources: &[Source]) -> Vec<anyhow::Result<LeapSecs>> {
+    sources.iter().map(Source::read).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::batch::{self, Status};
+    use crate::nist::Source;
+    use crate::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test() {
+        let base = temp_path("batch");
+        let mirror = |name: &str, gaps: &str| {
+            let path = format!("{}-{}.list", base.display(), name);
+            let list = LeapSecs::from_str_lenient(gaps).unwrap();
+            std::fs::write(&path, nist::format(&list).unwrap()).unwrap();
+            Source::File(path)
+        };
+        let prefix = "6+6+12+12+12+12+12+12+12+18+12+12+24+30+24+";
+        let sources = vec![
+            mirror("old", &format!("{}998?", prefix)),
+            mirror("bogus", &format!("{}6-999?", prefix)),
+            mirror("new", &format!("{}999?", prefix)),
+            mirror("expired", &format!("{}5?", prefix)),
+            mirror("copy", &format!("{}999?", prefix)),
+            Source::File(format!("{}-missing.list", base.display())),
+        ];
+        let _ = std::fs::remove_file(sources[5].to_string());
+        let reports = batch::validate_all(sources.clone());
+        let status: Vec<Status> = reports.iter().map(|r| r.status).collect();
+        assert_eq!(
+            status,
+            [
+                Status::Stale,
+                Status::Inconsistent,
+                Status::Current,
+                Status::Expired,
+                Status::Current,
+                Status::Failed,
+            ]
+        );
+        for (report, source) in reports.iter().zip(&sources) {
+            assert_eq!(&report.source, source);
+        }
+        let new = LeapSecs::from_str(&format!("{}999?", prefix)).unwrap();
+        let got = reports[2].result.as_ref().unwrap();
+        assert_eq!(got.expires(), new.expires());
+
+        // a lone inconsistent list is the reference for itself
+        let reports = batch::validate_all(vec![sources[1].clone()]);
+        assert_eq!(reports[0].status, Status::Current);
+        assert!(batch::validate_all(Vec::new()).is_empty());
+    }
+}
@@ -4,7 +4,7 @@
 //! This module implements a number of methods and standard traits for
 //! the [`LeapSecs`][] type:
 //!
-//!   * [`std::convert::TryFrom<&[u8]>`][std::convert::TryFrom] parses a
+//!   * [`core::convert::TryFrom<&[u8]>`][core::convert::TryFrom] parses a
 //!     leap second list in compact binary format,  returning
 //!     `Result<`[`LeapSecs`][crate::LeapSecs]`, `[`Error`][enum@Error]`>`.
 //!
@@ -12,17 +12,55 @@
 //!     `[`Vec<u8>`][Vec] generate the compact binary format as a
 //!     freshly allocated `Vec`.
 //!
+//!   * [`LeapSecs::try_from_bytes_at()`][] is like `TryFrom<&[u8]>`
+//!     but checks for expiry using the given [`Clock`][].
+//!
+//!   * [`LeapSecs::try_from_bytes_lenient()`][] is like `TryFrom<&[u8]>`
+//!     but accepts expired lists.
+//!
+//!   * [`Decoder`][] parses the compact binary format incrementally,
+//!     and [`LeapSecs::read_bytes()`][] reads it from a
+//!     [`std::io::Read`][] object (which requires the `std` feature).
+//!
 //!   * [`LeapSecs::iter_bytes()`][] generates the compact binary
 //!     format one byte at a time as an iterator.
 //!
-//!   * [`LeapSecs::len_bytes()`][] returns the lenght of the compact
-//!     binary format.
+//!   * [`LeapSecs::canonical_bytes()`][] generates the compact
+//!     binary format as a `Vec`, and [`is_canonical()`][] checks
+//!     that some bytes are in the same canonical form.
+//!
+//!   * [`LeapSecs::len_bytes()`][] (or its alias
+//!     [`LeapSecs::binary_len()`][]) returns the length of the compact
+//!     binary format, without encoding it.
+//!
+//!   * [`encode_v2()`][] and [`decode_any()`][] handle a version
+//!     marker, so that the format can be extended without old
+//!     decoders misreading new data.
 //!
 //!   * [`LeapSecs::write_bytes()`][] outputs the compact binary
-//!     format to a [`std::io::Write`][] object, one byte at a time.
+//!     format to a [`std::io::Write`][] object. This requires the
+//!     `std` feature.
+//!
+//!   * [`BinWriter`][] generates the compact binary format as entries
+//!     are pushed, without building a [`LeapSecs`][] first. This also
+//!     requires the `std` feature.
+//!
+//! All of the above require the `alloc` feature. The decoder is also
+//! used by [`LeapSecsFixed`][] and [`LeapSecsRef`][], which do not.
+//!
+//! Decoding never panics, whatever the input. Malformed or
+//! unsuitable data fails with [`Error::Empty`][] or
+//! [`Error::Truncated`][] if the list is incomplete, [`Error::Gap`][]
+//! if a gap is too long, [`Error::Expired`][] if there is data after
+//! the expiry date (or if the list has expired, unless decoding
+//! leniently), [`Error::FromInt`][] if the dates or DTAI overflow,
+//! [`Error::Full`][] if a [`LeapSecsFixed`][] is too small, or
+//! [`Error::BinVersion`][] from [`decode_any()`][].
+//! A [`LeapSecsRef`][] validates the compact binary format once, then
+//! reads the list directly from the borrowed bytes.
 
 use crate::*;
-use std::result::Result;
+use core::result::Result;
 
 const WIDE: u8 = 0x80;
 const MONTH: u8 = 0x40;
@@ -41,65 +79,311 @@ fn wide(nibble: u8) -> bool {
 // |_| |_| \___/_|_|_| |_.__/\_, |\__\___/__/
 //                           |__/
 
-// iterate over bytes one nibble at a time
+// the decoder state between nibbles, kept apart from the list so
+// that the same code can fill a LeapSecBuilder or a LeapSecsFixed
 
-struct Nibbles<'a> {
-    inner: std::slice::Iter<'a, u8>,
-    byte: Option<u8>,
+#[derive(Copy, Clone, Debug, Default)]
+pub(crate) struct Nibbles {
+    // the first half of a wide bytecode
+    hi: Option<u8>,
 }
 
-impl<'a> Iterator for Nibbles<'a> {
-    type Item = u8;
-    fn next(&mut self) -> Option<u8> {
-        if let Some(byte) = self.byte {
-            self.byte = None;
-            Some(byte)
-        } else if let Some(&byte) = self.inner.next() {
+impl Nibbles {
+    pub(crate) fn feed<E>(
+        &mut self,
+        list: &mut E,
+        chunk: &[u8],
+    ) -> Result<(), Error>
+    where
+        E: Entries,
+    {
+        for &byte in chunk {
             // bigendian
-            self.byte = Some(byte & LOW);
-            Some(byte >> 4)
-        } else {
-            None
+            self.nibble(list, byte >> 4)?;
+            self.nibble(list, byte & LOW)?;
         }
+        Ok(())
+    }
+
+    fn nibble<E: Entries>(
+        &mut self,
+        list: &mut E,
+        nibble: u8,
+    ) -> Result<(), Error> {
+        match self.hi.take() {
+            Some(hi) => bytecode(list, hi << 4 | nibble),
+            None if !wide(nibble) => bytecode(list, POS | nibble),
+            None => {
+                self.hi = Some(nibble);
+                Ok(())
+            }
+        }
+    }
+
+    pub(crate) fn finish<E: Entries>(
+        mut self,
+        list: &mut E,
+    ) -> Result<(), Error> {
+        if let Some(hi) = self.hi.take() {
+            // add trailing nibble
+            bytecode(list, hi << 4 | 4)?;
+        }
+        Ok(())
     }
 }
 
-// convert nibbles to bytecodes
+fn bytecode<E: Entries>(list: &mut E, code: u8) -> Result<(), Error> {
+    let mul = if code & MONTH != 0 { 1 } else { 6 };
+    let gap = (((code & LOW) + 1) * mul) as i32;
+    let sign = match code & (NEG | POS) {
+        NEG => Leap::Neg,
+        POS => Leap::Pos,
+        0 => Leap::Zero,
+        _ => Leap::Exp,
+    };
+    list.push_gap(gap, sign)
+}
 
-struct Expand<'a>(Nibbles<'a>);
+/// An incremental decoder for the compact binary format.
+///
+/// This is for when the list arrives in pieces, for example over a
+/// network stream. Call [`Decoder::feed()`][] with each chunk as it
+/// arrives, then [`Decoder::finish()`][] to get the list.
+///
+#[cfg(feature = "alloc")]
+#[derive(Clone, Debug, Default)]
+pub struct Decoder {
+    list: LeapSecBuilder,
+    nibbles: Nibbles,
+}
 
-impl<'a> Iterator for Expand<'a> {
-    type Item = u8;
-    fn next(&mut self) -> Option<u8> {
-        match self.0.next() {
-            None => None,
-            Some(lo) if !wide(lo) => Some(POS | lo),
-            Some(hi) => match self.0.next() {
-                None => Some(hi << 4 | 4), // add trailing nibble
-                Some(lo) => Some(hi << 4 | lo),
-            },
-        }
+#[cfg(feature = "alloc")]
+impl Decoder {
+    /// Get a new [`Decoder`][]
+    pub fn new() -> Decoder {
+        Default::default()
+    }
+
+    /// Decode a chunk of the compact binary format.
+    ///
+    pub fn feed(&mut self, chunk: &[u8]) -> Result<(), Error> {
+        self.nibbles.feed(&mut self.list, chunk)
+    }
+
+    /// Get the [`LeapSecBuilder`][] containing the decoded list, so
+    /// that you can choose how to finish it.
+    ///
+    pub fn into_builder(mut self) -> Result<LeapSecBuilder, Error> {
+        self.nibbles.finish(&mut self.list)?;
+        Ok(self.list)
+    }
+
+    /// Finish decoding and return the list, as if by
+    /// [`LeapSecBuilder::finish()`][].
+    ///
+    pub fn finish(self) -> Result<LeapSecs, Error> {
+        self.into_builder()?.finish()
     }
 }
 
-impl std::convert::TryFrom<&[u8]> for LeapSecs {
+#[cfg(feature = "alloc")]
+impl core::convert::TryFrom<&[u8]> for LeapSecs {
     type Error = Error;
     fn try_from(slice: &[u8]) -> Result<LeapSecs, Error> {
-        let mut list = LeapSecs::builder();
-        let bytes = slice.iter();
-        let nibbles = Nibbles { inner: bytes, byte: None };
-        for code in Expand(nibbles) {
-            let mul = if code & MONTH != 0 { 1 } else { 6 };
-            let gap = (((code & LOW) + 1) * mul) as i32;
-            let sign = match code & (NEG | POS) {
-                NEG => Leap::Neg,
-                POS => Leap::Pos,
-                0 => Leap::Zero,
-                _ => Leap::Exp,
-            };
-            list.push_gap(gap, sign)?;
+        Ok(decode(slice)?.finish()?.with_literal(Format::Binary))
+    }
+}
+
+#[cfg(feature = "alloc")]
+pub(crate) fn decode(slice: &[u8]) -> Result<LeapSecBuilder, Error> {
+    let mut decoder = Decoder::new();
+    decoder.feed(slice)?;
+    decoder.into_builder()
+}
+
+/// A leap second list that borrows its compact binary format.
+///
+/// The bytes are checked once when the [`LeapSecsRef`][] is created,
+/// with the same consistency checks as a [`LeapSecs`][] list. After
+/// that, iteration and lookups decode the entries on the fly, so
+/// the list is never expanded into memory:
+///
+///     # use leapsecs::*;
+///     let binary: &[u8] = b"\x00\x11\x11\x11\x12\x11\x34\x31\
+///                           \x21\x12\x22\x9D\x56\x52\x7F";
+///     let today = Gregorian(2021, 1, 1).mjd();
+///     let list = bin::LeapSecsRef::try_from_bytes_at(binary, today)?;
+///     assert_eq!(list.dtai_at(Gregorian(2020, 1, 1))?, 37);
+///     assert_eq!(list.iter().count(), 30);
+///     # Ok::<(), Error>(())
+///
+/// Lookups take time proportional to the length of the list, so if
+/// there is memory to spare, a [`LeapSecsFixed`][] is faster.
+///
+/// This does not require the `alloc` feature.
+///
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct LeapSecsRef<'a> {
+    bytes: &'a [u8],
+    expires: MJD,
+}
+
+impl<'a> LeapSecsRef<'a> {
+    // wrap bytes that have already been checked, such as a mapped
+    // archive that is opened once and viewed many times
+    #[cfg(feature = "mmap")]
+    pub(crate) fn from_checked(bytes: &'a [u8], expires: MJD) -> Self {
+        LeapSecsRef { bytes, expires }
+    }
+
+    fn decode(bytes: &'a [u8]) -> Result<Self, Error> {
+        let mut iter = Iter::new(bytes);
+        let mut last = None;
+        while let Some(leap) = iter.step()? {
+            last = Some(leap);
+        }
+        match last {
+            Some(leap) if leap.sign() == Leap::Exp => {
+                Ok(LeapSecsRef { bytes, expires: leap.mjd() })
+            }
+            _ => Err(Error::Truncated),
+        }
+    }
+
+    /// Check a leap second list in compact binary format, and for
+    /// expiry using the given [`Clock`][].
+    ///
+    pub fn try_from_bytes_at<C: Clock>(
+        bytes: &'a [u8],
+        clock: C,
+    ) -> Result<Self, Error> {
+        let list = Self::decode(bytes)?;
+        if list.is_expired_at(clock) {
+            Err(Error::Expired(Gregorian::from(list.expires())))
+        } else {
+            Ok(list)
+        }
+    }
+
+    /// Check a leap second list in compact binary format, without
+    /// checking for expiry.
+    ///
+    pub fn try_from_bytes_lenient(bytes: &'a [u8]) -> Result<Self, Error> {
+        Self::decode(bytes)
+    }
+
+    /// Get the compact binary format of the list
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    /// Find the previous leap second before a particular `date`,
+    /// like [`LeapSecs::before()`][], but returning a copy because
+    /// the entries are not stored anywhere.
+    ///
+    pub fn before(&self, date: Gregorian) -> Option<LeapSec> {
+        self.iter().take_while(|leap| leap.date() <= date).last()
+    }
+
+    /// Get the difference between UTC and TAI on a particular `date`,
+    /// like [`LeapSecsFixed::dtai_at()`][].
+    ///
+    pub fn dtai_at(&self, date: Gregorian) -> Result<i16, Error> {
+        check_covered(date, self.expires())?;
+        // leave out the expiry entry, which is the only one that has
+        // no DTAI, because the list is still valid on its expiry date
+        let entries = self.iter().filter(|leap| leap.dtai().is_ok());
+        match entries.take_while(|leap| leap.date() <= date).last() {
+            Some(leap) => leap.dtai(),
+            None => Err(Error::TooEarly(date)),
+        }
+    }
+
+    /// Get the expiry date of the list.
+    pub fn expires(&self) -> MJD {
+        self.expires
+    }
+
+    /// Returns true if the list has expired according to the given
+    /// [`Clock`][]. The list is still valid on its expiry date.
+    ///
+    pub fn is_expired_at<C: Clock>(&self, clock: C) -> bool {
+        self.expires() < clock.today()
+    }
+
+    /// Get an iterator over the [`LeapSec`][] elements, decoding them
+    /// one at a time.
+    ///
+    pub fn iter(&self) -> Iter<'a> {
+        Iter::new(self.bytes)
+    }
+}
+
+impl<'a> IntoIterator for &LeapSecsRef<'a> {
+    type Item = LeapSec;
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Iter<'a> {
+        self.iter()
+    }
+}
+
+/// An iterator over a [`LeapSecsRef`][], from
+/// [`LeapSecsRef::iter()`][]
+///
+#[derive(Clone, Debug)]
+pub struct Iter<'a> {
+    bytes: &'a [u8],
+    // index of the next nibble
+    pos: usize,
+    nibbles: Nibbles,
+    // the last couple of entries, for the decoder
+    list: Window,
+}
+
+impl<'a> Iter<'a> {
+    fn new(bytes: &'a [u8]) -> Iter<'a> {
+        let list = Window::default();
+        Iter { bytes, pos: 0, nibbles: Nibbles::default(), list }
+    }
+
+    // decode until the next complete entry
+    fn step(&mut self) -> Result<Option<LeapSec>, Error> {
+        let prev = match self.list.last_entry() {
+            Ok(prev) => prev,
+            Err(_) => {
+                self.list.push_start()?;
+                return Ok(Some(LeapSec::start()));
+            }
+        };
+        while self.pos < self.bytes.len() * 2 {
+            // bigendian
+            let shift = if self.pos.is_multiple_of(2) { 4 } else { 0 };
+            let nibble = self.bytes[self.pos / 2] >> shift & LOW;
+            self.pos += 1;
+            self.nibbles.nibble(&mut self.list, nibble)?;
+            let last = self.list.last_entry()?;
+            if last != prev && last.sign() != Leap::Zero {
+                return Ok(Some(last));
+            }
+        }
+        // the trailing nibble, if any, once only
+        core::mem::take(&mut self.nibbles).finish(&mut self.list)?;
+        let last = self.list.last_entry()?;
+        if last != prev && last.sign() != Leap::Zero {
+            Ok(Some(last))
+        } else {
+            Ok(None)
         }
-        list.finish()
+    }
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = LeapSec;
+
+    // the bytes were checked by LeapSecsRef, so there are no errors
+    fn next(&mut self) -> Option<LeapSec> {
+        self.step().ok().flatten()
     }
 }
 
@@ -111,12 +395,14 @@ impl std::convert::TryFrom<&[u8]> for LeapSecs {
 
 // convert list of leap seconds to list of wide bytecodes
 
+#[cfg(feature = "alloc")]
 struct Widecodes<'a> {
-    inner: std::slice::Iter<'a, LeapSec>,
+    inner: core::slice::Iter<'a, LeapSec>,
     flags: u8,
     gap: u16,
 }
 
+#[cfg(feature = "alloc")]
 impl<'a> Iterator for Widecodes<'a> {
     type Item = u8;
     fn next(&mut self) -> Option<u8> {
@@ -133,10 +419,10 @@ impl<'a> Iterator for Widecodes<'a> {
                 return None;
             }
         }
-        if self.gap >= 16 * 6 {
+        if self.gap > 16 * 6 {
             self.gap -= 16 * 6;
             Some(WIDE | 15)
-        } else if self.gap % 6 == 0 {
+        } else if self.gap.is_multiple_of(6) {
             let gap = self.gap as u8 / 6 - 1;
             self.gap = 0;
             Some(self.flags | gap)
@@ -160,8 +446,13 @@ impl<'a> Iterator for Widecodes<'a> {
     }
 }
 
-// squash bytecodes to nibbles where possible
+// squash bytecodes to nibbles where possible, streaming
+//
+// The scan works out where a narrow bytecode needs to be widened to
+// round the list to a whole number of bytes, so that the bytes can
+// be generated one at a time without buffering.
 
+#[cfg(feature = "alloc")]
 struct Bytecodes<'a> {
     inner: Widecodes<'a>,
     prev: Option<u8>,
@@ -169,13 +460,11 @@ struct Bytecodes<'a> {
     widen: usize,
 }
 
+#[cfg(feature = "alloc")]
 impl<'a> Iterator for Bytecodes<'a> {
     type Item = u8;
     fn next(&mut self) -> Option<u8> {
-        let code = match self.inner.next() {
-            Some(code) => code,
-            None => return None,
-        };
+        let code = self.inner.next()?;
         let (this, next) = if code & FLAGS != WIDE | POS
             || wide(code & LOW)
             || self.widen == self.pos + 1
@@ -198,17 +487,129 @@ impl<'a> Iterator for Bytecodes<'a> {
     }
 }
 
+// squash bytecodes to nibbles where possible, in one pass
+//
+// A narrow bytecode might need to be widened to round the list to a
+// whole number of bytes, which is not known until the end. So the
+// last narrow code and the wide codes after it are held back, and
+// released unchanged when another narrow code arrives.
+
+#[cfg(feature = "alloc")]
+#[derive(Debug, Default)]
+struct Packer {
+    // the first half of a byte that has not been output
+    hi: Option<u8>,
+    // wide bytecodes starting with the last narrow one
+    pending: Vec<u8>,
+}
+
+#[cfg(feature = "alloc")]
+impl Packer {
+    fn code(&mut self, code: u8, out: &mut Vec<u8>) {
+        if code & FLAGS == WIDE | POS && !wide(code & LOW) {
+            self.flush(false, out);
+            self.pending.push(code);
+        } else if self.pending.is_empty() {
+            self.nibble(code >> 4, out);
+            self.nibble(code & LOW, out);
+        } else {
+            self.pending.push(code);
+        }
+    }
+
+    fn finish(&mut self, out: &mut Vec<u8>) {
+        let mut len = self.hi.iter().count();
+        for (i, &code) in self.pending.iter().enumerate() {
+            len += if i == 0 { 1 } else { 2 };
+            if code == FLAGS | 4 {
+                len -= 1;
+            }
+        }
+        self.flush(!len.is_multiple_of(2), out);
+        // a leftover nibble is the omitted trailing nibble
+        self.hi = None;
+    }
+
+    // output the pending codes, widening the narrow one if necessary
+
+    fn flush(&mut self, widen: bool, out: &mut Vec<u8>) {
+        for i in 0..self.pending.len() {
+            let code = self.pending[i];
+            if i == 0 && !widen {
+                self.nibble(code & LOW, out);
+            } else {
+                self.nibble(code >> 4, out);
+                self.nibble(code & LOW, out);
+            }
+        }
+        self.pending.clear();
+    }
+
+    fn nibble(&mut self, nibble: u8, out: &mut Vec<u8>) {
+        match self.hi.take() {
+            Some(hi) => out.push(hi << 4 | nibble),
+            None => self.hi = Some(nibble),
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
 impl LeapSecs {
+    /// Parse a leap second list in compact binary format, checking
+    /// for expiry using the given [`Clock`][].
+    ///
+    pub fn try_from_bytes_at<C>(
+        bytes: &[u8],
+        clock: C,
+    ) -> Result<LeapSecs, Error>
+    where
+        C: Clock,
+    {
+        Ok(decode(bytes)?.finish_at(clock)?.with_literal(Format::Binary))
+    }
+
+    /// Read a leap second list in compact binary format from a
+    /// [`std::io::Read`][] object.
+    ///
+    /// This requires the `std` feature.
+    ///
+    #[cfg(feature = "std")]
+    pub fn read_bytes<R>(input: &mut R) -> Result<LeapSecs, Error>
+    where
+        R: std::io::Read,
+    {
+        let mut decoder = Decoder::new();
+        let mut buffer = [0; 256];
+        loop {
+            match input.read(&mut buffer) {
+                Ok(0) => {
+                    let list = decoder.finish()?;
+                    return Ok(list.with_literal(Format::Binary));
+                }
+                Ok(len) => decoder.feed(&buffer[..len])?,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => (),
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+
+    /// Parse a leap second list in compact binary format, without
+    /// failing if it has expired. See [`LeapSecs::was_expired()`][].
+    ///
+    pub fn try_from_bytes_lenient(bytes: &[u8]) -> Result<LeapSecs, Error> {
+        Ok(decode(bytes)?.finish_lenient()?.with_literal(Format::Binary))
+    }
+
     fn widecodes(&self) -> Widecodes<'_> {
         Widecodes { inner: self.iter(), flags: 0, gap: 0 }
     }
 
-    // work out how to round bytecodes to whole number of bytes
+    // count the nibbles, rounded up to a whole number of bytes, and
+    // find the narrow bytecode to widen if the count is odd
 
     fn scan_bytes(&self) -> (usize, usize) {
-        let mut len = 0;
+        let mut len: usize = 0;
         let mut widen = 0;
-
         for code in self.widecodes() {
             if code == FLAGS | 4 {
                 // omit trailing nibble
@@ -220,22 +621,49 @@ impl LeapSecs {
                 widen = len;
             }
         }
-
-        if len % 2 == 0 {
+        if len.is_multiple_of(2) {
             (len / 2, 0)
         } else {
             (len / 2 + 1, widen)
         }
     }
 
+    /// Generate the canonical compact binary format, which is what
+    /// all the encoders here produce. See [`is_canonical()`][].
+    ///
+    /// This encodes the list in one pass, which is faster than
+    /// collecting [`LeapSecs::iter_bytes()`][].
+    ///
+    pub fn canonical_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(self.len() + 1);
+        let mut packer = Packer::default();
+        for code in self.widecodes() {
+            packer.code(code, &mut out);
+        }
+        packer.finish(&mut out);
+        out
+    }
+
     /// Get the length of the compact binary format in bytes.
     ///
+    /// This counts the nibbles without generating the bytes, so a
+    /// caller can reserve buffer space, or choose between inline and
+    /// referenced encodings, before doing the work. See also
+    /// [`LeapSecs::gap_histogram()`][].
+    ///
     pub fn len_bytes(&self) -> usize {
         self.scan_bytes().0
     }
 
+    /// Get the exact size of the compact binary format in bytes,
+    /// without encoding it, the same as [`LeapSecs::len_bytes()`][].
+    ///
+    pub fn binary_len(&self) -> usize {
+        self.len_bytes()
+    }
+
     /// Generate the compact binary format one byte at a time as an
-    /// iterator.
+    /// iterator, without allocating.
     ///
     pub fn iter_bytes(&self) -> impl Iterator<Item = u8> + '_ {
         let widen = self.scan_bytes().1;
@@ -243,44 +671,446 @@ impl LeapSecs {
     }
 
     /// Output the compact binary format to a [`std::io::Write`][]
-    /// object, one byte at a time.
+    /// object.
     ///
+    #[cfg(feature = "std")]
     pub fn write_bytes<W>(&self, out: &mut W) -> std::io::Result<()>
     where
         W: std::io::Write,
     {
-        for byte in self.iter_bytes() {
-            out.write_all(&[byte])?;
+        out.write_all(&self.canonical_bytes())
+    }
+}
+
+/// A streaming encoder for the compact binary format.
+///
+/// Entries are pushed as with a [`LeapSecBuilder`][], and with the
+/// same checks, but the bytes are written to a [`std::io::Write`][]
+/// object as soon as they are known, without keeping the whole list.
+/// This is for proxies that convert from another format on the fly:
+///
+///     # use leapsecs::*;
+///     let mut writer = bin::BinWriter::new(Vec::new());
+///     writer.push_date(Gregorian(1972, 1, 1), 10)?;
+///     writer.push_date(Gregorian(1972, 7, 1), 11)?;
+///     writer.push_exp(Gregorian(1972, 12, 28))?;
+///     assert_eq!(writer.finish()?, b"\x0F");
+///     # Ok::<(), Error>(())
+///
+/// Call [`BinWriter::finish()`][] after the expiry date to flush the
+/// last few bytes. Output is delayed after each nibble-sized code
+/// until the next one arrives, in case it needs to be widened to
+/// round the output to a whole number of bytes.
+///
+/// This requires the `std` feature.
+///
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct BinWriter<W> {
+    out: W,
+    list: Window,
+    packer: Packer,
+    // bytes that are ready to write
+    ready: Vec<u8>,
+}
+
+#[cfg(feature = "std")]
+impl<W: std::io::Write> BinWriter<W> {
+    /// Get a new [`BinWriter`][] that writes to `out`
+    pub fn new(out: W) -> BinWriter<W> {
+        BinWriter {
+            out,
+            list: Window::default(),
+            packer: Packer::default(),
+            ready: vec![],
+        }
+    }
+
+    /// Add an entry to the list, like [`LeapSecBuilder::push_gap()`][]
+    ///
+    pub fn push_gap(&mut self, gap: i32, sign: Leap) -> Result<(), Error> {
+        self.list.push_gap(gap, sign)?;
+        self.encode()
+    }
+
+    /// Add an entry to the list, like [`LeapSecBuilder::push_date()`][]
+    ///
+    pub fn push_date(
+        &mut self,
+        date: Gregorian,
+        dtai: i16,
+    ) -> Result<(), Error> {
+        self.list.push_date(date, dtai)?;
+        self.encode()
+    }
+
+    /// Add the expiry date, like [`LeapSecBuilder::push_exp()`][]
+    ///
+    pub fn push_exp(&mut self, date: Gregorian) -> Result<(), Error> {
+        self.list.push_exp(date)?;
+        self.encode()
+    }
+
+    /// Write the rest of the list and return the output object.
+    ///
+    /// Fails with [`Error::Truncated`][] if the list does not end
+    /// with an expiry date.
+    ///
+    pub fn finish(mut self) -> Result<W, Error> {
+        self.list.check_finished()?;
+        self.ready.clear();
+        self.packer.finish(&mut self.ready);
+        self.out.write_all(&self.ready)?;
+        Ok(self.out)
+    }
+
+    fn encode(&mut self) -> Result<(), Error> {
+        let leap = match self.list.finished() {
+            Some(leap) => leap,
+            None => return Ok(()),
+        };
+        let single = core::slice::from_ref(&leap);
+        let codes = Widecodes { inner: single.iter(), flags: 0, gap: 0 };
+        self.ready.clear();
+        for code in codes {
+            self.packer.code(code, &mut self.ready);
         }
+        self.out.write_all(&self.ready)?;
         Ok(())
     }
 }
 
+#[cfg(feature = "alloc")]
 impl From<&LeapSecs> for Vec<u8> {
     fn from(list: &LeapSecs) -> Vec<u8> {
-        let mut bytes = Vec::new();
-        list.write_bytes(&mut bytes).unwrap();
-        bytes
+        list.canonical_bytes()
     }
 }
 
+/// Get the length of the compact binary format of a list in bytes,
+/// without encoding it, the same as [`LeapSecs::len_bytes()`][].
+///
+/// See also [`LeapSecs::gap_histogram()`][].
+///
+#[cfg(feature = "alloc")]
+pub fn encoded_len(list: &LeapSecs) -> usize {
+    list.len_bytes()
+}
+
+/// Returns true if `bytes` is a leap second list in the canonical
+/// compact binary format.
+///
+/// The decoder accepts many encodings of the same list, so the
+/// encoders stick to one of them, so that two lists can be compared
+/// byte-for-byte. In the canonical encoding,
+///
+///   * each gap uses as few bytecodes as possible, preferring gaps
+///     that are a multiple of 6 months;
+///
+///   * nibble-sized bytecodes are used wherever possible, except
+///     that the last one is widened if that is needed to make the
+///     list a whole number of bytes;
+///
+///   * a trailing nibble `4` is omitted.
+///
+/// This does not check whether the list has expired.
+///
+#[cfg(feature = "alloc")]
+pub fn is_canonical(bytes: &[u8]) -> bool {
+    match LeapSecs::try_from_bytes_lenient(bytes) {
+        Ok(list) => list.iter_bytes().eq(bytes.iter().copied()),
+        Err(_) => false,
+    }
+}
+
+/// The version of the compact binary format written by
+/// [`encode_v2()`][].
+///
+pub const VERSION: u8 = 2;
+
+// a version marker is an expiry bytecode with the version in place
+// of the gap, which an old decoder rejects when more data follows
+const MARKER: u8 = WIDE | MONTH | NEG | POS;
+
+/// Get the version of some bytes in the compact binary format.
+///
+/// Version 1 is the original format, which has no marker. Later
+/// versions start with a marker byte `0xF0` plus the version number.
+/// To an old decoder the marker looks like a list that expired in
+/// 1972, so it fails instead of misreading the rest of the data.
+///
+pub fn version(bytes: &[u8]) -> u8 {
+    match bytes {
+        [marker, _, ..] if marker & FLAGS == MARKER => marker & LOW,
+        _ => 1,
+    }
+}
+
+/// Generate version 2 of the compact binary format, which is the
+/// version marker followed by the same data as version 1.
+///
+#[cfg(feature = "alloc")]
+pub fn encode_v2(list: &LeapSecs) -> Vec<u8> {
+    let mut out = Vec::with_capacity(list.len_bytes() + 1);
+    out.push(MARKER | VERSION);
+    out.extend(list.canonical_bytes());
+    out
+}
+
+/// Decode any supported version of the compact binary format, and
+/// return the [`LeapSecBuilder`][] so that you can choose how to
+/// finish it.
+///
+/// Fails with [`Error::BinVersion`][] if the bytes start with a
+/// version marker that this decoder does not know. For example,
+///
+///     # use leapsecs::*;
+///     # use std::convert::TryFrom;
+///     # use std::str::FromStr;
+///     let list = LeapSecs::from_str("6+6+12+999?")?;
+///     let v1 = Vec::from(&list);
+///     let v2 = bin::encode_v2(&list);
+///     assert_eq!((bin::version(&v1), bin::version(&v2)), (1, 2));
+///     assert_eq!(bin::decode_any(&v1)?.finish()?, list);
+///     assert_eq!(bin::decode_any(&v2)?.finish()?, list);
+///     assert!(LeapSecs::try_from(&v2[..]).is_err());
+///     let v3 = [&[0xF3][..], &v1].concat();
+///     assert!(matches!(bin::decode_any(&v3), Err(Error::BinVersion(3))));
+///     # Ok::<(), Error>(())
+///
+#[cfg(feature = "alloc")]
+pub fn decode_any(bytes: &[u8]) -> Result<LeapSecBuilder, Error> {
+    match bytes {
+        [_, rest @ ..] if version(bytes) == VERSION => decode(rest),
+        [marker, _, ..] if marker & FLAGS == MARKER => {
+            Err(Error::BinVersion(marker & LOW))
+        }
+        _ => decode(bytes),
+    }
+}
+
+#[cfg(feature = "alloc")]
 impl From<LeapSecs> for Vec<u8> {
     fn from(list: LeapSecs) -> Vec<u8> {
         Vec::<u8>::from(&list)
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "std"))]
 mod test {
     use crate::*;
-    use std::convert::TryFrom;
+    use std::str::FromStr;
 
     #[test]
     fn test() {
         let binary: &[u8] = b"\x00\x11\x11\x11\x12\x11\x34\x31\
                               \x21\x12\x22\x9D\x56\x52\x7F";
-        let parsed = LeapSecs::try_from(binary).unwrap();
+        let today = Gregorian(2021, 1, 1).mjd();
+        let parsed = LeapSecs::try_from_bytes_at(binary, today).unwrap();
+        let mut decoder = bin::Decoder::new();
+        for chunk in binary.chunks(1) {
+            decoder.feed(chunk).unwrap();
+        }
+        let builder = decoder.into_builder().unwrap();
+        assert_eq!(parsed, builder.finish_at(today).unwrap());
         let written: Vec<u8> = parsed.into();
         assert_eq!(binary, written);
+        assert!(bin::is_canonical(binary));
+        assert!(bin::is_canonical(b"\x0F"));
+        assert!(!bin::is_canonical(b"\x90\xF4"));
+        let wide = LeapSecs::try_from_bytes_lenient(b"\x90\xF4").unwrap();
+        assert_eq!(b"\x0F", &wide.canonical_bytes()[..]);
+        assert!(!bin::is_canonical(&binary[..binary.len() - 1]));
+
+        let list = LeapSecs::from_str("9+9-99+99-999+999?").unwrap();
+        let mut bytes: &[u8] = &Vec::<u8>::from(&list);
+        assert_eq!(list, LeapSecs::read_bytes(&mut bytes).unwrap());
+    }
+
+    // the properties checked by the fuzz target, so that they are
+    // also checked by `cargo test`
+    #[test]
+    fn malformed() {
+        let expected = |err: &Error| {
+            matches!(
+                err,
+                Error::BinVersion(_)
+                    | Error::Empty
+                    | Error::Expired(_)
+                    | Error::FromInt(_)
+                    | Error::Full(_)
+                    | Error::Gap(..)
+                    | Error::Truncated
+            )
+        };
+        let mut inputs = vec![vec![0xD0; 40000], vec![0xE0; 40000]];
+        let mut x: u64 = 0x9E37_79B9_7F4A_7C15;
+        for n in 0..20_000 {
+            inputs.push(
+                (0..n % 40)
+                    .map(|_| {
+                        x ^= x << 13;
+                        x ^= x >> 7;
+                        x ^= x << 17;
+                        x as u8
+                    })
+                    .collect(),
+            );
+        }
+        for data in &inputs {
+            let data = &data[..];
+            let results = [
+                LeapSecs::try_from_bytes_lenient(data).map(drop),
+                bin::decode_any(data)
+                    .and_then(|b| b.finish_lenient())
+                    .map(drop),
+                bin::LeapSecsRef::try_from_bytes_lenient(data).map(drop),
+                LeapSecsFixed::<64>::try_from_bytes_lenient(data).map(drop),
+            ];
+            for err in results.iter().filter_map(|r| r.as_ref().err()) {
+                assert!(expected(err), "{:?} {}", data, err);
+            }
+            let mut decoder = bin::Decoder::new();
+            let chunked = data
+                .chunks(3)
+                .try_for_each(|chunk| decoder.feed(chunk))
+                .and_then(|()| decoder.into_builder()?.finish_lenient());
+            let parsed = match LeapSecs::try_from_bytes_lenient(data) {
+                Ok(parsed) => parsed,
+                Err(_) => {
+                    assert!(chunked.is_err());
+                    continue;
+                }
+            };
+            assert_eq!(parsed, chunked.unwrap());
+            let canonical = parsed.canonical_bytes();
+            assert!(parsed.iter_bytes().eq(canonical.iter().copied()));
+            assert!(bin::is_canonical(&canonical), "{:?}", data);
+            assert_eq!(bin::is_canonical(data), data == &canonical[..]);
+            let text = LeapSecs::from_str_lenient(&parsed.to_string());
+            assert_eq!(canonical, text.unwrap().canonical_bytes());
+        }
+        let overflow = LeapSecs::try_from_bytes_lenient(&inputs[0]);
+        assert!(matches!(overflow, Err(Error::FromInt(_))));
+    }
+
+    #[test]
+    fn borrowed() {
+        for text in
+            &[history!("999?"), "9+9-99+99-999+999?", "6+5?", "6+6+5?", "6-7?"]
+        {
+            let list = LeapSecs::from_str_lenient(text).unwrap();
+            let bytes = Vec::<u8>::from(&list);
+            assert_eq!(bytes.len(), list.len_bytes(), "{}", text);
+            assert_eq!(bytes.len(), bin::encoded_len(&list), "{}", text);
+            let view = bin::LeapSecsRef::try_from_bytes_lenient(&bytes);
+            let view = view.unwrap();
+            assert!(list.iter().copied().eq(view.iter()), "{}", text);
+            assert_eq!(list.expires(), view.expires());
+            for leap in &list {
+                let date = leap.date();
+                let day_before = Gregorian::from(leap.mjd() - 1);
+                assert_eq!(list.before(date).copied(), view.before(date));
+                assert_eq!(
+                    list.before(day_before).copied(),
+                    view.before(day_before)
+                );
+            }
+            // still valid on the expiry date
+            for &delta in &[-1, 0, 1] {
+                let date = Gregorian::from(list.expires() + delta);
+                let want = list.dtai_at(date).ok();
+                assert_eq!(want, view.dtai_at(date).ok(), "{} {}", text, date);
+                assert_eq!(want.is_none(), delta > 0, "{} {}", text, date);
+            }
+            assert!(matches!(
+                bin::LeapSecsRef::try_from_bytes_lenient(
+                    &bytes[..bytes.len() - 1]
+                ),
+                Err(Error::Truncated)
+            ));
+        }
+        let view = bin::LeapSecsRef::try_from_bytes_lenient(b"\x90\xF4");
+        assert_eq!(view.unwrap().iter().count(), 3);
+        let today = Gregorian(2021, 1, 1).mjd();
+        let old = bin::LeapSecsRef::try_from_bytes_at(b"\x0F", today);
+        assert!(matches!(old, Err(Error::Expired(_))));
+        let bad = bin::LeapSecsRef::try_from_bytes_lenient(b"\x0F\x11");
+        assert!(bad.is_err());
+        assert!(matches!(
+            bin::LeapSecsRef::try_from_bytes_lenient(b""),
+            Err(Error::Truncated)
+        ));
+    }
+
+    // iter_bytes() and canonical_bytes() are separate encoders, the
+    // streaming one so that it needs no allocation, and the one-pass
+    // one because it is faster, so they must agree byte for byte
+    #[test]
+    fn encoders() {
+        let mut lists = vec![];
+        for text in &[
+            history!("999?"),
+            "9+9-99+99-999+999?",
+            "6+5?",
+            "6+6+5?",
+            "6+12+7?",
+            "6-7?",
+            "6+6+12+999?",
+        ] {
+            lists.push(LeapSecs::from_str_lenient(text).unwrap());
+        }
+        lists.push(LeapSecs::try_from_bytes_lenient(b"\x90\xF4").unwrap());
+        #[cfg(feature = "builtin")]
+        lists.push(builtin());
+        for list in &lists {
+            let streamed: Vec<u8> = list.iter_bytes().collect();
+            assert_eq!(streamed, list.canonical_bytes(), "{}", list);
+            assert_eq!(streamed.len(), list.len_bytes(), "{}", list);
+        }
+    }
+
+    #[test]
+    fn writer() {
+        for text in &[
+            history!("999?"),
+            "9+9-99+99-999+999?",
+            "6+5?",
+            "6+6+5?",
+            "6+12+7?",
+            "6-7?",
+        ] {
+            let list = LeapSecs::from_str_lenient(text).unwrap();
+            let mut writer = bin::BinWriter::new(Vec::new());
+            for leap in list.iter().skip(1) {
+                writer.push_gap(leap.gap() as i32, leap.sign()).unwrap();
+            }
+            let bytes = Vec::<u8>::from(&list);
+            assert_eq!(bytes, writer.finish().unwrap());
+            assert!(list.iter_bytes().eq(bytes.iter().copied()));
+            assert_eq!(bytes.len(), list.len_bytes());
+        }
+        let mut writer = bin::BinWriter::new(Vec::new());
+        writer.push_gap(6, Leap::Pos).unwrap();
+        assert!(matches!(writer.finish(), Err(Error::Truncated)));
+    }
+
+    #[test]
+    fn versions() {
+        let list = LeapSecs::from_str("6+6+12+999?").unwrap();
+        let v2 = bin::encode_v2(&list);
+        assert_eq!(0xF2, v2[0]);
+        assert_eq!(&v2[1..], &list.canonical_bytes()[..]);
+        let mut decoder = bin::Decoder::new();
+        assert!(matches!(decoder.feed(&v2), Err(Error::Expired(_))));
+        // a single marker-like byte is a (very old) version 1 list
+        assert_eq!(1, bin::version(&[0xF2]));
+        let old = bin::decode_any(&[0xF2]).unwrap().finish_lenient();
+        assert_eq!("3?", old.unwrap().to_string());
+        assert_eq!(1, bin::version(&[]));
+        let v0 = [&[0xF0][..], &v2[1..]].concat();
+        assert!(matches!(bin::decode_any(&v0), Err(Error::BinVersion(0))));
+        let v1 = [&[0xF1][..], &v2[1..]].concat();
+        assert!(matches!(bin::decode_any(&v1), Err(Error::BinVersion(1))));
     }
 }
@@ -0,0 +1,58 @@
+//! A leap second list compiled into the crate
+//! ==========================================
+//!
+//! The list is stored in compact binary format in `src/builtin.bin`.
+//! To regenerate it from the NIST `leap-seconds.list`, run
+//! `make builtin` and rebuild.
+//!
+//! **The built-in list goes stale by design.** Each list expires
+//! about six months after the IERS publishes it, and a copy that is
+//! compiled into the crate is only as fresh as the release it came
+//! from. The current copy is the IERS list of January 2026, which
+//! expires on 2026-12-28. `make it` runs an ignored test that fails
+//! when the copy is within a month of expiry, as a reminder to
+//! regenerate it before a release.
+
+use crate::*;
+
+static BUILTIN: &[u8] = include_bytes!("builtin.bin");
+
+/// Get the leap second list that was compiled into the crate.
+///
+/// This is intended as a fallback for when there is no network
+/// and no cached copy of the list.
+///
+/// The list does not fail if it has expired, because it will
+/// inevitably go stale before the crate is rebuilt. You should check
+/// [`LeapSecs::was_expired()`][] to find out if it is still usable.
+///
+/// Requires the `builtin` feature.
+///
+pub fn builtin() -> LeapSecs {
+    // panic if the build was broken by a bad `make builtin`
+    bin::decode(BUILTIN).unwrap().finish_lenient().unwrap()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+
+    #[test]
+    fn test() {
+        let list = builtin();
+        assert_eq!(super::BUILTIN, &Vec::<u8>::from(&list)[..]);
+        assert_eq!(Some(10), list[0].dtai().ok());
+    }
+
+    // the list needs regenerating, not the code fixing, so this only
+    // runs when asked for, e.g. by `make it`
+    #[cfg(feature = "std")]
+    #[test]
+    #[ignore]
+    fn fresh() {
+        let list = builtin();
+        let expires = Gregorian::from(list.expires());
+        let days = nist::NTPD_WARNING_DAYS;
+        assert!(!list.expires_within(days), "run make builtin: {}", expires);
+    }
+}
@@ -0,0 +1,293 @@
+//! IERS Bulletin C announcements
+//! =============================
+//!
+//! Leap seconds are announced twice a year by the IERS in
+//! [Bulletin C](https://hpiers.obspm.fr/iers/bul/bulc/bulletinc.dat),
+//! which says whether or not there will be a leap second at the end
+//! of the following June or December. Each bulletin extends the
+//! validity of the leap second list by six months.
+//!
+//! An [`Announcement`][] is the content of one bulletin, which is the
+//! unit of change from one version of the list to the next. It can be
+//! parsed from the text of a bulletin and applied to a list, for
+//! example,
+//!
+//!     # use leapsecs::*;
+//!     let text = "NO leap second will be introduced at the end of June 2021.";
+//!     let news = bulletin::parse(text)?;
+//!     assert_eq!(news.to_string(),
+//!                "no leap second before 2021-07-01, expires 2021-12-28");
+//!     let list = LeapSecs::from_str_lenient("6+6+12+12+12+12+12+12+12+\
+//!         18+12+12+24+30+24+12+18+12+12+18+18+18+84+36+42+36+18+53?")?;
+//!     let list = news.apply(&list)?;
+//!     assert!(list.to_string().ends_with("+18+59?"));
+//!     # Ok::<(), Error>(())
+//!
+//! With the `json` feature, an [`Announcement`][] can be serialized
+//! with the same conventions as the [`json`][crate::json] module:
+//!
+//! ```json
+//! { "effective": "2017-01-01", "sign": 1, "new_expiry": "2017-06-28" }
+//! ```
+//!
+//! The `sign` is 0 when there is no leap second.
+
+use crate::*;
+
+/// The content of an IERS Bulletin C.
+///
+/// It is displayed like,
+///
+/// ```text
+/// positive leap second before 2017-01-01, expires 2017-06-28
+/// ```
+///
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "json", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "json", serde(try_from = "Json", into = "Json"))]
+pub struct Announcement {
+    /// The first day after the announced leap second, or when the
+    /// leap second would have been if there is none
+    pub effective: Gregorian,
+    /// [`Leap::Pos`][] or [`Leap::Neg`][], or [`None`][] if there
+    /// is no leap second
+    pub sign: Option<Leap>,
+    /// The expiry date of the list after the announcement
+    pub new_expiry: Gregorian,
+}
+
+impl Announcement {
+    /// Make an announcement about the leap second opportunity at the
+    /// end of the month before `effective`, with the usual expiry
+    /// date, 28 days before the next opportunity six months later.
+    ///
+    pub fn new(effective: Gregorian, sign: Option<Leap>) -> Announcement {
+        let new_expiry = Gregorian(effective.0, effective.1, 28);
+        let new_expiry = new_expiry.add_months(5);
+        Announcement { effective, sign, new_expiry }
+    }
+
+    /// Apply the announcement to a list, returning the updated list.
+    ///
+    /// Fails with [`Error::Bulletin`][] if the announcement is not
+    /// news, because the list already covers the `effective` date,
+    /// or if the `sign` is not [`Leap::Pos`][], [`Leap::Neg`][], or
+    /// [`None`][]. The new list is checked as if by
+    /// [`LeapSecBuilder::finish_lenient()`][], and keeps the old
+    /// list's [`LeapSecs::updated()`][], [`LeapSecs::refresh()`][]
+    /// and [`LeapSecs::provenance()`][].
+    ///
+    pub fn apply(&self, list: &LeapSecs) -> Result<LeapSecs> {
+        if self.effective <= Gregorian::from(list.expires()) {
+            return Err(Error::Bulletin("effective date is already covered"));
+        }
+        let mut builder = LeapSecs::builder();
+        let mut dtai = 10;
+        for leap in list.iter().filter(|leap| leap.sign() != Leap::Exp) {
+            dtai = leap.dtai()?;
+            builder.push_date(leap.date(), dtai)?;
+        }
+        match self.sign {
+            None => (),
+            Some(Leap::Pos) => builder.push_date(self.effective, dtai + 1)?,
+            Some(Leap::Neg) => builder.push_date(self.effective, dtai - 1)?,
+            Some(_) => return Err(Error::Bulletin("sign must be + or -")),
+        }
+        builder.push_exp(self.new_expiry)?;
+        Ok(builder.finish_lenient()?.with_metadata_of(list))
+    }
+}
+
+impl core::fmt::Display for Announcement {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let leap = match self.sign {
+            Some(Leap::Pos) => "positive leap second",
+            Some(Leap::Neg) => "negative leap second",
+            _ => "no leap second",
+        };
+        write!(f, "{} before {}, ", leap, self.effective)?;
+        write!(f, "expires {}", self.new_expiry)
+    }
+}
+
+const MONTHS: [&str; 12] = [
+    "january",
+    "february",
+    "march",
+    "april",
+    "may",
+    "june",
+    "july",
+    "august",
+    "september",
+    "october",
+    "november",
+    "december",
+];
+
+/// Parse the text of an IERS Bulletin C.
+///
+/// This looks for the sentence that says whether a leap second will
+/// be introduced at the end of a month, and ignores the rest. The
+/// sentence can say "NO leap second will be introduced", as in the
+/// IERS bulletins, or "a leap second will not be introduced". The
+/// expiry date is worked out as by [`Announcement::new()`][].
+///
+/// Fails with [`Error::Bulletin`][] if the sentence is missing or
+/// garbled.
+///
+pub fn parse(text: &str) -> Result<Announcement> {
+    const AT_END: &str = "leap second will be introduced at the end of";
+    const NOT_AT_END: &str = "leap second will not be introduced at the end of";
+    let (before, after, not) = text
+        .lines()
+        .find_map(|line| match line.split_once(AT_END) {
+            Some((before, after)) => Some((before, after, false)),
+            None => {
+                let (before, after) = line.split_once(NOT_AT_END)?;
+                Some((before, after, true))
+            }
+        })
+        .ok_or(Error::Bulletin("announcement not found"))?;
+    let sign = match (before.trim().to_ascii_lowercase().as_str(), not) {
+        ("no", false) | ("a", true) => None,
+        ("a positive", false) => Some(Leap::Pos),
+        ("a negative", false) => Some(Leap::Neg),
+        _ => return Err(Error::Bulletin("unknown kind of leap second")),
+    };
+    let mut words = after.split_whitespace();
+    let month = words
+        .next()
+        .and_then(|month| {
+            let month = month.to_ascii_lowercase();
+            MONTHS.iter().position(|&name| name == month)
+        })
+        .ok_or(Error::Bulletin("month not found"))?;
+    let year = words
+        .next()
+        .and_then(|year| year.trim_end_matches('.').parse().ok())
+        .ok_or(Error::Bulletin("year not found"))?;
+    let effective = Gregorian(year, month as i32 + 1, 1).add_months(1);
+    Ok(Announcement::new(effective, sign))
+}
+
+// the JSON representation, like json::Entry
+
+#[cfg(feature = "json")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Json {
+    effective: alloc::string::String,
+    sign: i8,
+    new_expiry: alloc::string::String,
+}
+
+#[cfg(feature = "json")]
+impl From<Announcement> for Json {
+    fn from(news: Announcement) -> Json {
+        use alloc::string::ToString;
+        let sign = match news.sign {
+            Some(Leap::Pos) => 1,
+            Some(Leap::Neg) => -1,
+            _ => 0,
+        };
+        Json {
+            effective: news.effective.to_string(),
+            sign,
+            new_expiry: news.new_expiry.to_string(),
+        }
+    }
+}
+
+#[cfg(feature = "json")]
+impl core::convert::TryFrom<Json> for Announcement {
+    type Error = Error;
+
+    fn try_from(json: Json) -> Result<Announcement> {
+        let sign = match json.sign {
+            1 => Some(Leap::Pos),
+            -1 => Some(Leap::Neg),
+            0 => None,
+            _ => return Err(Error::Bulletin("sign must be 1, 0, or -1")),
+        };
+        Ok(Announcement {
+            effective: json::parse_date(&json.effective)?,
+            sign,
+            new_expiry: json::parse_date(&json.new_expiry)?,
+        })
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod test {
+    use crate::*;
+
+    #[test]
+    fn test() {
+        let text = "
+ INFORMATION ON UTC - TAI
+
+ A positive leap second will be introduced at the end of December 2016.
+ The sequence of dates of the UTC second markers will be:
+
+                          2016 December 31,     23h 59m 59s
+                          2016 December 31,     23h 59m 60s
+                          2017 January   1,      0h  0m  0s
+";
+        let news = bulletin::parse(text).unwrap();
+        let want = Announcement {
+            effective: Gregorian(2017, 1, 1),
+            sign: Some(Leap::Pos),
+            new_expiry: Gregorian(2017, 6, 28),
+        };
+        assert_eq!(want, news);
+        let old = "6+6+12+12+12+12+12+12+12+18+12+12+24+30+24+\
+                   12+18+12+12+18+18+18+84+36+42+36+11?";
+        let old = LeapSecs::from_str_lenient(old).unwrap();
+        let updated = Gregorian(2016, 7, 1).mjd();
+        let old = old.with_updated(updated).with_refresh("make update");
+        let new = news.apply(&old).unwrap();
+        assert!(new.to_string().ends_with("+36+18+5?"));
+        assert_eq!(Some(updated), new.updated());
+        assert_eq!(Some("make update"), new.refresh());
+        assert_eq!(old.provenance(), new.provenance());
+        assert!(matches!(news.apply(&new), Err(Error::Bulletin(_))));
+
+        let text = "A negative leap second will be introduced \
+                    at the end of JUNE 2030.";
+        let news = bulletin::parse(text);
+        assert_eq!(
+            "negative leap second before 2030-07-01, expires 2030-12-28",
+            news.unwrap().to_string()
+        );
+        let text = "A leap second will not be introduced \
+                    at the end of December 2030.";
+        let news = bulletin::parse(text).unwrap();
+        assert_eq!(Announcement::new(Gregorian(2031, 1, 1), None), news);
+        let new = old.apply_bulletin(&news).unwrap();
+        assert_eq!(Gregorian(2031, 6, 28).mjd(), new.expires());
+        for bad in &[
+            "",
+            "A positive leap second will not be introduced \
+             at the end of June 2021",
+            "Maybe a leap second will be introduced at the end of June 2021",
+            "NO leap second will be introduced at the end of Jun 2021",
+            "NO leap second will be introduced at the end of June",
+        ] {
+            let err = bulletin::parse(bad);
+            assert!(matches!(err, Err(Error::Bulletin(_))), "{}", bad);
+        }
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn json() {
+        let news = Announcement::new(Gregorian(2017, 1, 1), Some(Leap::Pos));
+        let json = serde_json::to_string(&news).unwrap();
+        let want =
+            r#"{"effective":"2017-01-01","sign":1,"new_expiry":"2017-06-28"}"#;
+        assert_eq!(want, json);
+        assert_eq!(news, serde_json::from_str(&json).unwrap());
+        let bad = json.replace(r#""sign":1"#, r#""sign":2"#);
+        assert!(serde_json::from_str::<Announcement>(&bad).is_err());
+    }
+}
@@ -0,0 +1,180 @@
+//! Signed leap second lists
+//! ========================
+//!
+//! This module wraps the compact binary format in a COSE_Sign1
+//! envelope (RFC 9052) using Ed25519 signatures, so that the list can
+//! be distributed securely to devices that only need to know a public
+//! key. The checksum in the NIST `leap-seconds.list` format provides
+//! integrity but not authenticity, and it uses SHA-1.
+//!
+//! The envelope is the smallest that COSE allows: the protected
+//! header contains only the algorithm, the unprotected header is
+//! empty, and the payload is the compact binary list.
+//!
+//! Requires the `signed` feature.
+
+use ring::signature::{Ed25519KeyPair, UnparsedPublicKey, ED25519};
+use std::convert::TryFrom;
+
+use crate::*;
+
+// CBOR tag for COSE_Sign1
+const TAG: u8 = 18;
+
+// CBOR major types
+const BSTR: u8 = 2;
+const TSTR: u8 = 3;
+const ARRAY: u8 = 4;
+const MAP: u8 = 5;
+const TAGGED: u8 = 6;
+
+// protected header {1: -8}, i.e. {alg: EdDSA}
+const PROTECTED: &[u8] = &[0xA1, 0x01, 0x27];
+
+fn head(out: &mut Vec<u8>, major: u8, len: usize) {
+    let major = major << 5;
+    if len < 24 {
+        out.push(major | len as u8);
+    } else if len < 0x100 {
+        out.extend_from_slice(&[major | 24, len as u8]);
+    } else {
+        // lists are much smaller than 64 KiB
+        out.push(major | 25);
+        out.extend_from_slice(&(len as u16).to_be_bytes());
+    }
+}
+
+fn bytes(out: &mut Vec<u8>, major: u8, data: &[u8]) {
+    head(out, major, data.len());
+    out.extend_from_slice(data);
+}
+
+// the data that is signed
+fn sig_structure(payload: &[u8]) -> Vec<u8> {
+    let mut tbs = Vec::new();
+    head(&mut tbs, ARRAY, 4);
+    bytes(&mut tbs, TSTR, b"Signature1");
+    bytes(&mut tbs, BSTR, PROTECTED);
+    bytes(&mut tbs, BSTR, b"");
+    bytes(&mut tbs, BSTR, payload);
+    tbs
+}
+
+/// Sign a leap second list, returning a tagged COSE_Sign1 object.
+///
+pub fn sign(list: &LeapSecs, key: &Ed25519KeyPair) -> Vec<u8> {
+    let payload = Vec::<u8>::from(list);
+    let signature = key.sign(&sig_structure(&payload));
+    let mut out = Vec::new();
+    head(&mut out, TAGGED, TAG as usize);
+    head(&mut out, ARRAY, 4);
+    bytes(&mut out, BSTR, PROTECTED);
+    head(&mut out, MAP, 0);
+    bytes(&mut out, BSTR, &payload);
+    bytes(&mut out, BSTR, signature.as_ref());
+    out
+}
+
+// a very small CBOR reader that only understands what we need
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn byte(&mut self) -> Result<u8> {
+        let (&byte, rest) = self.0.split_first().ok_or(Error::Cose("short"))?;
+        self.0 = rest;
+        Ok(byte)
+    }
+
+    fn head(&mut self, major: u8) -> Result<usize> {
+        let byte = self.byte()?;
+        if byte >> 5 != major {
+            return Err(Error::Cose("unexpected type"));
+        }
+        match byte & 31 {
+            len @ 0..=23 => Ok(len as usize),
+            24 => Ok(self.byte()? as usize),
+            25 => Ok((self.byte()? as usize) << 8 | self.byte()? as usize),
+            _ => Err(Error::Cose("unsupported length")),
+        }
+    }
+
+    fn bytes(&mut self) -> Result<&'a [u8]> {
+        let len = self.head(BSTR)?;
+        if len > self.0.len() {
+            return Err(Error::Cose("short"));
+        }
+        let (data, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(data)
+    }
+}
+
+/// Verify a COSE_Sign1 object and decode the leap second list inside.
+///
+/// The `public_key` is a raw 32 byte Ed25519 public key. The CBOR tag
+/// is optional.
+///
+/// Returns [`Error::Cose`][] if the envelope is malformed or uses an
+/// unexpected algorithm, or [`Error::Signature`][] if the signature
+/// is not valid.
+///
+pub fn verify(cose: &[u8], public_key: &[u8]) -> Result<LeapSecs> {
+    LeapSecs::try_from(verified_payload(cose, public_key)?)
+}
+
+/// Verify a COSE_Sign1 object like [`verify()`][], without failing
+/// if the list inside has expired. See [`LeapSecs::was_expired()`][].
+///
+pub fn verify_lenient(cose: &[u8], public_key: &[u8]) -> Result<LeapSecs> {
+    LeapSecs::try_from_bytes_lenient(verified_payload(cose, public_key)?)
+}
+
+fn verified_payload<'a>(cose: &'a [u8], public_key: &[u8]) -> Result<&'a [u8]> {
+    let mut input = Reader(cose);
+    if input.0.first() == Some(&(TAGGED << 5 | TAG)) {
+        input.head(TAGGED)?;
+    }
+    if input.head(ARRAY)? != 4 {
+        return Err(Error::Cose("not COSE_Sign1"));
+    }
+    if input.bytes()? != PROTECTED {
+        return Err(Error::Cose("unsupported protected header"));
+    }
+    if input.head(MAP)? != 0 {
+        return Err(Error::Cose("unsupported unprotected header"));
+    }
+    let payload = input.bytes()?;
+    let signature = input.bytes()?;
+    if !input.0.is_empty() {
+        return Err(Error::Cose("trailing garbage"));
+    }
+    UnparsedPublicKey::new(&ED25519, public_key)
+        .verify(&sig_structure(payload), signature)
+        .map_err(|_| Error::Signature)?;
+    Ok(payload)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ring::signature::KeyPair;
+    use std::str::FromStr;
+
+    #[test]
+    fn test() {
+        let text = history!("999?");
+        let list = LeapSecs::from_str(text).unwrap();
+        let key = Ed25519KeyPair::from_seed_unchecked(&[42; 32]).unwrap();
+        let public = key.public_key().as_ref();
+        let signed = sign(&list, &key);
+        assert_eq!(list, verify(&signed, public).unwrap());
+        assert_eq!(list, verify(&signed[1..], public).unwrap());
+
+        let mut forged = signed.clone();
+        let pos = 1 + 1 + 4 + 1 + 1 + 1;
+        forged[pos] ^= 1;
+        assert!(matches!(verify(&forged, public), Err(Error::Signature)));
+        assert!(matches!(verify(&signed[..20], public), Err(Error::Cose(_))));
+    }
+}
@@ -0,0 +1,205 @@
+//! Comma-separated values
+//! ======================
+//!
+//! For loading the list into a spreadsheet or database,
+//! [`LeapSecs::to_csv()`][] writes it as a table with a header row
+//! and one row for each entry in the list, and
+//! [`LeapSecs::from_csv()`][] reads it back. The columns are:
+//!
+//!   * `date`: the date in ISO 8601 format, which is the first day
+//!     of the month after a leap second, or the list's expiry date;
+//!
+//!   * `mjd`: the same date as a modified Julian day number;
+//!
+//!   * `ntp`: the NTP timestamp at the start of the date, as in the
+//!     NIST `leap-seconds.list`;
+//!
+//!   * `dtai`: TAI − UTC in seconds from the start of the date, which
+//!     is empty for the expiry date;
+//!
+//!   * `sign`: `start` for the first row, when DTAI became 10 in
+//!     1972, then `positive` or `negative` for each leap second, and
+//!     `expires` for the last row.
+//!
+//! For example,
+//!
+//!     # use leapsecs::*;
+//!     # use std::str::FromStr;
+//!     let list = LeapSecs::from_str("6+6+999?")?;
+//!     let mut csv = Vec::new();
+//!     list.to_csv(&mut csv)?;
+//!     let csv = String::from_utf8(csv)?;
+//!     assert_eq!(csv, "date,mjd,ntp,dtai,sign\n\
+//!                      1972-01-01,41317,2272060800,10,start\n\
+//!                      1972-07-01,41499,2287785600,11,positive\n\
+//!                      1973-01-01,41683,2303683200,12,positive\n\
+//!                      2056-04-28,72116,4933094400,,expires\n");
+//!     assert_eq!(list, LeapSecs::from_csv(&csv)?);
+//!     # Ok::<(), anyhow::Error>(())
+//!
+//! This requires the `std` feature.
+
+use crate::*;
+
+/// The header row of the CSV format, without its line ending
+pub const HEADER: &str = "date,mjd,ntp,dtai,sign";
+
+fn sign_name(sign: Leap) -> &'static str {
+    match sign {
+        Leap::Zero => "start",
+        Leap::Neg => "negative",
+        Leap::Pos => "positive",
+        Leap::Exp => "expires",
+    }
+}
+
+impl LeapSecs {
+    /// Write the list as comma-separated values, with the columns
+    /// described in the [`csv`][] module.
+    ///
+    pub fn to_csv<W: std::io::Write>(&self, out: &mut W) -> Result<()> {
+        writeln!(out, "{}", HEADER)?;
+        for leap in self.iter() {
+            let dtai = leap.dtai().map_or(String::new(), |d| d.to_string());
+            writeln!(
+                out,
+                "{},{},{},{},{}",
+                leap.date(),
+                leap.mjd() - MJD::from(0),
+                NTP::from(leap.mjd()),
+                dtai,
+                sign_name(leap.sign())
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Read a list in the format written by [`LeapSecs::to_csv()`][],
+    /// checking it as if by [`LeapSecBuilder::finish()`][].
+    ///
+    /// Fails with [`Error::Csv`][] if a row is malformed, or if its
+    /// columns disagree with each other.
+    ///
+    pub fn from_csv(text: &str) -> Result<LeapSecs> {
+        from_csv_with(text, LeapSecBuilder::finish)
+    }
+
+    /// Read a list in the format written by [`LeapSecs::to_csv()`][],
+    /// without failing if it has expired. See
+    /// [`LeapSecs::was_expired()`][].
+    ///
+    pub fn from_csv_lenient(text: &str) -> Result<LeapSecs> {
+        from_csv_with(text, LeapSecBuilder::finish_lenient)
+    }
+}
+
+fn from_csv_with<F>(text: &str, finish: F) -> Result<LeapSecs>
+where
+    F: FnOnce(LeapSecBuilder) -> Result<LeapSecs>,
+{
+    let mut lines = text.lines().enumerate().filter(|(_, l)| !l.is_empty());
+    match lines.next() {
+        Some((_, line)) if line.trim() == HEADER => (),
+        _ => return Err(Error::Csv(1, "missing header row")),
+    }
+    let mut builder = LeapSecs::builder();
+    let mut signs = Vec::new();
+    for (i, line) in lines {
+        let number = i + 1;
+        let bad = |what| Error::Csv(number, what);
+        let fields: Vec<&str> =
+            line.split(',').map(|f| f.trim().trim_matches('"')).collect();
+        let (date, mjd, ntp, dtai, sign) = match fields[..] {
+            [date, mjd, ntp, dtai, sign] => (date, mjd, ntp, dtai, sign),
+            _ => return Err(bad("wrong number of columns")),
+        };
+        let date = parse_date(date).ok_or_else(|| bad("invalid date"))?;
+        let mjd: i32 = mjd.parse().map_err(|_| bad("invalid mjd"))?;
+        if MJD::from(mjd) != date.mjd() {
+            return Err(bad("mjd does not match date"));
+        }
+        let ntp: i64 = ntp.parse().map_err(|_| bad("invalid ntp"))?;
+        if NTP(ntp) != NTP::from(date.mjd()) {
+            return Err(bad("ntp does not match date"));
+        }
+        let sign = [Leap::Zero, Leap::Neg, Leap::Pos, Leap::Exp]
+            .iter()
+            .copied()
+            .find(|&s| sign_name(s) == sign)
+            .ok_or_else(|| bad("invalid sign"))?;
+        match (sign, dtai) {
+            (Leap::Exp, "") => builder.push_exp(date)?,
+            (Leap::Exp, _) => return Err(bad("dtai after expiry")),
+            (_, dtai) => {
+                let dtai = dtai.parse().map_err(|_| bad("invalid dtai"))?;
+                builder.push_date(date, dtai)?;
+            }
+        }
+        signs.push((number, sign));
+    }
+    let list = finish(builder)?;
+    // the builder works out the signs from the DTAI values
+    for (leap, &(number, sign)) in list.iter().zip(&signs) {
+        if leap.sign() != sign {
+            return Err(Error::Csv(number, "sign does not match dtai"));
+        }
+    }
+    Ok(list)
+}
+
+// an ISO 8601 date, which must exist in the calendar
+fn parse_date(date: &str) -> Option<Gregorian> {
+    let mut parts = date.splitn(3, '-').map(|n| n.parse::<i32>().ok());
+    let (year, month, day) = (parts.next()??, parts.next()??, parts.next()??);
+    let date = Gregorian(year, month, day);
+    match Gregorian::from(date.mjd()) == date {
+        true => Some(date),
+        false => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test() {
+        let text = history!("999?");
+        let list = LeapSecs::from_str(text).unwrap();
+        let mut out = Vec::new();
+        list.to_csv(&mut out).unwrap();
+        let table = String::from_utf8(out).unwrap();
+        assert_eq!(table.lines().count(), 30);
+        assert!(table.contains("\n2017-01-01,57754,3692217600,37,positive\n"));
+        assert_eq!(list, LeapSecs::from_csv(&table).unwrap());
+        let crlf = table.replace('\n', "\r\n");
+        assert_eq!(list, LeapSecs::from_csv(&crlf).unwrap());
+
+        let old = LeapSecs::from_str_lenient("6-6+12?").unwrap();
+        let mut out = Vec::new();
+        old.to_csv(&mut out).unwrap();
+        let table = String::from_utf8(out).unwrap();
+        assert!(table.contains(",9,negative\n"));
+        assert!(LeapSecs::from_csv(&table).is_err());
+        assert_eq!(old, LeapSecs::from_csv_lenient(&table).unwrap());
+
+        let row = |line: &str| format!("{}\n{}\n", csv::HEADER, line);
+        for (bad, want) in &[
+            ("1972-01-01,41317,2272060800,10", 2),
+            ("1972-13-01,41317,2272060800,10,start", 2),
+            ("1972-01-01,41318,2272060800,10,start", 2),
+            ("1972-01-01,41317,2272060801,10,start", 2),
+            ("1972-01-01,41317,2272060800,ten,start", 2),
+            ("1972-01-01,41317,2272060800,10,begin", 2),
+        ] {
+            let err = LeapSecs::from_csv_lenient(&row(bad)).unwrap_err();
+            assert!(matches!(err, Error::Csv(n, _) if n == *want), "{}", bad);
+        }
+        let wrong = table.replace(",9,negative", ",9,positive");
+        let err = LeapSecs::from_csv_lenient(&wrong).unwrap_err();
+        assert!(matches!(err, Error::Csv(3, "sign does not match dtai")));
+        let err = LeapSecs::from_csv_lenient("1972-01-01").unwrap_err();
+        assert!(matches!(err, Error::Csv(1, _)));
+    }
+}
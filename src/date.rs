@@ -11,6 +11,21 @@
 //! You can use the [From][] and [Into][] traits to convert between
 //! them in either direction. Conversion from MJD to Gregorian is
 //! about twice as expensive as conversion from Gregorian to MJD.
+//!
+//! An [NTP][] timestamp counts seconds since 1900, and converts to
+//! and from dates at midnight. A [Unix][] timestamp counts seconds
+//! since 1970, and converts to the date that contains it.
+//!
+//! The [Clock][] trait says what the date is today, for checking
+//! whether leap second lists have expired.
+//!
+//! Some helpers do the date arithmetic that leap seconds need:
+//! [`Gregorian::add_months()`][], [`Gregorian::last_day_of_month()`][],
+//! [`MJD::succ()`][], [`MJD::pred()`][], and [`MJD::weekday()`][].
+//!
+//! There are also constants for some well-known dates:
+//! [UTC_START_1972][], [NTP_EPOCH][], [UNIX_EPOCH_MJD][], and
+//! [GPS_EPOCH][].
 
 /// A date in the Gregorian calendar
 ///
@@ -21,7 +36,7 @@
 ///     let epoch = Gregorian(1858,11,17);
 ///     assert_eq!(MJD::from(epoch), MJD::from(0));
 ///
-/// [`Gregorian`][]'s [`std::fmt::Display`][]
+/// [`Gregorian`][]'s [`core::fmt::Display`][]
 /// implementation prints the date in ISO 8601 format.
 ///
 /// The elements of the date are represented as `i32`, to prioritize
@@ -35,8 +50,8 @@ pub struct Gregorian(pub i32, pub i32, pub i32);
 
 /// Write a [Gregorian][] date in ISO 8601 format
 ///
-impl std::fmt::Display for Gregorian {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl core::fmt::Display for Gregorian {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         write!(f, "{:04}-{:02}-{:02}", self.year(), self.month(), self.day())
     }
 }
@@ -55,6 +70,39 @@ impl Gregorian {
         self.2
     }
 
+    /// Add a number of months to the date, which can be negative.
+    ///
+    /// The day is kept the same, unless it would be past the end of
+    /// the month, in which case the result is the last day of the
+    /// month.
+    ///
+    ///     # use leapsecs::*;
+    ///     assert_eq!(Gregorian(2016, 6, 28).add_months(6),
+    ///                Gregorian(2016, 12, 28));
+    ///     assert_eq!(Gregorian(2016, 3, 31).add_months(-1),
+    ///                Gregorian(2016, 2, 29));
+    ///
+    pub fn add_months(self, months: i32) -> Gregorian {
+        let month = self.0 * 12 + (self.1 - 1) + months;
+        let first =
+            Gregorian(month.div_euclid(12), month.rem_euclid(12) + 1, 1);
+        let last = first.last_day_of_month();
+        Gregorian(first.0, first.1, self.2.min(last.2))
+    }
+
+    /// Get the last day of the date's month, which is the day before a
+    /// leap second happens if this is a leap month.
+    ///
+    ///     # use leapsecs::*;
+    ///     assert_eq!(Gregorian(2016, 12, 1).last_day_of_month(),
+    ///                Gregorian(2016, 12, 31));
+    ///
+    pub fn last_day_of_month(self) -> Gregorian {
+        let (y, m) =
+            if self.1 < 12 { (self.0, self.1 + 1) } else { (self.0 + 1, 1) };
+        Gregorian::from(Gregorian(y, m, 1).mjd().pred())
+    }
+
     /// Convert the date to an [MJD][]
     ///
     /// (This method can be used in `const` items, whereas
@@ -63,18 +111,20 @@ impl Gregorian {
     pub const fn mjd(self) -> MJD {
         let Gregorian(y, m, d) = self;
         let (y, m) = if m > 2 { (y, m + 1) } else { (y - 1, m + 13) };
-        MJD(days_in_years(y) + muldiv(m, 153, 5) + d - 679004)
+        let days = days_in_years(y as i64) + muldiv(m as i64, 153, 5);
+        MJD((days + d as i64 - 679004) as i32)
     }
 }
 
 impl From<MJD> for Gregorian {
     fn from(mjd: MJD) -> Gregorian {
-        let mut d = mjd.0 + 678881;
+        let mut d = i64::from(mjd.0) + 678881;
         let mut y = muldiv(d, 400, 146097) + 1;
-        y -= (days_in_years(y) > d) as i32;
+        y -= (days_in_years(y) > d) as i64;
         d -= days_in_years(y) - 31;
         let m = muldiv(d, 17, 520);
         d -= muldiv(m, 520, 17);
+        let (y, m, d) = (y as i32, m as i32, d as i32);
         if m > 10 {
             Gregorian(y + 1, m - 10, d)
         } else {
@@ -89,11 +139,14 @@ impl From<Gregorian> for MJD {
     }
 }
 
-const fn days_in_years(y: i32) -> i32 {
+// the arithmetic is done in i64 so that every MJD has a date
+// and converts back again without overflow
+
+const fn days_in_years(y: i64) -> i64 {
     muldiv(y, 1461, 4) - muldiv(y, 1, 100) + muldiv(y, 1, 400)
 }
 
-const fn muldiv(var: i32, mul: i32, div: i32) -> i32 {
+const fn muldiv(var: i64, mul: i64, div: i64) -> i64 {
     (var * mul).div_euclid(div)
 }
 
@@ -101,21 +154,21 @@ const fn muldiv(var: i32, mul: i32, div: i32) -> i32 {
 ///
 /// An MJD is a signed count of days where 1858-11-17 is day zero.
 ///
-/// [`MJD`][]'s [`std::fmt::Display`][] implementation prints the date
+/// [`MJD`][]'s [`core::fmt::Display`][] implementation prints the date
 /// in human-readable ISO 8601 format as well as the MJD number.
 ///
 #[allow(clippy::upper_case_acronyms)]
 #[derive(Copy, Clone, Eq, Ord, PartialEq, PartialOrd)]
 pub struct MJD(i32);
 
-impl std::fmt::Display for MJD {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl core::fmt::Display for MJD {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         write!(f, "{} mjd {}", Gregorian::from(*self), self.0)
     }
 }
 
-impl std::fmt::Debug for MJD {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl core::fmt::Debug for MJD {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         write!(f, "MJD({})", self)
     }
 }
@@ -126,21 +179,21 @@ impl From<i32> for MJD {
     }
 }
 
-impl std::ops::Add<i32> for MJD {
+impl core::ops::Add<i32> for MJD {
     type Output = MJD;
     fn add(self, days: i32) -> MJD {
         MJD(self.0 + days)
     }
 }
 
-impl std::ops::Sub<i32> for MJD {
+impl core::ops::Sub<i32> for MJD {
     type Output = MJD;
     fn sub(self, days: i32) -> MJD {
         MJD(self.0 - days)
     }
 }
 
-impl std::ops::Sub<MJD> for MJD {
+impl core::ops::Sub<MJD> for MJD {
     type Output = i32;
     fn sub(self, other: MJD) -> i32 {
         self.0 - other.0
@@ -148,21 +201,230 @@ impl std::ops::Sub<MJD> for MJD {
 }
 
 impl MJD {
+    /// Get the next day
+    pub fn succ(self) -> MJD {
+        MJD(self.0 + 1)
+    }
+
+    /// Get the previous day
+    pub fn pred(self) -> MJD {
+        MJD(self.0 - 1)
+    }
+
+    // add a number of days that might not fit, failing instead of
+    // overflowing, for conversions from timestamps
+    pub(crate) fn checked_add_days(self, days: i64) -> crate::Result<MJD> {
+        use core::convert::TryFrom;
+        Ok(MJD(i32::try_from(i64::from(self.0) + days)?))
+    }
+
+    /// Get the day of the week as an ISO 8601 day number, from 1 for
+    /// Monday to 7 for Sunday.
+    ///
+    ///     # use leapsecs::*;
+    ///     assert_eq!(Gregorian(1972, 1, 1).mjd().weekday(), 6);
+    ///
+    pub fn weekday(self) -> i32 {
+        // day zero was a Wednesday
+        (self.0 + 2).rem_euclid(7) + 1
+    }
+
     /// Get today's date as an [`MJD`][]
     ///
+    /// This requires the `std` feature.
+    ///
+    #[cfg(feature = "std")]
     pub fn today() -> MJD {
-        use std::convert::TryFrom;
+        use core::convert::TryFrom;
         use std::time::SystemTime;
         let now = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH);
         // panic if we are in a tardis
         let days = now.unwrap().as_secs().div_euclid(86400);
-        MJD::from(Gregorian(1970, 1, 1)) + i32::try_from(days).unwrap()
+        UNIX_EPOCH_MJD + i32::try_from(days).unwrap()
+    }
+}
+
+/// An NTP timestamp, in seconds since 1900-01-01 not counting leap
+/// seconds, as used in the NIST `leap-seconds.list`
+///
+/// An [`MJD`][] or [`Gregorian`][] date converts to the timestamp at
+/// midnight at the start of the day. Converting the other way fails
+/// with [`Error::Midnight`][crate::Error::Midnight] if the timestamp
+/// is not at midnight.
+///
+///     # use leapsecs::*;
+///     # use core::convert::TryFrom;
+///     let ntp = NTP::from(Gregorian(1972, 1, 1));
+///     assert_eq!(ntp, NTP(2272060800));
+///     assert_eq!(Gregorian::try_from(ntp)?, Gregorian(1972, 1, 1));
+///     assert!(MJD::try_from(NTP(2272060801)).is_err());
+///     # Ok::<(), Error>(())
+///
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct NTP(pub i64);
+
+impl core::fmt::Display for NTP {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl From<MJD> for NTP {
+    fn from(mjd: MJD) -> NTP {
+        NTP((mjd - NTP_EPOCH) as i64 * 86400)
+    }
+}
+
+impl From<Gregorian> for NTP {
+    fn from(date: Gregorian) -> NTP {
+        NTP::from(date.mjd())
+    }
+}
+
+impl core::convert::TryFrom<NTP> for MJD {
+    type Error = crate::Error;
+    fn try_from(ntp: NTP) -> crate::Result<MJD> {
+        let mjd = NTP_EPOCH.checked_add_days(ntp.0.div_euclid(86400))?;
+        let secs = i32::try_from(ntp.0.rem_euclid(86400))?;
+        if secs != 0 {
+            Err(crate::Error::Midnight(ntp.0, mjd, secs))
+        } else {
+            Ok(mjd)
+        }
+    }
+}
+
+impl core::convert::TryFrom<NTP> for Gregorian {
+    type Error = crate::Error;
+    fn try_from(ntp: NTP) -> crate::Result<Gregorian> {
+        Ok(Gregorian::from(MJD::try_from(ntp)?))
+    }
+}
+
+/// A Unix timestamp, in seconds since 1970-01-01 not counting leap
+/// seconds, i.e. POSIX `time_t`
+///
+/// An [`MJD`][] or [`Gregorian`][] date converts to the timestamp at
+/// midnight at the start of the day. Unlike an [`NTP`][] timestamp,
+/// a Unix timestamp can be at any time of day, and converts to the
+/// date that contains it. That conversion only fails if the date is
+/// out of range.
+///
+///     # use leapsecs::*;
+///     # use core::convert::TryFrom;
+///     let unix = Unix(1_483_228_799);
+///     assert_eq!(Gregorian::try_from(unix)?, Gregorian(2016, 12, 31));
+///     assert_eq!(Unix::from(Gregorian(2017, 1, 1)), Unix(1_483_228_800));
+///     assert_eq!(NTP::from(Unix(0)), NTP::from(UNIX_EPOCH_MJD));
+///     # Ok::<(), Error>(())
+///
+#[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Unix(pub i64);
+
+// seconds from the NTP epoch to the Unix epoch
+const NTP_UNIX: i64 = (UNIX_EPOCH_MJD.0 - NTP_EPOCH.0) as i64 * 86400;
+
+impl core::fmt::Display for Unix {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl From<MJD> for Unix {
+    fn from(mjd: MJD) -> Unix {
+        Unix((mjd - UNIX_EPOCH_MJD) as i64 * 86400)
+    }
+}
+
+impl From<Gregorian> for Unix {
+    fn from(date: Gregorian) -> Unix {
+        Unix::from(date.mjd())
+    }
+}
+
+impl From<NTP> for Unix {
+    fn from(ntp: NTP) -> Unix {
+        Unix(ntp.0 - NTP_UNIX)
+    }
+}
+
+impl From<Unix> for NTP {
+    fn from(unix: Unix) -> NTP {
+        NTP(unix.0 + NTP_UNIX)
+    }
+}
+
+impl core::convert::TryFrom<Unix> for MJD {
+    type Error = crate::Error;
+    fn try_from(unix: Unix) -> crate::Result<MJD> {
+        UNIX_EPOCH_MJD.checked_add_days(unix.0.div_euclid(86400))
+    }
+}
+
+impl core::convert::TryFrom<Unix> for Gregorian {
+    type Error = crate::Error;
+    fn try_from(unix: Unix) -> crate::Result<Gregorian> {
+        Ok(Gregorian::from(MJD::try_from(unix)?))
+    }
+}
+
+/// The start of UTC as currently defined, 1972-01-01, which is the
+/// date of the first entry in every leap second list
+pub const UTC_START_1972: MJD = Gregorian(1972, 1, 1).mjd();
+
+/// The NTP epoch, 1900-01-01, from which NTP timestamps count seconds
+pub const NTP_EPOCH: MJD = Gregorian(1900, 1, 1).mjd();
+
+/// The Unix epoch, 1970-01-01, from which POSIX `time_t` counts seconds
+pub const UNIX_EPOCH_MJD: MJD = Gregorian(1970, 1, 1).mjd();
+
+/// The GPS epoch, 1980-01-06, when GPS time was 19 seconds behind TAI
+pub const GPS_EPOCH: MJD = Gregorian(1980, 1, 6).mjd();
+
+/// A source of today's date, for checking when leap second lists
+/// expire.
+///
+/// An [`MJD`][] is a [`Clock`][] that is stuck on a fixed date, which
+/// is useful for tests, for examining historical lists, and for
+/// systems that have a better idea of the time than the system clock.
+///
+pub trait Clock {
+    /// Get today's date
+    fn today(&self) -> MJD;
+}
+
+/// The system clock, via [`MJD::today()`][]
+///
+/// This requires the `std` feature.
+///
+#[cfg(feature = "std")]
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct SystemClock;
+
+#[cfg(feature = "std")]
+impl Clock for SystemClock {
+    fn today(&self) -> MJD {
+        MJD::today()
+    }
+}
+
+impl Clock for MJD {
+    fn today(&self) -> MJD {
+        *self
+    }
+}
+
+impl<C: Clock> Clock for &C {
+    fn today(&self) -> MJD {
+        (*self).today()
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use core::convert::TryFrom;
 
     #[test]
     fn test() {
@@ -183,5 +445,56 @@ mod test {
             assert_eq!(mjd, MJD::from(date));
         }
         assert_eq!(146097, days_in_years(400));
+        assert_eq!(MJD::from(15020), NTP_EPOCH);
+        assert_eq!(MJD::from(40587), UNIX_EPOCH_MJD);
+        assert_eq!(MJD::from(41317), UTC_START_1972);
+        assert_eq!(MJD::from(44244), GPS_EPOCH);
+        assert_eq!(NTP(0), NTP::from(NTP_EPOCH));
+        assert_eq!(NTP(-86400), NTP::from(Gregorian(1899, 12, 31)));
+        assert_eq!(MJD::try_from(NTP(-86400)).unwrap(), NTP_EPOCH - 1);
+        assert!(MJD::try_from(NTP(-1)).is_err());
+        let err = MJD::try_from(NTP(i64::MAX)).unwrap_err();
+        assert!(matches!(err, crate::Error::FromInt(_)));
+        assert!(MJD::try_from(NTP(i64::MIN)).is_err());
+        assert!(Gregorian::try_from(NTP(i64::MAX)).is_err());
+        assert_eq!(Unix(0), Unix::from(NTP::from(UNIX_EPOCH_MJD)));
+        assert_eq!(MJD::try_from(Unix(-1)).unwrap(), UNIX_EPOCH_MJD - 1);
+        assert!(MJD::try_from(Unix(i64::MAX)).is_err());
+        assert!(MJD::try_from(Unix(i64::MIN)).is_err());
+        let max = (i64::from(i32::MAX) - 40587) * 86400;
+        assert_eq!(MJD::try_from(Unix(max)).unwrap(), MJD::from(i32::MAX));
+        let err = MJD::try_from(Unix(max + 86400)).unwrap_err();
+        assert!(matches!(err, crate::Error::FromInt(_)));
+        assert!(MJD::try_from(Unix(185_542_587_187_199)).is_err());
+        let min = (i64::from(i32::MIN) - 40587) * 86400;
+        assert_eq!(MJD::try_from(Unix(min)).unwrap(), MJD::from(i32::MIN));
+        let err = MJD::try_from(Unix(min - 1)).unwrap_err();
+        assert!(matches!(err, crate::Error::FromInt(_)));
+        for &mjd in &[MJD::from(i32::MIN), MJD::from(i32::MAX)] {
+            assert_eq!(Gregorian::from(mjd).mjd(), mjd);
+        }
+        assert!(Gregorian::try_from(Unix(max)).is_ok());
+        assert!(Gregorian::try_from(Unix(min)).is_ok());
+    }
+
+    #[test]
+    fn arithmetic() {
+        let date = Gregorian(2016, 12, 31);
+        assert_eq!(date.mjd(), Gregorian(2017, 1, 1).mjd().pred());
+        assert_eq!(date.mjd().succ().pred(), date.mjd());
+        assert_eq!(Gregorian(2017, 1, 31), date.add_months(1));
+        assert_eq!(Gregorian(2017, 2, 28), date.add_months(2));
+        assert_eq!(Gregorian(2015, 12, 31), date.add_months(-12));
+        assert_eq!(Gregorian(-1, 11, 30), Gregorian(0, 1, 30).add_months(-2));
+        assert_eq!(
+            Gregorian(1900, 2, 28),
+            Gregorian(1900, 2, 1).last_day_of_month()
+        );
+        assert_eq!(
+            Gregorian(2000, 2, 29),
+            Gregorian(2000, 2, 1).last_day_of_month()
+        );
+        assert_eq!(3, MJD::from(0).weekday());
+        assert_eq!(7, Gregorian(2017, 1, 1).mjd().weekday());
     }
 }
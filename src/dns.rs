@@ -0,0 +1,230 @@
+//! Leap second lists in the DNS
+//! ============================
+//!
+//! The compact binary format is small enough to fit comfortably in a
+//! DNS record. This module converts between a [`LeapSecs`][] list and
+//! DNS RDATA in two styles:
+//!
+//!   * [`to_null_rdata()`][] and [`from_null_rdata()`][] use the raw
+//!     compact binary format, suitable for a NULL record or a private
+//!     record type.
+//!
+//!   * [`to_txt_rdata()`][] and [`from_txt_rdata()`][] encode the
+//!     compact binary format as text using [`Encoding::Base32`][] or
+//!     [`Encoding::Base64`][], split into TXT record character-strings
+//!     of at most 255 bytes.
+//!
+//! The [`base32_encode()`][], [`base32_decode()`][],
+//! [`base64_encode()`][], and [`base64_decode()`][] helpers implement
+//! the standard alphabets from RFC 4648, with padding.
+
+use crate::*;
+use alloc::string::String;
+use core::convert::TryFrom;
+
+/// The largest possible RDATA
+const RDATA_MAX: usize = 65535;
+
+/// The largest possible TXT record character-string
+const STRING_MAX: usize = 255;
+
+/// How to represent the compact binary format in a TXT record
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Encoding {
+    /// RFC 4648 base32, which is case-insensitive
+    Base32,
+    /// RFC 4648 base64, which is more compact
+    Base64,
+}
+
+/// Encode a list as RDATA in compact binary format.
+///
+/// Returns [`Error::RData`][] if the list is too long to fit.
+///
+pub fn to_null_rdata(list: &LeapSecs) -> Result<Vec<u8>> {
+    let rdata = Vec::<u8>::from(list);
+    if rdata.len() > RDATA_MAX {
+        return Err(Error::RData("too long"));
+    }
+    Ok(rdata)
+}
+
+/// Decode a list from RDATA in compact binary format.
+///
+pub fn from_null_rdata(rdata: &[u8]) -> Result<LeapSecs> {
+    if rdata.len() > RDATA_MAX {
+        return Err(Error::RData("too long"));
+    }
+    LeapSecs::try_from(rdata)
+}
+
+/// Encode a list as TXT RDATA.
+///
+/// The compact binary format is encoded as text, then split into
+/// character-strings, each prefixed with its length.
+///
+/// Returns [`Error::RData`][] if the list is too long to fit.
+///
+pub fn to_txt_rdata(list: &LeapSecs, encoding: Encoding) -> Result<Vec<u8>> {
+    let bytes = Vec::<u8>::from(list);
+    let text = match encoding {
+        Encoding::Base32 => base32_encode(&bytes),
+        Encoding::Base64 => base64_encode(&bytes),
+    };
+    let mut rdata = Vec::new();
+    for chunk in text.as_bytes().chunks(STRING_MAX) {
+        rdata.push(chunk.len() as u8);
+        rdata.extend_from_slice(chunk);
+    }
+    if rdata.len() > RDATA_MAX {
+        return Err(Error::RData("too long"));
+    }
+    Ok(rdata)
+}
+
+/// Decode a list from TXT RDATA.
+///
+/// The character-strings are concatenated before they are decoded,
+/// so the list can be split at any point.
+///
+pub fn from_txt_rdata(rdata: &[u8], encoding: Encoding) -> Result<LeapSecs> {
+    if rdata.len() > RDATA_MAX {
+        return Err(Error::RData("too long"));
+    }
+    let mut text = String::new();
+    let mut rest = rdata;
+    while let Some((&len, tail)) = rest.split_first() {
+        let len = len as usize;
+        if len > tail.len() {
+            return Err(Error::RData("truncated character-string"));
+        }
+        for &byte in &tail[..len] {
+            text.push(byte as char);
+        }
+        rest = &tail[len..];
+    }
+    let bytes = match encoding {
+        Encoding::Base32 => base32_decode(&text)?,
+        Encoding::Base64 => base64_decode(&text)?,
+    };
+    LeapSecs::try_from(&bytes[..])
+}
+
+//  _                 _______ __ _ _
+// | |__  __ _ ___ __|__ /_  )/ /| | |
+// | '_ \/ _` (_-</ -_)|_ \/ // _ \_  _|
+// |_.__/\__,_/__/\___|___/___\___/ |_|
+
+const BASE32: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+const BASE64: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+// encode `bytes` in `bits`-sized pieces using `alphabet`, padded to a
+// multiple of `block` characters
+fn encode(bytes: &[u8], alphabet: &[u8], bits: u32, block: usize) -> String {
+    let mask = (1 << bits) - 1;
+    let mut out = String::new();
+    let mut acc = 0u32;
+    let mut have = 0;
+    for &byte in bytes {
+        acc = acc << 8 | byte as u32;
+        have += 8;
+        while have >= bits {
+            have -= bits;
+            out.push(alphabet[(acc >> have & mask) as usize] as char);
+        }
+    }
+    if have > 0 {
+        out.push(alphabet[(acc << (bits - have) & mask) as usize] as char);
+    }
+    while !out.len().is_multiple_of(block) {
+        out.push('=');
+    }
+    out
+}
+
+// the inverse of `encode()`, ignoring padding
+fn decode(text: &str, alphabet: &[u8], bits: u32) -> Result<Vec<u8>> {
+    let mut out = Vec::new();
+    let mut acc = 0u32;
+    let mut have = 0;
+    for c in text.trim_end_matches('=').chars() {
+        let value = alphabet
+            .iter()
+            .position(|&a| a as char == c)
+            .ok_or(Error::BaseN(c))?;
+        acc = (acc << bits | value as u32) & 0xFFFF;
+        have += bits;
+        if have >= 8 {
+            have -= 8;
+            out.push((acc >> have) as u8);
+        }
+    }
+    Ok(out)
+}
+
+/// Encode bytes as RFC 4648 base32.
+///
+pub fn base32_encode(bytes: &[u8]) -> String {
+    encode(bytes, BASE32, 5, 8)
+}
+
+/// Decode RFC 4648 base32, in upper or lower case.
+///
+/// Returns [`Error::BaseN`][] if there is an invalid character.
+///
+pub fn base32_decode(text: &str) -> Result<Vec<u8>> {
+    decode(&text.to_ascii_uppercase(), BASE32, 5)
+}
+
+/// Encode bytes as RFC 4648 base64.
+///
+pub fn base64_encode(bytes: &[u8]) -> String {
+    encode(bytes, BASE64, 6, 4)
+}
+
+/// Decode RFC 4648 base64.
+///
+/// Returns [`Error::BaseN`][] if there is an invalid character.
+///
+pub fn base64_decode(text: &str) -> Result<Vec<u8>> {
+    decode(text, BASE64, 6)
+}
+
+#[cfg(all(test, feature = "std"))]
+mod test {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test() {
+        for &(plain, b32, b64) in &[
+            ("", "", ""),
+            ("f", "MY======", "Zg=="),
+            ("fo", "MZXQ====", "Zm8="),
+            ("foo", "MZXW6===", "Zm9v"),
+            ("foob", "MZXW6YQ=", "Zm9vYg=="),
+            ("fooba", "MZXW6YTB", "Zm9vYmE="),
+            ("foobar", "MZXW6YTBOI======", "Zm9vYmFy"),
+        ] {
+            assert_eq!(b32, base32_encode(plain.as_bytes()));
+            assert_eq!(b64, base64_encode(plain.as_bytes()));
+            assert_eq!(plain.as_bytes(), &base32_decode(b32).unwrap()[..]);
+            assert_eq!(plain.as_bytes(), &base64_decode(b64).unwrap()[..]);
+        }
+        assert_eq!(b"foobar", &base32_decode("mzxw6ytboi").unwrap()[..]);
+        assert!(base64_decode("Zm9v!").is_err());
+
+        let text = history!("999?");
+        let list = LeapSecs::from_str(text).unwrap();
+        let rdata = to_null_rdata(&list).unwrap();
+        assert_eq!(list, from_null_rdata(&rdata).unwrap());
+        for &encoding in &[Encoding::Base32, Encoding::Base64] {
+            let rdata = to_txt_rdata(&list, encoding).unwrap();
+            assert_eq!(rdata[0] as usize, rdata.len() - 1);
+            assert_eq!(list, from_txt_rdata(&rdata, encoding).unwrap());
+        }
+        assert!(from_txt_rdata(b"\x05ABC", Encoding::Base64).is_err());
+    }
+}
@@ -0,0 +1,161 @@
+//! C bindings
+//! ==========
+//!
+//! These `extern "C"` functions let programs written in C, such as
+//! NTP daemons, use a leap second list in compact binary format. The
+//! C declarations are in `include/leapsecs.h`, which is written by
+//! hand and must be kept in step with this module. There is also a
+//! `cbindgen.toml` for checking it against `cbindgen`'s output.
+//!
+//! ```c
+//! struct LeapSecs *list = leapsecs_from_bytes(buf, len);
+//! int16_t dtai;
+//! if (list != NULL && leapsecs_dtai_at_unix(list, now, &dtai) == 0)
+//!         printf("TAI - UTC = %d\n", dtai);
+//! leapsecs_free(list);
+//! ```
+//!
+//! The list is not checked for expiry when it is parsed, because
+//! the caller should compare [`leapsecs_expires()`][] with its own
+//! idea of the time.
+//!
+//! To build a static library for linking with C, run
+//! `cargo rustc --release --features ffi --crate-type staticlib`.
+//!
+//! Requires the `ffi` feature.
+
+use crate::*;
+use alloc::boxed::Box;
+
+/// Parse a leap second list in compact binary format.
+///
+/// Returns `NULL` if the list is invalid. Otherwise the list must be
+/// released with [`leapsecs_free()`][].
+///
+/// # Safety
+///
+/// `bytes` must point to `len` readable bytes.
+///
+#[no_mangle]
+pub unsafe extern "C" fn leapsecs_from_bytes(
+    bytes: *const u8,
+    len: usize,
+) -> *mut LeapSecs {
+    if bytes.is_null() {
+        return core::ptr::null_mut();
+    }
+    let bytes = core::slice::from_raw_parts(bytes, len);
+    match LeapSecs::try_from_bytes_lenient(bytes) {
+        Ok(list) => Box::into_raw(Box::new(list)),
+        Err(_) => core::ptr::null_mut(),
+    }
+}
+
+/// Release a list returned by [`leapsecs_from_bytes()`][]. Does
+/// nothing if `list` is `NULL`.
+///
+/// # Safety
+///
+/// `list` must be `NULL` or a list from [`leapsecs_from_bytes()`][]
+/// that has not already been released.
+///
+#[no_mangle]
+pub unsafe extern "C" fn leapsecs_free(list: *mut LeapSecs) {
+    if !list.is_null() {
+        drop(Box::from_raw(list));
+    }
+}
+
+/// Get the difference between UTC and TAI at a Unix timestamp, like
+/// [`LeapSecs::dtai_at_unix()`][], and store it in `*dtai`.
+///
+/// Returns 0 on success, or -1 if the timestamp is before 1972 or
+/// after the list's expiry date, in which case `*dtai` is unchanged.
+///
+/// # Safety
+///
+/// `list` must be a list from [`leapsecs_from_bytes()`][] and `dtai`
+/// must point to writable memory.
+///
+#[no_mangle]
+pub unsafe extern "C" fn leapsecs_dtai_at_unix(
+    list: *const LeapSecs,
+    unix: i64,
+    dtai: *mut i16,
+) -> i32 {
+    match list.as_ref().map(|list| list.dtai_at_unix(Unix(unix))) {
+        Some(Ok(value)) if !dtai.is_null() => {
+            *dtai = value;
+            0
+        }
+        _ => -1,
+    }
+}
+
+/// Get the Unix timestamp at the start of the list's expiry date.
+/// The list remains valid until the end of that day, 86400 seconds
+/// later.
+///
+/// Returns `INT64_MIN` if `list` is `NULL`, which is long before any
+/// list expires.
+///
+/// # Safety
+///
+/// `list` must be `NULL` or a list from [`leapsecs_from_bytes()`][].
+///
+#[no_mangle]
+pub unsafe extern "C" fn leapsecs_expires(list: *const LeapSecs) -> i64 {
+    match list.as_ref() {
+        Some(list) => Unix::from(list.expires()).0,
+        None => i64::MIN,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::ffi::*;
+
+    #[test]
+    fn test() {
+        let text = history!("59?");
+        let want = LeapSecs::from_str_lenient(text).unwrap();
+        let bytes = Vec::from(&want);
+        unsafe {
+            let list = leapsecs_from_bytes(bytes.as_ptr(), bytes.len());
+            assert!(!list.is_null());
+            let mut dtai = 0;
+            assert_eq!(
+                0,
+                leapsecs_dtai_at_unix(list, 1_483_228_800, &mut dtai)
+            );
+            assert_eq!(37, dtai);
+            assert_eq!(
+                0,
+                leapsecs_dtai_at_unix(list, 1_483_228_799, &mut dtai)
+            );
+            assert_eq!(36, dtai);
+            let expires = leapsecs_expires(list);
+            assert_eq!(Unix::from(want.expires()).0, expires);
+            assert_eq!(
+                0,
+                leapsecs_dtai_at_unix(list, expires + 86399, &mut dtai)
+            );
+            assert_eq!(
+                -1,
+                leapsecs_dtai_at_unix(list, expires + 86400, &mut dtai)
+            );
+            assert_eq!(-1, leapsecs_dtai_at_unix(list, 0, &mut dtai));
+            assert_eq!(-1, leapsecs_dtai_at_unix(list, i64::MIN, &mut dtai));
+            assert_eq!(-1, leapsecs_dtai_at_unix(list, i64::MAX, &mut dtai));
+            assert_eq!(37, dtai);
+            leapsecs_free(list);
+
+            let list = leapsecs_from_bytes(bytes.as_ptr(), bytes.len() - 1);
+            assert!(list.is_null());
+            leapsecs_free(list);
+            let mut dtai = 0;
+            assert_eq!(-1, leapsecs_dtai_at_unix(list, 0, &mut dtai));
+            assert_eq!(i64::MIN, leapsecs_expires(list));
+        }
+    }
+}
@@ -0,0 +1,220 @@
+//! Fixed-capacity leap second lists
+//! ================================
+//!
+//! A [`LeapSecsFixed`][] is for targets that lack `alloc`. It stores
+//! up to `N` entries in an array, so it can live in a `static` or on
+//! the stack. It is built by decoding the compact binary format, with
+//! the same consistency checks as a heap-allocated list.
+
+use crate::*;
+
+/// A leap second list with room for at most `N` entries.
+///
+/// The list has one more entry than the number of leap seconds, plus
+/// one more for its expiry date. In 2021 that is 29 entries, so an
+/// `N` of 40 or so leaves room for future leap seconds.
+///
+/// It is constructed from the compact binary format. Without `std`
+/// there is no system clock, so firmware should check for expiry
+/// against its own idea of the date:
+///
+///     # use leapsecs::*;
+///     let binary: &[u8] = b"\x00\x11\x11\x11\x12\x11\x34\x31\
+///                           \x21\x12\x22\x9D\x56\x52\x7F";
+///     let today = Gregorian(2021, 1, 1).mjd();
+///     let list = LeapSecsFixed::<40>::try_from_bytes_at(binary, today)?;
+///     assert_eq!(list.dtai_at(Gregorian(2020, 1, 1))?, 37);
+///     # Ok::<(), Error>(())
+///
+/// A list that does not fit fails with [`Error::Full`][].
+///
+/// Two lists are equal if their entries are equal, regardless of
+/// their capacity.
+///
+#[derive(Copy, Clone, Debug)]
+pub struct LeapSecsFixed<const N: usize> {
+    list: [LeapSec; N],
+    len: usize,
+}
+
+impl<const N: usize> Entries for LeapSecsFixed<N> {
+    fn entries(&self) -> &[LeapSec] {
+        &self.list[..self.len]
+    }
+
+    fn add_entry(&mut self, leap: LeapSec) -> Result<()> {
+        let slot = self.list.get_mut(self.len).ok_or(Error::Full(N))?;
+        *slot = leap;
+        self.len += 1;
+        Ok(())
+    }
+
+    fn pop_entry(&mut self) {
+        self.len -= 1;
+    }
+}
+
+impl<const N: usize, const M: usize> PartialEq<LeapSecsFixed<M>>
+    for LeapSecsFixed<N>
+{
+    fn eq(&self, other: &LeapSecsFixed<M>) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
+
+impl<const N: usize> Eq for LeapSecsFixed<N> {}
+
+impl<const N: usize> LeapSecsFixed<N> {
+    fn decode(bytes: &[u8]) -> Result<Self> {
+        let mut list = LeapSecsFixed { list: [LeapSec::start(); N], len: 0 };
+        let mut nibbles = bin::Nibbles::default();
+        nibbles.feed(&mut list, bytes)?;
+        nibbles.finish(&mut list)?;
+        list.check_finished()?;
+        Ok(list)
+    }
+
+    /// Parse a leap second list in compact binary format, checking
+    /// for expiry using the given [`Clock`][].
+    ///
+    pub fn try_from_bytes_at<C: Clock>(bytes: &[u8], clock: C) -> Result<Self> {
+        let list = Self::decode(bytes)?;
+        if list.is_expired_at(clock) {
+            Err(Error::Expired(Gregorian::from(list.expires())))
+        } else {
+            Ok(list)
+        }
+    }
+
+    /// Parse a leap second list in compact binary format, without
+    /// checking for expiry.
+    ///
+    pub fn try_from_bytes_lenient(bytes: &[u8]) -> Result<Self> {
+        Self::decode(bytes)
+    }
+
+    /// Get the entries as a slice
+    pub fn as_slice(&self) -> &[LeapSec] {
+        self.entries()
+    }
+
+    /// Find the previous leap second before a particular `date`,
+    /// like [`LeapSecs::before()`][].
+    ///
+    pub fn before(&self, date: Gregorian) -> Option<&LeapSec> {
+        before(self.as_slice(), date)
+    }
+
+    /// Get the difference between UTC and TAI on a particular `date`,
+    /// like [`LeapSecs::dtai_at()`][].
+    ///
+    /// Without the `alloc` feature, a date that is not covered by the
+    /// list fails with [`Error::TooEarly`][] or [`Error::Expired`][]
+    /// instead of `Error::Uncovered`.
+    ///
+    pub fn dtai_at(&self, date: Gregorian) -> Result<i16> {
+        check_covered(date, self.expires())?;
+        // still valid on the expiry date
+        dtai_at(&self.as_slice()[..self.len - 1], date)
+    }
+
+    /// Get the expiry date of the list.
+    pub fn expires(&self) -> MJD {
+        self.as_slice().last().unwrap().mjd()
+    }
+
+    /// Returns true if the list has expired according to the given
+    /// [`Clock`][]. The list is still valid on its expiry date.
+    ///
+    pub fn is_expired_at<C: Clock>(&self, clock: C) -> bool {
+        self.expires() < clock.today()
+    }
+
+    /// Get an element of the list, or [`None`][] if the index is out
+    /// of range
+    pub fn get(&self, i: usize) -> Option<&LeapSec> {
+        self.as_slice().get(i)
+    }
+
+    /// Returns true if [`LeapSecsFixed::len()`][] is zero
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Get an iterator over the [`LeapSec`][] elements
+    pub fn iter(&self) -> core::slice::Iter<'_, LeapSec> {
+        self.as_slice().iter()
+    }
+
+    /// Get the number of [`LeapSec`][] elements
+    pub fn len(&self) -> usize {
+        self.len
+    }
+}
+
+impl<const N: usize> core::convert::TryFrom<&[u8]> for LeapSecsFixed<N> {
+    type Error = Error;
+
+    /// Like the [`LeapSecs`][] implementation, this checks for expiry
+    /// against the [`SystemClock`][] when the `std` feature is
+    /// enabled, and otherwise does not check.
+    ///
+    fn try_from(bytes: &[u8]) -> Result<Self> {
+        #[cfg(feature = "std")]
+        {
+            Self::try_from_bytes_at(bytes, SystemClock)
+        }
+        #[cfg(not(feature = "std"))]
+        {
+            Self::try_from_bytes_lenient(bytes)
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod test {
+    use crate::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test() {
+        let text = history!("999?");
+        let list = LeapSecs::from_str(text).unwrap();
+        let bytes = Vec::<u8>::from(&list);
+        let fixed = LeapSecsFixed::<40>::try_from(&bytes[..]).unwrap();
+        assert!(list.iter().eq(fixed.iter()));
+        for year in 1970..2030 {
+            let date = Gregorian(year, 3, 1);
+            let want = list.dtai_at(date).ok();
+            assert_eq!(want, fixed.dtai_at(date).ok());
+        }
+        // still valid on the expiry date
+        for &delta in &[-1, 0, 1] {
+            let date = Gregorian::from(list.expires() + delta);
+            let want = list.dtai_at(date);
+            let got = fixed.dtai_at(date);
+            assert_eq!(format!("{:?}", want), format!("{:?}", got), "{}", date);
+            assert_eq!(got.is_err(), delta > 0, "{}", date);
+        }
+        let early = Gregorian(1971, 12, 31);
+        assert!(matches!(fixed.dtai_at(early), Err(Error::Uncovered(_))));
+        let exact = LeapSecsFixed::<29>::try_from(&bytes[..]).unwrap();
+        assert_eq!(fixed, exact);
+        assert!(matches!(
+            LeapSecsFixed::<28>::try_from(&bytes[..]),
+            Err(Error::Full(28))
+        ));
+        assert!(matches!(
+            LeapSecsFixed::<40>::try_from(&bytes[..bytes.len() - 1]),
+            Err(Error::Truncated)
+        ));
+
+        let binary: &[u8] = b"\x00\x11\x11\x11\x12\x11\x34\x31\
+                              \x21\x12\x22\x9D\x56\x52\x7F";
+        let today = Gregorian(2021, 1, 1).mjd();
+        let old = LeapSecsFixed::<40>::try_from_bytes_lenient(binary).unwrap();
+        assert!(old.is_expired_at(MJD::today()));
+        assert!(!old.is_expired_at(today));
+        assert!(LeapSecsFixed::<40>::try_from(binary).is_err());
+    }
+}
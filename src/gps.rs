@@ -0,0 +1,191 @@
+//! GPS time
+//! ========
+//!
+//! GPS time counts seconds since the [`GPS_EPOCH`][] without leap
+//! seconds, so it is a fixed [`TAI_MINUS_GPS`][] seconds behind TAI,
+//! and GPS − UTC is DTAI − 19. Receivers and almanacs give GPS time
+//! as a [`GpsTime`][] week number and seconds of the week. These
+//! helpers convert between that and UTC using a leap second list, for
+//! example,
+//!
+//!     # use leapsecs::*;
+//!     # use std::str::FromStr;
+//!     let list = LeapSecs::from_str("6+6+12+12+12+12+12+12+12+\
+//!         18+12+12+24+30+24+12+18+12+12+18+18+18+84+36+42+36+18+999?")?;
+//!     let gps = gps::GpsTime { week: 1930, tow: 18 };
+//!     assert_eq!(list.gps_to_unix(gps)?, Unix(1_483_228_800));
+//!     assert_eq!(list.unix_to_gps(Unix(1_483_228_800))?, gps);
+//!     assert_eq!(list.gps_utc_offset_at(Gregorian(2017, 1, 1).mjd())?, 18);
+//!     # Ok::<(), Error>(())
+//!
+//! Timestamps are POSIX time, in seconds since 1970 not counting leap
+//! seconds, like [`Unix`][]. A GPS time during a positive leap second
+//! has no POSIX time of its own, so it converts to the first second of
+//! the following day, the same as the next GPS second. A negative leap
+//! second removes the last POSIX second of the day, which GPS times
+//! never convert to.
+
+use crate::*;
+use core::convert::TryFrom;
+
+/// TAI − GPS, which has been fixed since the [`GPS_EPOCH`][]
+pub const TAI_MINUS_GPS: i16 = 19;
+
+/// The length of a GPS week in seconds
+pub const WEEK: i64 = 7 * 86400;
+
+// broadcast week numbers roll over after this many weeks
+const ROLLOVER: i64 = 1024;
+
+// POSIX timestamp of the GPS epoch
+fn epoch() -> i64 {
+    Unix::from(GPS_EPOCH).0
+}
+
+/// A GPS time as a week number and seconds of the week
+#[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct GpsTime {
+    /// Weeks since the [`GPS_EPOCH`][], counting from 0 and without
+    /// rolling over
+    pub week: u32,
+    /// Time of week: seconds since the start of Sunday, less than
+    /// [`WEEK`][]
+    pub tow: u32,
+}
+
+impl GpsTime {
+    /// Get the number of seconds since the [`GPS_EPOCH`][]
+    pub fn seconds(self) -> i64 {
+        self.week as i64 * WEEK + self.tow as i64
+    }
+
+    /// Get the GPS time that is `seconds` after the [`GPS_EPOCH`][].
+    ///
+    /// Fails with [`Error::FromInt`][] if `seconds` is negative.
+    ///
+    pub fn from_seconds(seconds: i64) -> Result<GpsTime> {
+        let week = u32::try_from(seconds.div_euclid(WEEK))?;
+        let tow = seconds.rem_euclid(WEEK) as u32;
+        Ok(GpsTime { week, tow })
+    }
+
+    /// Get the GPS time for a week number as broadcast by satellites,
+    /// which is 10 bits and rolls over every 1024 weeks (about 19.6
+    /// years), choosing the full week number nearest to the date
+    /// `near`.
+    ///
+    ///     # use leapsecs::*;
+    ///     let near = Gregorian(2020, 1, 1).mjd();
+    ///     let gps = gps::GpsTime::from_broadcast(1930 % 1024, 18, near);
+    ///     assert_eq!(gps.week, 1930);
+    ///
+    pub fn from_broadcast(week: u16, tow: u32, near: MJD) -> GpsTime {
+        let near = (near - GPS_EPOCH) as i64 / 7;
+        let week = week as i64 % ROLLOVER;
+        let era = (near - week + ROLLOVER / 2).div_euclid(ROLLOVER);
+        let week = (era * ROLLOVER + week).max(week) as u32;
+        GpsTime { week, tow }
+    }
+}
+
+impl LeapSecs {
+    /// Get GPS − UTC on a date, which is [`LeapSecs::dtai_at()`][]
+    /// minus [`TAI_MINUS_GPS`][].
+    ///
+    /// Before 1980 this is negative, though GPS time did not exist.
+    ///
+    pub fn gps_utc_offset_at(&self, mjd: MJD) -> Result<i16> {
+        Ok(self.dtai_at(Gregorian::from(mjd))? - TAI_MINUS_GPS)
+    }
+
+    // GPS − UTC at a POSIX timestamp
+    fn gps_offset(&self, unix: i64) -> Result<i64> {
+        Ok(self.gps_utc_offset_at(MJD::try_from(Unix(unix))?)? as i64)
+    }
+
+    /// Convert a GPS time to a POSIX timestamp.
+    ///
+    /// Fails like [`LeapSecs::dtai_at()`][] if the list does not cover
+    /// the time.
+    ///
+    pub fn gps_to_unix(&self, gps: GpsTime) -> Result<Unix> {
+        let time = epoch() + gps.seconds();
+        // the offset on the GPS date is close enough to find the UTC
+        // date, except at the very end of the list
+        let guess =
+            self.gps_offset(time).or_else(|_| self.gps_offset(time - 86400))?;
+        let offset = self.gps_offset(time - guess)?;
+        let unix = time - offset;
+        // the second before a negative leap second does not exist
+        match self.gps_offset(unix + 1) {
+            Ok(next) if next < offset => Ok(Unix(unix + 1)),
+            _ => Ok(Unix(unix)),
+        }
+    }
+
+    /// Convert a POSIX timestamp to GPS time.
+    ///
+    /// Fails like [`LeapSecs::dtai_at()`][] if the list does not cover
+    /// the time, or with [`Error::FromInt`][] if it is before the
+    /// [`GPS_EPOCH`][].
+    ///
+    pub fn unix_to_gps(&self, unix: Unix) -> Result<GpsTime> {
+        let offset = self.gps_offset(unix.0)?;
+        GpsTime::from_seconds(unix.0 - epoch() + offset)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod test {
+    use crate::gps::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test() {
+        let text = history!("999?");
+        let list = LeapSecs::from_str(text).unwrap();
+        assert_eq!(315_964_800, epoch());
+        let start = GpsTime { week: 0, tow: 0 };
+        assert_eq!(Unix(epoch()), list.gps_to_unix(start).unwrap());
+        assert_eq!(start, list.unix_to_gps(Unix(epoch())).unwrap());
+        assert!(list.unix_to_gps(Unix(epoch() - 1)).is_err());
+
+        // around the leap second at the end of 2016
+        let midnight = 1_483_228_800;
+        for (tow, unix) in &[(16, -1), (17, 0), (18, 0), (19, 1)] {
+            let gps = GpsTime { week: 1930, tow: *tow };
+            let got = list.gps_to_unix(gps).unwrap();
+            assert_eq!(Unix(midnight + unix), got, "tow {}", tow);
+            if *tow != 17 {
+                assert_eq!(gps, list.unix_to_gps(got).unwrap());
+            }
+        }
+        let date = Gregorian(2016, 12, 31).mjd();
+        assert_eq!(17, list.gps_utc_offset_at(date).unwrap());
+        assert_eq!(-9, list.gps_utc_offset_at(UTC_START_1972).unwrap());
+
+        // a negative leap second skips 23:59:59
+        let text = "6+6+12+12+12+12+12+12+12+18-999?";
+        let list = LeapSecs::from_str(text).unwrap();
+        let midnight = Unix::from(Gregorian(1981, 7, 1)).0;
+        let gps = GpsTime::from_seconds(midnight - epoch() - 1).unwrap();
+        let got = list.gps_to_unix(GpsTime { tow: gps.tow - 1, ..gps });
+        assert_eq!(Unix(midnight), list.gps_to_unix(gps).unwrap());
+        assert_eq!(Unix(midnight - 2), got.unwrap());
+
+        let near = Gregorian(2000, 1, 1).mjd();
+        assert_eq!(1023, GpsTime::from_broadcast(1023, 0, near).week);
+        assert_eq!(1024, GpsTime::from_broadcast(0, 0, near).week);
+        assert_eq!(5, GpsTime::from_broadcast(5, 0, GPS_EPOCH).week);
+
+        // far outside the list, however the time is represented
+        let far = (i64::from(i32::MAX) - 40587) * 86400 - epoch();
+        let last = i64::from(u32::MAX) * WEEK + WEEK - 1;
+        for &secs in &[far, far + 86400, last] {
+            let gps = GpsTime::from_seconds(secs).unwrap();
+            assert!(list.gps_to_unix(gps).is_err(), "{}", secs);
+        }
+        assert!(list.unix_to_gps(Unix(i64::MAX)).is_err());
+        assert!(list.unix_to_gps(Unix(i64::MIN)).is_err());
+    }
+}
@@ -0,0 +1,186 @@
+//! Conversions to and from other date and time crates
+//! ==================================================
+//!
+//! With the `chrono` feature, [`Gregorian`][] and [`MJD`][] convert
+//! to and from [`chrono::NaiveDate`][], and [`ToUtcDate`][] is
+//! implemented for `chrono::NaiveDateTime` and `chrono::DateTime`.
+//!
+//! With the `time` feature, [`Gregorian`][] and [`MJD`][] convert
+//! to and from [`time::Date`][], and [`ToUtcDate`][] is implemented
+//! for `time::PrimitiveDateTime` and `time::OffsetDateTime`.
+//!
+//! Conversions into the other crates' types can fail with
+//! [`Error::Date`][] if the date is out of their range.
+
+use crate::*;
+
+/// A date and time that can tell what the date is in UTC, for
+/// looking up DTAI with [`LeapSecs::dtai_at_datetime()`][].
+///
+/// Date-times without a time zone are assumed to be UTC.
+///
+pub trait ToUtcDate {
+    /// Get the date in UTC
+    fn utc_date(&self) -> Gregorian;
+}
+
+#[cfg(feature = "alloc")]
+impl LeapSecs {
+    /// Get the difference between UTC and TAI at a particular
+    /// date and time, like [`LeapSecs::dtai_at()`][].
+    ///
+    /// This requires the `chrono` or `time` feature.
+    ///
+    pub fn dtai_at_datetime<D: ToUtcDate>(&self, datetime: &D) -> Result<i16> {
+        self.dtai_at(datetime.utc_date())
+    }
+}
+
+//     _
+//  __| |_  _ _ ___ _ _  ___
+// / _| ' \| '_/ _ \ ' \/ _ \
+// \__|_||_|_| \___/_||_\___/
+
+#[cfg(feature = "chrono")]
+mod with_chrono {
+    use crate::*;
+    use chrono::{Datelike, NaiveDate, NaiveDateTime, TimeZone};
+    use core::convert::TryFrom;
+
+    impl From<NaiveDate> for Gregorian {
+        fn from(date: NaiveDate) -> Gregorian {
+            Gregorian(date.year(), date.month() as i32, date.day() as i32)
+        }
+    }
+
+    impl From<NaiveDate> for MJD {
+        fn from(date: NaiveDate) -> MJD {
+            Gregorian::from(date).mjd()
+        }
+    }
+
+    impl TryFrom<Gregorian> for NaiveDate {
+        type Error = Error;
+        fn try_from(date: Gregorian) -> Result<NaiveDate> {
+            let (m, d) = (u32::try_from(date.1)?, u32::try_from(date.2)?);
+            NaiveDate::from_ymd_opt(date.0, m, d).ok_or(Error::Date(date))
+        }
+    }
+
+    impl TryFrom<MJD> for NaiveDate {
+        type Error = Error;
+        fn try_from(mjd: MJD) -> Result<NaiveDate> {
+            NaiveDate::try_from(Gregorian::from(mjd))
+        }
+    }
+
+    impl ToUtcDate for NaiveDateTime {
+        fn utc_date(&self) -> Gregorian {
+            Gregorian::from(self.date())
+        }
+    }
+
+    impl<Tz: TimeZone> ToUtcDate for chrono::DateTime<Tz> {
+        fn utc_date(&self) -> Gregorian {
+            Gregorian::from(self.naive_utc().date())
+        }
+    }
+}
+
+//  _   _
+// | |_(_)_ __  ___
+// |  _| | '  \/ -_)
+//  \__|_|_|_|_\___|
+
+#[cfg(feature = "time")]
+mod with_time {
+    use crate::*;
+    use core::convert::TryFrom;
+    use time::{Date, Month, OffsetDateTime, PrimitiveDateTime, UtcOffset};
+
+    impl From<Date> for Gregorian {
+        fn from(date: Date) -> Gregorian {
+            Gregorian(date.year(), date.month() as i32, date.day() as i32)
+        }
+    }
+
+    impl From<Date> for MJD {
+        fn from(date: Date) -> MJD {
+            Gregorian::from(date).mjd()
+        }
+    }
+
+    impl TryFrom<Gregorian> for Date {
+        type Error = Error;
+        fn try_from(date: Gregorian) -> Result<Date> {
+            let invalid = |_| Error::Date(date);
+            let m = u8::try_from(date.1)?;
+            let d = u8::try_from(date.2)?;
+            let m = Month::try_from(m).map_err(invalid)?;
+            Date::from_calendar_date(date.0, m, d).map_err(invalid)
+        }
+    }
+
+    impl TryFrom<MJD> for Date {
+        type Error = Error;
+        fn try_from(mjd: MJD) -> Result<Date> {
+            Date::try_from(Gregorian::from(mjd))
+        }
+    }
+
+    impl ToUtcDate for PrimitiveDateTime {
+        fn utc_date(&self) -> Gregorian {
+            Gregorian::from(self.date())
+        }
+    }
+
+    impl ToUtcDate for OffsetDateTime {
+        fn utc_date(&self) -> Gregorian {
+            Gregorian::from(self.to_offset(UtcOffset::UTC).date())
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+    use core::convert::TryFrom;
+    use std::str::FromStr;
+
+    const TEXT: &str = history!("999?");
+
+    #[test]
+    #[cfg(feature = "chrono")]
+    fn chrono() {
+        use chrono::{FixedOffset, NaiveDate, TimeZone};
+        let naive = NaiveDate::from_ymd_opt(2016, 12, 31).unwrap();
+        assert_eq!(Gregorian(2016, 12, 31), Gregorian::from(naive));
+        assert_eq!(naive, NaiveDate::try_from(MJD::from(naive)).unwrap());
+        assert!(NaiveDate::try_from(Gregorian(2016, 2, 30)).is_err());
+
+        let list = LeapSecs::from_str(TEXT).unwrap();
+        let utc = naive.and_hms_opt(23, 0, 0).unwrap();
+        assert_eq!(36, list.dtai_at_datetime(&utc).unwrap());
+        let east = FixedOffset::east_opt(3600).unwrap();
+        let local =
+            east.from_local_datetime(&(utc + chrono::Duration::hours(2)));
+        assert_eq!(37, list.dtai_at_datetime(&local.unwrap()).unwrap());
+    }
+
+    #[test]
+    #[cfg(feature = "time")]
+    fn time() {
+        use time::{Date, Month, Time, UtcOffset};
+        let date = Date::from_calendar_date(2016, Month::December, 31).unwrap();
+        assert_eq!(Gregorian(2016, 12, 31), Gregorian::from(date));
+        assert_eq!(date, Date::try_from(MJD::from(date)).unwrap());
+        assert!(Date::try_from(Gregorian(2016, 13, 1)).is_err());
+
+        let list = LeapSecs::from_str(TEXT).unwrap();
+        let utc = date.with_time(Time::from_hms(23, 0, 0).unwrap());
+        assert_eq!(36, list.dtai_at_datetime(&utc).unwrap());
+        let west = UtcOffset::from_hms(-2, 0, 0).unwrap();
+        let local = utc.assume_offset(west);
+        assert_eq!(37, list.dtai_at_datetime(&local).unwrap());
+    }
+}
@@ -0,0 +1,142 @@
+//! JSON format for the leap second list
+//! ====================================
+//!
+//! This is for monitoring dashboards and web services that would
+//! rather not parse the NIST or compact formats. A list looks like,
+//!
+//! ```json
+//! {
+//!   "leap_seconds": [
+//!     { "date": "1972-01-01", "dtai": 10, "sign": 0 },
+//!     { "date": "1972-07-01", "dtai": 11, "sign": 1 },
+//!     { "date": "1973-01-01", "dtai": 12, "sign": 1 }
+//!   ],
+//!   "expires": "1973-06-28",
+//!   "updated": "1972-12-01"
+//! }
+//! ```
+//!
+//! The `leap_seconds` array starts with the beginning of UTC in 1972,
+//! which has a `sign` of 0. Each following entry has the `date` after
+//! the leap second, when the new `dtai` takes effect, and a `sign` of
+//! 1 or -1.
+//!
+//! The `expires` date is the last day on which the list is valid.
+//! The `updated` date is optional, and corresponds to
+//! [`LeapSecs::updated()`][].
+//!
+//! Dates are in ISO 8601 `YYYY-MM-DD` format. Other fields are
+//! ignored, and whitespace is not significant.
+//!
+//! Requires the `json` feature.
+
+use crate::*;
+use alloc::format;
+use alloc::string::{String, ToString};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize)]
+struct Json {
+    leap_seconds: Vec<Entry>,
+    expires: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    updated: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Entry {
+    date: String,
+    dtai: i16,
+    sign: i8,
+}
+
+pub(crate) fn parse_date(s: &str) -> Result<Gregorian> {
+    let mut parts = s.splitn(3, '-').map(|part| part.parse().ok());
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some(Some(y)), Some(Some(m)), Some(Some(d))) if s.len() == 10 => {
+            Ok(Gregorian(y, m, d))
+        }
+        _ => Err(Error::Json(format!("invalid date {:?}", s))),
+    }
+}
+
+/// Convert a list to JSON.
+///
+pub fn to_string(list: &LeapSecs) -> Result<String> {
+    let leap_seconds = list
+        .iter()
+        .filter_map(|leap| {
+            let sign = match leap.sign() {
+                Leap::Zero => 0,
+                Leap::Neg => -1,
+                Leap::Pos => 1,
+                Leap::Exp => return None,
+            };
+            let date = leap.date().to_string();
+            Some(leap.dtai().map(|dtai| Entry { date, dtai, sign }))
+        })
+        .collect::<Result<_>>()?;
+    let json = Json {
+        leap_seconds,
+        expires: Gregorian::from(list.expires()).to_string(),
+        updated: list.updated().map(|mjd| Gregorian::from(mjd).to_string()),
+    };
+    serde_json::to_string_pretty(&json).map_err(|e| Error::Json(e.to_string()))
+}
+
+/// Parse a list from JSON.
+///
+/// The list is checked in the same way as
+/// [`LeapSecBuilder::finish()`][], and each `sign` must match the
+/// change in `dtai`.
+///
+pub fn from_str(text: &str) -> Result<LeapSecs> {
+    let json: Json =
+        serde_json::from_str(text).map_err(|e| Error::Json(e.to_string()))?;
+    let mut builder = LeapSecs::builder();
+    let mut dtai = None;
+    for entry in &json.leap_seconds {
+        let date = parse_date(&entry.date)?;
+        let sign = dtai.map_or(0, |prev| entry.dtai - prev);
+        if sign != entry.sign as i16 {
+            let msg = format!("wrong sign {} at {}", entry.sign, date);
+            return Err(Error::Json(msg));
+        }
+        builder.push_date(date, entry.dtai)?;
+        dtai = Some(entry.dtai);
+    }
+    builder.push_exp(parse_date(&json.expires)?)?;
+    let mut list = builder.finish()?.with_literal(Format::Json);
+    if let Some(date) = json.updated {
+        list.updated = Some(parse_date(&date)?.mjd());
+    }
+    Ok(list)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test() {
+        let text = history!("999?");
+        let list = LeapSecs::from_str(text).unwrap();
+        let output = json::to_string(&list).unwrap();
+        assert!(!output.contains("updated"));
+        assert_eq!(list, json::from_str(&output).unwrap());
+        let updated = Gregorian(2021, 1, 7).mjd();
+        let list = list.with_updated(updated);
+        let output = json::to_string(&list).unwrap();
+        assert!(output.contains(r#""date": "2017-01-01","#));
+        let parsed = json::from_str(&output).unwrap();
+        assert_eq!((&list, Some(updated)), (&parsed, parsed.updated()));
+
+        let bad = output.replacen(r#""sign": 1"#, r#""sign": -1"#, 1);
+        assert!(matches!(json::from_str(&bad), Err(Error::Json(_))));
+        let bad = output.replacen("1972-07-01", "1972-07-1", 1);
+        assert!(matches!(json::from_str(&bad), Err(Error::Json(_))));
+        let bad = output.replacen("1972-07-01", "1972-07-02", 1);
+        assert!(matches!(json::from_str(&bad), Err(Error::MonthDay(..))));
+    }
+}
@@ -0,0 +1,314 @@
+//! Localized error messages
+//! ========================
+//!
+//! The [`Display`][core::fmt::Display] implementation for
+//! [`enum@Error`][] produces English messages. Applications with
+//! other languages can instead look up a message template by the
+//! error's [`ErrorKind`][], and fill in the error's fields, for
+//! example,
+//!
+//!     # use leapsecs::*;
+//!     # use leapsecs::l10n::*;
+//!     # use core::convert::TryFrom;
+//!     let err = LeapSecs::try_from(&b"\x00"[..]).unwrap_err();
+//!     let msg = localize(&err, |kind| match kind {
+//!         ErrorKind::Truncated => Some("il manque la date d'expiration"),
+//!         _ => None,
+//!     });
+//!     assert_eq!(msg, "il manque la date d'expiration");
+//!
+//!     let err = Error::Expired(Gregorian(2021, 12, 28));
+//!     let msg = localize(&err, |kind| match kind {
+//!         ErrorKind::Expired => Some("la liste a expiré le {0}"),
+//!         _ => None,
+//!     });
+//!     assert_eq!(msg, "la liste a expiré le 2021-12-28");
+//!
+//! In a template, `{0}`, `{1}`, and so on are replaced by the
+//! error's fields, in the same order as in the [`enum@Error`][]
+//! variant, formatted in the same way as in the English message.
+//! `{{` and `}}` are literal braces. When there is no template for
+//! an error, the English message is used.
+//!
+//! This requires the `l10n` feature.
+
+use crate::*;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use core::fmt::Write;
+
+/// The kinds of [`enum@Error`][], without their fields.
+///
+/// There is one kind for each variant of [`enum@Error`][], with the
+/// same name.
+///
+#[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum ErrorKind {
+    Archive,
+    BaseN,
+    BinVersion,
+    Bulletin,
+    #[cfg(feature = "std")]
+    Checksum,
+    #[cfg(feature = "signed")]
+    Cose,
+    #[cfg(feature = "std")]
+    Csv,
+    Date,
+    Empty,
+    Expired,
+    FalseStart,
+    Format,
+    FromInt,
+    FromStr,
+    Full,
+    Gap,
+    History,
+    #[cfg(feature = "std")]
+    Io,
+    #[cfg(feature = "json")]
+    Json,
+    LeapAfterExp,
+    Lossy,
+    Midnight,
+    MonthDay,
+    #[cfg(feature = "std")]
+    Nom,
+    NonCanonical,
+    NotLeap,
+    #[cfg(feature = "std")]
+    NtpdWarning,
+    #[cfg(feature = "pgp")]
+    Pgp,
+    Policy,
+    #[cfg(feature = "qr")]
+    QrCode,
+    RData,
+    #[cfg(feature = "pre1972")]
+    RubberEra,
+    #[cfg(any(feature = "pgp", feature = "signed"))]
+    Signature,
+    TextChecksum,
+    TimeDate,
+    TooEarly,
+    Txt,
+    Truncated,
+    Tzif,
+    Uncovered,
+    Unicode,
+    WrongLeap,
+}
+
+impl Error {
+    /// Get the kind of error, for looking up a localized message
+    ///
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Error::Archive(..) => ErrorKind::Archive,
+            Error::BaseN(..) => ErrorKind::BaseN,
+            Error::BinVersion(..) => ErrorKind::BinVersion,
+            Error::Bulletin(..) => ErrorKind::Bulletin,
+            #[cfg(feature = "std")]
+            Error::Checksum(..) => ErrorKind::Checksum,
+            #[cfg(feature = "signed")]
+            Error::Cose(..) => ErrorKind::Cose,
+            #[cfg(feature = "std")]
+            Error::Csv(..) => ErrorKind::Csv,
+            Error::Date(..) => ErrorKind::Date,
+            Error::Empty => ErrorKind::Empty,
+            Error::Expired(..) => ErrorKind::Expired,
+            Error::FalseStart(..) => ErrorKind::FalseStart,
+            Error::Format(..) => ErrorKind::Format,
+            Error::FromInt(..) => ErrorKind::FromInt,
+            Error::FromStr(..) => ErrorKind::FromStr,
+            Error::Full(..) => ErrorKind::Full,
+            Error::Gap(..) => ErrorKind::Gap,
+            Error::History(..) => ErrorKind::History,
+            #[cfg(feature = "std")]
+            Error::Io(..) => ErrorKind::Io,
+            #[cfg(feature = "json")]
+            Error::Json(..) => ErrorKind::Json,
+            Error::LeapAfterExp(..) => ErrorKind::LeapAfterExp,
+            Error::Lossy(..) => ErrorKind::Lossy,
+            Error::Midnight(..) => ErrorKind::Midnight,
+            Error::MonthDay(..) => ErrorKind::MonthDay,
+            #[cfg(feature = "std")]
+            Error::Nom(..) => ErrorKind::Nom,
+            Error::NonCanonical => ErrorKind::NonCanonical,
+            Error::NotLeap(..) => ErrorKind::NotLeap,
+            #[cfg(feature = "std")]
+            Error::NtpdWarning(..) => ErrorKind::NtpdWarning,
+            #[cfg(feature = "pgp")]
+            Error::Pgp(..) => ErrorKind::Pgp,
+            Error::Policy(..) => ErrorKind::Policy,
+            #[cfg(feature = "qr")]
+            Error::QrCode(..) => ErrorKind::QrCode,
+            Error::RData(..) => ErrorKind::RData,
+            #[cfg(feature = "pre1972")]
+            Error::RubberEra(..) => ErrorKind::RubberEra,
+            #[cfg(any(feature = "pgp", feature = "signed"))]
+            Error::Signature => ErrorKind::Signature,
+            Error::TextChecksum(..) => ErrorKind::TextChecksum,
+            Error::TimeDate(..) => ErrorKind::TimeDate,
+            Error::TooEarly(..) => ErrorKind::TooEarly,
+            Error::Txt(..) => ErrorKind::Txt,
+            Error::Truncated => ErrorKind::Truncated,
+            Error::Tzif(..) => ErrorKind::Tzif,
+            Error::Uncovered(..) => ErrorKind::Uncovered,
+            Error::Unicode(..) => ErrorKind::Unicode,
+            Error::WrongLeap(..) => ErrorKind::WrongLeap,
+        }
+    }
+
+    /// Get the error's fields as strings, for filling in a localized
+    /// message template. See [`localize()`][].
+    ///
+    pub fn fields(&self) -> Vec<String> {
+        fn s<T: ToString>(field: T) -> String {
+            field.to_string()
+        }
+        match self {
+            Error::Archive(a) => vec![s(a)],
+            Error::BaseN(c) => vec![format!("{:?}", c)],
+            Error::BinVersion(version) => vec![version.to_string()],
+            Error::Bulletin(a) => vec![s(a)],
+            #[cfg(feature = "std")]
+            Error::Checksum(a, b, c) => vec![s(a), s(b), s(c)],
+            #[cfg(feature = "signed")]
+            Error::Cose(a) => vec![s(a)],
+            #[cfg(feature = "std")]
+            Error::Csv(a, b) => vec![s(a), s(b)],
+            Error::Date(a) => vec![s(a)],
+            Error::Empty => vec![],
+            Error::Expired(a) => vec![s(a)],
+            Error::FalseStart(a, b) => vec![s(a), s(b)],
+            Error::Format(a) => vec![s(a)],
+            Error::FromInt(a) => vec![s(a)],
+            Error::FromStr(a, b) => vec![s(a), s(b)],
+            Error::Full(a) => vec![s(a)],
+            Error::Gap(a, b, c) => vec![s(a), s(b), s(c)],
+            Error::History(a, b) => vec![s(a), s(b)],
+            #[cfg(feature = "std")]
+            Error::Io(a) => vec![s(a)],
+            #[cfg(feature = "json")]
+            Error::Json(a) => vec![s(a)],
+            Error::LeapAfterExp(a, b) => vec![s(a), s(b)],
+            Error::Lossy(a, b) => vec![format!("{:?}", a), s(b)],
+            Error::Midnight(a, b, c) => vec![s(a), s(b), s(c)],
+            Error::MonthDay(a, b) => vec![s(a), s(b)],
+            #[cfg(feature = "std")]
+            Error::Nom(a) => vec![s(a)],
+            Error::NonCanonical => vec![],
+            Error::NotLeap(a, b) => vec![s(a), format!("{:?}", b)],
+            #[cfg(feature = "std")]
+            Error::NtpdWarning(a, b) => vec![s(a), s(b)],
+            #[cfg(feature = "pgp")]
+            Error::Pgp(a) => vec![s(a)],
+            Error::Policy(a) => vec![s(a)],
+            #[cfg(feature = "qr")]
+            Error::QrCode(a) => vec![s(a)],
+            Error::RData(a) => vec![s(a)],
+            #[cfg(feature = "pre1972")]
+            Error::RubberEra(a) => vec![s(a)],
+            #[cfg(any(feature = "pgp", feature = "signed"))]
+            Error::Signature => vec![],
+            Error::TextChecksum(a, b) => {
+                vec![format!("{:04x}", a), format!("{:04x}", b)]
+            }
+            Error::TimeDate(a, b, c) => vec![s(a), s(b), s(c)],
+            Error::TooEarly(a) => vec![s(a)],
+            Error::Txt(a) => vec![s(a)],
+            Error::Truncated => vec![],
+            Error::Tzif(a) => vec![s(a)],
+            Error::Uncovered(a) => vec![
+                s(a.date),
+                s(a.first),
+                s(a.expires),
+                a.refresh.clone().unwrap_or_default(),
+            ],
+            Error::Unicode(a) => vec![s(a)],
+            Error::WrongLeap(a, b, c, d) => vec![s(a), s(b), s(c), s(d)],
+        }
+    }
+}
+
+/// Fill in a message template with an error's fields.
+///
+/// Placeholders that do not match a field are left as they are.
+///
+pub fn fill(template: &str, err: &Error) -> String {
+    let fields = err.fields();
+    let mut out = String::new();
+    let mut rest = template;
+    while let Some(i) = rest.find(['{', '}']) {
+        out.push_str(&rest[..i]);
+        rest = &rest[i..];
+        if rest.starts_with("{{") || rest.starts_with("}}") {
+            out.push_str(&rest[..1]);
+            rest = &rest[2..];
+            continue;
+        }
+        let field = match (rest.starts_with('{'), rest.find('}')) {
+            (true, Some(end)) => rest[1..end]
+                .parse::<usize>()
+                .ok()
+                .and_then(|n| fields.get(n))
+                .map(|field| (end, field)),
+            _ => None,
+        };
+        match field {
+            Some((end, field)) => {
+                out.push_str(field);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push_str(&rest[..1]);
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Get a localized message for an error, using a template from the
+/// `templates` function, or the English message if it returns
+/// [`None`][].
+///
+pub fn localize<'a, F>(err: &Error, templates: F) -> String
+where
+    F: FnOnce(ErrorKind) -> Option<&'a str>,
+{
+    match templates(err.kind()) {
+        Some(template) => fill(template, err),
+        None => {
+            let mut out = String::new();
+            // writing to a String cannot fail
+            let _ = write!(out, "{}", err);
+            out
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::l10n::*;
+
+    #[test]
+    fn test() {
+        let err = Error::WrongLeap(
+            Gregorian(1972, 7, 1),
+            11,
+            Gregorian(1973, 1, 1),
+            13,
+        );
+        assert_eq!(ErrorKind::WrongLeap, err.kind());
+        let msg = fill("{2}: {1} -> {3} {{{0}}} {4} {x", &err);
+        assert_eq!("1973-01-01: 11 -> 13 {1972-07-01} {4} {x", msg);
+        let msg = localize(&Error::TextChecksum(0x12, 0xab), |_| None);
+        assert_eq!("checksum failed 0012 <> 00ab", msg);
+        let msg = localize(&Error::TextChecksum(0x12, 0xab), |_| Some("{1}"));
+        assert_eq!("00ab", msg);
+    }
+}
@@ -20,6 +20,15 @@
 //!
 //!   * The NIST `leap-seconds.list` format, implemented by the [`nist`][] module.
 //!
+//! The [`dns`][] module wraps the compact binary format in DNS RDATA.
+//!
+//! The [`lookup`][] module converts timestamps in bulk, the [`gps`][]
+//! module converts between GPS time and UTC, and the [`scale`][]
+//! module gives the offsets between satellite time scales.
+//!
+//! The [`bulletin`][] module reads and applies IERS Bulletin C
+//! announcements of new leap seconds.
+//!
 //! The main interface is through the [`LeapSecs`][] type and the standard
 //! conversion traits that it implements. These are documented in the
 //! [`txt`][] and [`bin`][] modules.
@@ -30,22 +39,192 @@
 //!
 //! The [`enum@Error`][] type collects together the possible kinds of
 //! conversion failures.
+//!
+//! Cargo features
+//! --------------
+//!
+//!   * `fetch` (enabled by default) lets the [`nist`][] module read
+//!     lists from files and URLs and cache them, using `curl`. It
+//!     also provides the [`batch`][] module. It implies `std`.
+//!
+//!   * `std` provides the [`nist`][] module's parser and formatter,
+//!     which depend on `nom` and `ring`, and checks lists against
+//!     the system clock when they are constructed. It also provides
+//!     the [`csv`][], [`now`][], [`shared`][], and [`smear`][]
+//!     modules. It implies `alloc`. Without `fetch` it does no I/O,
+//!     so it does not need `curl` and its native build requirements.
+//!
+//!   * `alloc` provides [`LeapSecs`][], [`LeapSecBuilder`][], and the
+//!     [`txt`][], [`archive`][], [`dns`][], [`gps`][], [`lookup`][],
+//!     [`scale`][], and [`tzif`][] modules.
+//!
+//!   * `builtin` provides the [`builtin()`][] function, which returns
+//!     a copy of the leap second list that is compiled into the crate.
+//!     It implies `alloc`.
+//!
+//!   * `chrono` and `time` provide conversions between [`Gregorian`][]
+//!     or [`MJD`][] and the date types from those crates, and
+//!     [`LeapSecs::dtai_at_datetime()`][] for their date-time types.
+//!
+//!   * `ffi` provides the [`ffi`][] module, with C bindings for
+//!     the compact binary format. It implies `alloc`.
+//!
+//!   * `gzip` allows the [`nist`][] module to read compressed files
+//!     and HTTP responses. It implies `fetch`.
+//!
+//!   * `json` provides the [`json`][] module, for a JSON version of
+//!     the list. It implies `alloc`.
+//!
+//!   * `l10n` provides the [`l10n`][] module, for error messages in
+//!     languages other than English. It implies `alloc`.
+//!
+//!   * `log` makes the [`nist`][] module report what it is doing,
+//!     such as fetching a list, using a cached copy, or finding a
+//!     bad checksum, via the `log` crate. Without it the library
+//!     does not print anything. It has no effect without `std`.
+//!
+//!   * `mmap` provides [`archive::Mapped`][], for memory-mapping
+//!     a list archive at startup. It implies `std`.
+//!
+//!   * `pgp` provides the [`pgp`][] module, for checking OpenPGP
+//!     signatures on the NIST `leap-seconds.list`. It implies `std`.
+//!
+//!   * `pre1972` provides the [`pre1972`][] module, with the table
+//!     of fractional TAI − UTC offsets from 1961 to 1971.
+//!
+//!   * `python` provides the [`python`][] module, with Python
+//!     bindings made by `pyo3`. It implies `std`.
+//!
+//!   * `qr` provides the [`qr`][] module, for carrying lists in
+//!     compact binary format as QR codes. It implies `std`.
+//!
+//!   * `rayon` makes [`batch::validate_all()`][] read its sources
+//!     in parallel. It has no effect without `fetch`.
+//!
+//!   * `signed` provides the [`cose`][] module, for signed lists in
+//!     compact binary format. It implies `std`.
+//!
+//!   * `testing` provides the [`testing`][] module, with canned
+//!     lists for testing code that handles leap seconds. It implies
+//!     `std`.
+//!
+//!   * `wasm` provides the [`wasm`][] module, with JavaScript bindings
+//!     made by `wasm-bindgen`. It implies `alloc` but not `std`, so
+//!     that the crate can be built for `wasm32-unknown-unknown`.
+//!
+//! Without `std` the crate is `no_std`, so the compact text and
+//! binary formats can be used in firmware. Without `alloc` as well,
+//! [`LeapSecsFixed`][] can decode the compact binary format into a
+//! fixed-size array.
 
+#![cfg_attr(not(feature = "std"), no_std)]
 #![doc(
     html_logo_url = "https://raw.githubusercontent.com/fanf2/leapsecs/main/doc/logo.png"
 )]
 
-use std::convert::TryFrom;
-use std::ops::Index;
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+#[cfg(feature = "alloc")]
+use core::ops::Index;
 use thiserror::Error;
 
+// The leap seconds up to 2017 in the compact text format, followed by
+// the given gaps, such as "59?" for a list that expires at the end of
+// 2021, so that the tests and fixtures share one copy of the history.
+#[cfg(all(feature = "std", any(test, feature = "testing")))]
+macro_rules! history {
+    ($gaps:literal) => {
+        concat!(
+            "6+6+12+12+12+12+12+12+12+18+12+12+24+30+24+",
+            "12+18+12+12+18+18+18+84+36+42+36+18+",
+            $gaps
+        )
+    };
+}
+
+// a unique file name in the temporary directory, so that tests which
+// run at the same time, in this process or another, do not collide
+#[cfg(all(test, any(feature = "fetch", feature = "mmap")))]
+fn temp_path(name: &str) -> std::path::PathBuf {
+    use core::sync::atomic::{AtomicUsize, Ordering};
+    static COUNT: AtomicUsize = AtomicUsize::new(0);
+    let count = COUNT.fetch_add(1, Ordering::Relaxed);
+    let name = format!("leapsecs-{}-{}-{}", std::process::id(), count, name);
+    std::env::temp_dir().join(name)
+}
+
+#[cfg(feature = "alloc")]
+pub mod archive;
+#[cfg(feature = "fetch")]
+pub mod batch;
 pub mod bin;
+#[cfg(feature = "builtin")]
+mod builtin;
+#[cfg(feature = "alloc")]
+pub mod bulletin;
+#[cfg(feature = "signed")]
+pub mod cose;
+#[cfg(feature = "std")]
+pub mod csv;
 pub mod date;
+#[cfg(feature = "alloc")]
+pub mod dns;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+mod fixed;
+#[cfg(feature = "alloc")]
+pub mod gps;
+#[cfg(any(feature = "chrono", feature = "time"))]
+mod interop;
+#[cfg(feature = "json")]
+pub mod json;
+#[cfg(feature = "l10n")]
+pub mod l10n;
+#[cfg(feature = "alloc")]
+pub mod lookup;
+#[cfg(feature = "std")]
 pub mod nist;
+#[cfg(feature = "std")]
+pub mod now;
+#[cfg(feature = "pgp")]
+pub mod pgp;
+#[cfg(feature = "pre1972")]
+pub mod pre1972;
+#[cfg(feature = "python")]
+pub mod python;
+#[cfg(feature = "qr")]
+pub mod qr;
+#[cfg(feature = "alloc")]
+pub mod scale;
+#[cfg(feature = "std")]
+pub mod shared;
+#[cfg(feature = "std")]
+pub mod smear;
+#[cfg(feature = "testing")]
+pub mod testing;
+#[cfg(feature = "alloc")]
 pub mod txt;
+#[cfg(feature = "alloc")]
+pub mod tzif;
+#[cfg(feature = "wasm")]
+pub mod wasm;
 
+#[cfg(feature = "std")]
 use crate::nist::Hash;
+#[cfg(feature = "builtin")]
+pub use builtin::builtin;
+#[cfg(feature = "alloc")]
+pub use bulletin::Announcement;
 pub use date::*;
+pub use fixed::LeapSecsFixed;
+#[cfg(any(feature = "chrono", feature = "time"))]
+pub use interop::ToUtcDate;
+#[cfg(feature = "std")]
+pub use shared::SharedLeapSecs;
 
 //  ___             _ _       ___
 // | _ \___ ____  _| | |_    | __|_ _ _ _ ___ _ _
@@ -56,7 +235,7 @@ pub use date::*;
 /// A specialized [`Result`][] type to avoid writing out
 /// [`leapsecs::Error`][enum@Error].
 ///
-pub type Result<T> = std::result::Result<T, Error>;
+pub type Result<T> = core::result::Result<T, Error>;
 
 /// The error type for leap seconds.
 ///
@@ -67,11 +246,40 @@ pub type Result<T> = std::result::Result<T, Error>;
 /// use `anyhow::Result` because those functions are more
 /// application-oriented.
 ///
+/// The variants that only the [`nist`][] module can produce are not
+/// present without the `std` feature.
+///
 #[derive(Error, Debug)]
 pub enum Error {
+    /// Malformed or corrupt archive, see [`archive`][]
+    #[error("archive error: {0}")]
+    Archive(&'static str),
+    /// Invalid character in base32 or base64 text
+    #[error("invalid base32 or base64 character {0:?}")]
+    BaseN(char),
+    /// The compact binary format has a version marker that this
+    /// decoder does not support
+    #[error("unsupported compact binary format version {0}")]
+    BinVersion(u8),
+    /// An IERS Bulletin C announcement could not be parsed or applied
+    #[error("Bulletin C {0}")]
+    Bulletin(&'static str),
     /// The NIST `leap-seconds.list` checksum did not match.
+    #[cfg(feature = "std")]
     #[error("checksum failed {0} <> {1} data {2}")]
     Checksum(Hash, Hash, String),
+    /// A COSE_Sign1 envelope is malformed or unsupported
+    #[cfg(feature = "signed")]
+    #[error("COSE error: {0}")]
+    Cose(&'static str),
+    /// A row of comma-separated values is malformed, with its line
+    /// number and what is wrong with it
+    #[cfg(feature = "std")]
+    #[error("CSV line {0}: {1}")]
+    Csv(usize, &'static str),
+    /// A date that cannot be represented by another crate's date type
+    #[error("invalid date {0}")]
+    Date(Gregorian),
     /// Attempted to create an empty list
     #[error("leap seconds list is empty")]
     Empty,
@@ -83,19 +291,38 @@ pub enum Error {
     FalseStart(Gregorian, i16),
     /// An error occurred when converting `LeapSecs` to a string
     #[error("format error {0}")]
-    Format(#[from] std::fmt::Error),
+    Format(#[from] core::fmt::Error),
     /// We encountered a date in the distant past or future
     #[error("overflow in date arithmetic")]
-    FromInt(#[from] std::num::TryFromIntError),
-    /// Syntax error in the compact text format of the leap seconds list
+    FromInt(#[from] core::num::TryFromIntError),
+    /// Syntax error in a date, a [`LeapSec`][], or a hash
     #[error("expected {0}, found {1}")]
     FromStr(&'static str, char),
-    /// The leap seconds list is out of order or excessively spaced out
-    #[error("gap must be between 1 and 999 months")]
+    /// A [`LeapSecsFixed`][] list has no room for more entries
+    #[error("leap seconds list is longer than {0} entries")]
+    Full(usize),
+    /// The leap seconds list is out of order or excessively spaced
+    /// out, see [`LeapSecBuilder::with_max_gap()`][]
+    #[error("gap of {1} months from {0} to {2} is out of range")]
     Gap(Gregorian, i32, Gregorian),
+    /// A list disagrees with the history in a pinned list, see
+    /// [`LeapSecs::verify_history()`][]
+    #[error("history differs from pinned list ({0} <> {1})")]
+    History(LeapSec, LeapSec),
+    /// An IO error when reading or writing a list
+    #[cfg(feature = "std")]
+    #[error("{0}")]
+    Io(#[from] std::io::Error),
+    /// Malformed or inconsistent JSON
+    #[cfg(feature = "json")]
+    #[error("JSON error: {0}")]
+    Json(alloc::string::String),
     /// There can't be any leap seconds after the list's expiry date
     #[error("can't add more leap seconds after expiry time ({0})")]
     LeapAfterExp(Gregorian, Gregorian),
+    /// A conversion would not preserve everything about a list
+    #[error("{0:?} format cannot represent the {1}")]
+    Lossy(Format, &'static str),
     /// Timestamps in the NIST `leap-seconds.list` should be at midnight
     #[error("time is not midnight (NTP {0} is {1} + {2})")]
     Midnight(i64, MJD, i32),
@@ -104,17 +331,74 @@ pub enum Error {
     #[error("date {0} is not {1} of month")]
     MonthDay(Gregorian, i32),
     /// Syntax error in the NIST `leap-seconds.list`
+    #[cfg(feature = "std")]
     #[error("parse error {0}")]
     Nom(String),
+    /// The compact text format was not in canonical form, see
+    /// [`txt::is_canonical()`][]
+    #[error("text is not in canonical form")]
+    NonCanonical,
+    /// A hypothetical leap second must be [`Leap::Pos`][] or
+    /// [`Leap::Neg`][], see [`LeapSecs::with_hypothetical()`][]
+    #[error("{0} {1:?} is not a leap second")]
+    NotLeap(Gregorian, Leap),
+    /// ntpd would warn that the list is about to expire, see
+    /// [`LeapSecs::render_ntpd_leapfile_header()`][]
+    #[cfg(feature = "std")]
+    #[error("updated {0} on or after ntpd expiry warning {1}")]
+    NtpdWarning(Gregorian, Gregorian),
+    /// An OpenPGP key or signature is malformed or unsupported
+    #[cfg(feature = "pgp")]
+    #[error("OpenPGP error: {0}")]
+    Pgp(&'static str),
+    /// An entry was rejected by a [`LeapSecBuilder::finish_with()`][]
+    /// validation callback
+    #[error("entry rejected by policy ({0})")]
+    Policy(LeapSec),
+    /// A QR code could not be made
+    #[cfg(feature = "qr")]
+    #[error("QR code error: {0}")]
+    QrCode(String),
+    /// Malformed or oversized DNS RDATA
+    #[error("DNS RDATA {0}")]
+    RData(&'static str),
+    /// A date outside the period from 1961 to 1971 covered by the
+    /// [`pre1972`][] table
+    #[cfg(feature = "pre1972")]
+    #[error("date {0} is not between 1961 and 1971")]
+    RubberEra(Gregorian),
+    /// A signature failed to verify
+    #[cfg(any(feature = "pgp", feature = "signed"))]
+    #[error("signature verification failed")]
+    Signature,
+    /// The checksum in the compact text format did not match
+    #[error("checksum failed {0:04x} <> {1:04x}")]
+    TextChecksum(u16, u16),
     /// Mismatched timestamp and date in the NIST `leap-seconds.list`
     #[error("timestamp and date do not match (NTP {0} is {1} <> {2})")]
     TimeDate(i64, MJD, Gregorian),
+    /// Syntax error in the compact text format of the leap seconds
+    /// list, with details in the [`txt::TxtParseError`][]
+    #[cfg(feature = "alloc")]
+    #[error("syntax error in compact text format")]
+    Txt(#[from] txt::TxtParseError),
+    /// A date before the start of the leap second list in 1972
+    #[error("date {0} is before 1972")]
+    TooEarly(Gregorian),
     /// The leap seconds list lacks an expiry date
     #[error("missing expiry date at end of list")]
     Truncated,
+    /// Malformed TZif file
+    #[error("TZif error: {0}")]
+    Tzif(&'static str),
+    /// A date outside the period covered by the list, see
+    /// [`Coverage`][]
+    #[cfg(feature = "alloc")]
+    #[error("{0}")]
+    Uncovered(Coverage),
     /// The NIST `leap-seconds.list` is not valid UTF-8
     #[error("{0}")]
-    Unicode(#[from] std::str::Utf8Error),
+    Unicode(#[from] core::str::Utf8Error),
     /// A leap second is not exactly +1 or -1
     #[error("leap is not +1 or -1 ({0} -> {1})")]
     WrongLeap(Gregorian, i16, Gregorian, i16),
@@ -131,7 +415,10 @@ pub enum Error {
 /// This type is also used with [`LeapSecBuilder`][] and elsewhere
 /// inside [`leapsecs`][self].
 ///
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+/// The variants are ordered as they appear in a list, so that entries
+/// in the same month sort in the order they happen.
+///
+#[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub enum Leap {
     /// Used for the first entry in the list, to represent the
     /// starting point rather than a leap second.
@@ -151,6 +438,26 @@ pub enum Leap {
 
 use Leap::*;
 
+/// The NTP leap indicator, from RFC 5905 section 7.3.
+///
+/// The discriminants are the values of the two LI bits in an NTP
+/// packet header. They are obtained from
+/// [`LeapSecs::leap_indicator_at()`][].
+///
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[repr(u8)]
+pub enum LeapIndicator {
+    /// There is no leap second at the end of this month.
+    NoWarning = 0,
+    /// The last minute of this month has 61 seconds.
+    Insert = 1,
+    /// The last minute of this month has 59 seconds.
+    Delete = 2,
+    /// The leap second list has expired, so the clock is not
+    /// synchronized.
+    Unsynchronized = 3,
+}
+
 //  _                  ___
 // | |   ___ __ _ _ __/ __| ___ __
 // | |__/ -_) _` | '_ \__ \/ -_) _|
@@ -171,7 +478,13 @@ use Leap::*;
 /// The entries in between are [`Leap::Pos`][] or maybe [`Leap::Neg`][] leap
 /// seconds.
 ///
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+/// Entries are ordered by month, then by [`Leap`][] sign, so that an
+/// expiry date sorts after a leap second in the same month, then by
+/// DTAI, then by the gap from the previous entry. This is the order
+/// of the entries in a list, and it is consistent with equality when
+/// entries from different lists are compared.
+///
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
 pub struct LeapSec {
     gap: u16,
     sign: Leap,
@@ -179,6 +492,19 @@ pub struct LeapSec {
     dtai: Option<i16>,
 }
 
+impl Ord for LeapSec {
+    fn cmp(&self, other: &LeapSec) -> core::cmp::Ordering {
+        let key = |leap: &LeapSec| (leap.month, leap.sign, leap.dtai, leap.gap);
+        key(self).cmp(&key(other))
+    }
+}
+
+impl PartialOrd for LeapSec {
+    fn partial_cmp(&self, other: &LeapSec) -> Option<core::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
 fn date_of(month: i32, day: i32) -> Gregorian {
     let year = month.div_euclid(12);
     let month = month.rem_euclid(12);
@@ -196,6 +522,10 @@ fn month_of(date: Gregorian, day: i32) -> Result<i32> {
 // NIST and IERS leap second tables expire on the 28th of the month
 const EXPIRES_DATE: i32 = 28;
 
+// the longest gap that fits in three digits in the compact text
+// format, see LeapSecBuilder::with_max_gap()
+const MAX_GAP: u16 = 999;
+
 impl LeapSec {
     /// Get the date immediately following the leap second. This is
     /// the date from which [`LeapSec::dtai()`][] is valid, or the list's
@@ -228,7 +558,9 @@ impl LeapSec {
     /// Get the length of the gap between the previous leap second and this
     /// one, counted in months.
     ///
-    /// The compact leap second formats limit this value to at most 999.
+    /// The compact leap second formats limit this value to at most 999,
+    /// unless the list was built with a larger
+    /// [`LeapSecBuilder::with_max_gap()`][].
     ///
     pub fn gap(self) -> u16 {
         self.gap
@@ -247,6 +579,85 @@ impl LeapSec {
         self.sign
     }
 
+    /// Get the number of months from January 1972 to the month of
+    /// [`LeapSec::date()`][]. The [`LeapSec::gap()`][] is the
+    /// difference between the month indexes of successive entries.
+    ///
+    ///     # use leapsecs::*;
+    ///     # use std::str::FromStr;
+    ///     let list = LeapSecs::from_str("6+6+999?")?;
+    ///     let months = list.iter().map(|leap| leap.month_index());
+    ///     let months: Vec<u16> = months.collect();
+    ///     assert_eq!(months, [0, 6, 12, 1011]);
+    ///     assert!(list[0].is_start() && list[3].is_expiry());
+    ///     # Ok::<(), Error>(())
+    ///
+    pub fn month_index(self) -> u16 {
+        self.month
+    }
+
+    /// Returns true if this is the first entry in a list, which is
+    /// the starting point in January 1972 rather than a leap second.
+    ///
+    pub fn is_start(self) -> bool {
+        self.sign == Zero && self.month == 0
+    }
+
+    /// Returns true if this is the last entry in a list, which
+    /// represents its expiry date rather than a leap second.
+    ///
+    pub fn is_expiry(self) -> bool {
+        self.sign == Exp
+    }
+
+    /// Returns true if this leap second is at the end of June or
+    /// December, as the IERS prefers, or if it is not a leap second.
+    ///
+    /// See [`LeapConvention`][] for other conventions.
+    ///
+    pub fn is_preferred_month(self) -> bool {
+        LeapConvention::HalfYearly.allows(&self)
+    }
+
+    /// Make a standalone entry, which can be added to a list by
+    /// [`LeapSecs::with_appended()`][].
+    ///
+    /// The `date` must be the 1st of the month for a leap second, or
+    /// the 28th of the month for [`Leap::Exp`][], in which case the
+    /// `dtai` is ignored. [`Leap::Zero`][] is only valid for the start
+    /// of the list on 1972-01-01, when DTAI was 10.
+    ///
+    /// A standalone entry is not part of a list, so its
+    /// [`LeapSec::gap()`][] is zero and it is not equal to the entry
+    /// that [`LeapSecs::with_appended()`][] adds to the list.
+    ///
+    ///     # use leapsecs::*;
+    ///     let leap = LeapSec::try_new(Gregorian(2017, 1, 1), Leap::Pos, 37)?;
+    ///     assert_eq!(leap.to_string(), "2017-01-01 +1 DTAI 37");
+    ///     assert_eq!(leap, "2017-01-01 +1 DTAI 37".parse()?);
+    ///     let bad = LeapSec::try_new(Gregorian(2017, 1, 28), Leap::Pos, 37);
+    ///     assert!(matches!(bad, Err(Error::MonthDay(..))));
+    ///     # Ok::<(), Error>(())
+    ///
+    pub fn try_new(date: Gregorian, sign: Leap, dtai: i16) -> Result<LeapSec> {
+        if !(1..=12).contains(&date.month()) {
+            return Err(Error::Date(date));
+        }
+        let (day, dtai) = match sign {
+            Exp => (EXPIRES_DATE, None),
+            _ => (1, Some(dtai)),
+        };
+        let month = month_of(date, day)?;
+        if sign == Zero && (month != 0 || dtai != Some(10)) {
+            return Err(Error::FalseStart(date, dtai.unwrap_or_default()));
+        }
+        if month < 0 {
+            return Err(Error::TooEarly(date));
+        }
+        let month = u16::try_from(month)?;
+        Ok(LeapSec { gap: 0, sign, month, dtai })
+    }
+
     /// Get the value first entry in a [`LeapSecs`][] list
     ///
     fn start() -> LeapSec {
@@ -254,8 +665,8 @@ impl LeapSec {
     }
 }
 
-impl std::fmt::Display for LeapSec {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl core::fmt::Display for LeapSec {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         let leap = match self.sign {
             Zero => "  ",
             Neg => "-1",
@@ -266,6 +677,81 @@ impl std::fmt::Display for LeapSec {
     }
 }
 
+/// Parse an entry in the form written by its [`Display`][core::fmt::Display]
+/// implementation, like
+///
+/// ```text
+/// 1972-01-01    DTAI 10
+/// 2017-01-01 +1 DTAI 37
+/// 2021-12-28 ??
+/// ```
+///
+/// The entry is checked by [`LeapSec::try_new()`][].
+///
+impl core::str::FromStr for LeapSec {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<LeapSec> {
+        let mut words = s.split_whitespace();
+        let date = parse_date(words.next().ok_or(Error::Truncated)?)?;
+        let (sign, dtai) = match words.next().ok_or(Error::Truncated)? {
+            "??" => (Exp, 0),
+            word => {
+                let sign = match word {
+                    "DTAI" => Zero,
+                    "+1" => Pos,
+                    "-1" => Neg,
+                    _ => return Err(unexpected("[+-]1 DTAI", word)),
+                };
+                if sign != Zero {
+                    match words.next().ok_or(Error::Truncated)? {
+                        "DTAI" => (),
+                        word => return Err(unexpected("DTAI", word)),
+                    }
+                }
+                let word = words.next().ok_or(Error::Truncated)?;
+                let dtai =
+                    word.parse().map_err(|_| unexpected("[0-9]", word))?;
+                (sign, dtai)
+            }
+        };
+        if let Some(word) = words.next() {
+            return Err(unexpected("end of entry", word));
+        }
+        LeapSec::try_new(date, sign, dtai)
+    }
+}
+
+// the first character in a word that is not a digit, or its first
+// character if the trouble is overflow
+fn unexpected(expected: &'static str, word: &str) -> Error {
+    let mut chars = word.chars();
+    let first = chars.clone().next().unwrap_or(' ');
+    let bad = chars.find(|c| !c.is_ascii_digit()).unwrap_or(first);
+    Error::FromStr(expected, bad)
+}
+
+// an ISO 8601 date in YYYY-MM-DD format
+pub(crate) fn parse_date(word: &str) -> Result<Gregorian> {
+    let mut fields = [0; 3];
+    let mut field = 0;
+    let mut len = 0;
+    for c in word.chars() {
+        match (len, c.to_digit(10)) {
+            (4 | 7, _) if c == '-' => field += 1,
+            (4 | 7, _) => return Err(Error::FromStr("-", c)),
+            (0..=9, Some(n)) => fields[field] = fields[field] * 10 + n as i32,
+            (0..=9, None) => return Err(Error::FromStr("[0-9]", c)),
+            (_, _) => return Err(Error::FromStr("YYYY-MM-DD", c)),
+        }
+        len += 1;
+    }
+    if len < 10 {
+        return Err(Error::Truncated);
+    }
+    Ok(Gregorian(fields[0], fields[1], fields[2]))
+}
+
 //  _                  ___
 // | |   ___ __ _ _ __/ __| ___ ___ ___
 // | |__/ -_) _` | '_ \__ \/ -_) __(_-<
@@ -283,9 +769,62 @@ impl std::fmt::Display for LeapSec {
 /// The conversion traits implemented for [`LeapSecs`][] are documented in the
 /// [`txt`][] and [`bin`][] modules.
 ///
-#[derive(Clone, Debug, Eq, PartialEq)]
-pub struct LeapSecs(Vec<LeapSec>);
+/// Two lists are equal if their entries are equal, regardless of how
+/// they were constructed or when they were updated.
+///
+#[cfg(feature = "alloc")]
+#[derive(Clone, Debug)]
+pub struct LeapSecs {
+    list: Vec<LeapSec>,
+    expired: bool,
+    inferred: bool,
+    updated: Option<MJD>,
+    refresh: Option<alloc::string::String>,
+    provenance: Option<Provenance>,
+}
+
+#[cfg(feature = "alloc")]
+impl PartialEq for LeapSecs {
+    fn eq(&self, other: &LeapSecs) -> bool {
+        self.list == other.list
+    }
+}
 
+#[cfg(feature = "alloc")]
+impl Eq for LeapSecs {}
+
+/// Like equality, hashing only depends on the entries, so lists
+/// collected from many places can be deduplicated in a `HashSet`.
+///
+#[cfg(feature = "alloc")]
+impl core::hash::Hash for LeapSecs {
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+        self.list.hash(state)
+    }
+}
+
+/// Lists are ordered by comparing their entries in turn, as for
+/// slices, so that they can be kept in a `BTreeSet`. Successive
+/// versions of the same history sort in the order they were
+/// published, because a new version's extra entries come after the
+/// old version's expiry date. Lists that disagree about history sort
+/// by their first differing entry.
+///
+#[cfg(feature = "alloc")]
+impl Ord for LeapSecs {
+    fn cmp(&self, other: &LeapSecs) -> core::cmp::Ordering {
+        self.list.cmp(&other.list)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl PartialOrd for LeapSecs {
+    fn partial_cmp(&self, other: &LeapSecs) -> Option<core::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[cfg(feature = "alloc")]
 impl LeapSecs {
     /// Find the next leap second after a particular `date`.
     ///
@@ -316,6 +855,13 @@ impl LeapSecs {
         Some(&self[0])
     }
 
+    /// Get a view of the list as it would have been seen on a
+    /// particular `date`. See [`AsOf`][].
+    ///
+    pub fn as_of(&self, date: Gregorian) -> AsOf<'_> {
+        AsOf { list: self, today: date }
+    }
+
     /// Find the previous leap second before a particular `date`.
     ///
     /// If the given `date` is the day after a leap second, that is the
@@ -332,213 +878,1675 @@ impl LeapSecs {
     /// [`LeapSec`][] representing that expiry time is returned.
     ///
     pub fn before(&self, date: Gregorian) -> Option<&LeapSec> {
-        let mut prev = None;
-        for leap in self.iter() {
-            if leap.date() > date {
-                return prev;
-            } else {
-                prev = Some(leap);
-            }
-        }
-        self.0.last()
+        before(&self.list, date)
     }
 
-    /// Convenience method for getting a [`LeapSecBuilder`][]
-    pub fn builder() -> LeapSecBuilder {
-        Default::default()
+    /// Get the difference between UTC and TAI on a particular `date`.
+    ///
+    /// Returns [`Error::Uncovered`][] if the `date` is after the
+    /// list's expiry date, or before 1972 when the current version of
+    /// UTC started. The error says which dates the list covers, and
+    /// how to refresh it if that was set by
+    /// [`LeapSecs::with_refresh()`][].
+    ///
+    pub fn dtai_at(&self, date: Gregorian) -> Result<i16> {
+        self.check_covered(date)?;
+        // leave out the expiry entry, because the list is still
+        // valid on its expiry date
+        dtai_at(&self.list[..self.len() - 1], date)
     }
 
-    /// Get the expiry date of the list.
-    pub fn expires(&self) -> MJD {
-        self.0.last().unwrap().mjd()
+    /// Check that a `date` is within the period covered by the list,
+    /// failing with [`Error::Uncovered`][] if it is not.
+    ///
+    pub fn check_covered(&self, date: Gregorian) -> Result<()> {
+        let mjd = MJD::from(date);
+        if mjd < UTC_START_1972 || self.expires() < mjd {
+            Err(Error::Uncovered(self.coverage(date)))
+        } else {
+            Ok(())
+        }
     }
 
-    /// Get an element of the list
-    pub fn get(&self, i: usize) -> Option<&LeapSec> {
-        self.0.get(i)
+    /// Describe the period covered by the list, and how to refresh
+    /// it, for an error about the given `date`.
+    ///
+    pub fn coverage(&self, date: Gregorian) -> Coverage {
+        Coverage {
+            date,
+            first: Gregorian::from(UTC_START_1972),
+            expires: Gregorian::from(self.expires()),
+            refresh: self.refresh.clone(),
+        }
     }
 
-    /// Returns true if [`LeapSecs::len()`][] is zero
-    pub fn is_empty(&self) -> bool {
-        self.0.is_empty()
+    /// Get the difference between UTC and TAI at a [`Unix`][]
+    /// timestamp, like [`LeapSecs::dtai_at()`][] on the date that
+    /// contains the timestamp.
+    ///
+    pub fn dtai_at_unix(&self, unix: Unix) -> Result<i16> {
+        self.dtai_at(Gregorian::try_from(unix)?)
     }
 
-    /// Get an iterator over the [`LeapSec`][] elements
-    pub fn iter(&self) -> std::slice::Iter<'_, LeapSec> {
-        self.into_iter()
+    /// Get the number of SI seconds that elapsed from one [`Unix`][]
+    /// timestamp to another, counting any leap seconds in between. It
+    /// is negative if `b` is before `a`. For example,
+    ///
+    ///     # use leapsecs::*;
+    ///     # use std::str::FromStr;
+    ///     let list = LeapSecs::from_str("6+6-999?")?;
+    ///     // 1972-06-30 23:59:59 to 1972-07-01 00:00:00
+    ///     let (a, b) = (Unix(78_796_799), Unix(78_796_800));
+    ///     assert_eq!(list.duration_between(a, b)?, 2);
+    ///     assert_eq!(list.duration_between(b, a)?, -2);
+    ///     // 1972-12-31 23:59:58 to 1973-01-01 00:00:00
+    ///     let (a, b) = (Unix(94_694_398), Unix(94_694_400));
+    ///     assert_eq!(list.duration_between(a, b)?, 1);
+    ///     assert!(list.duration_between(Unix(0), b).is_err());
+    ///     # Ok::<(), Error>(())
+    ///
+    /// POSIX time cannot name a positive leap second, and it names a
+    /// negative leap second that did not happen, which counts as the
+    /// second before it.
+    ///
+    /// Fails like [`LeapSecs::dtai_at_unix()`][] if either timestamp
+    /// is not covered by the list.
+    ///
+    pub fn duration_between(&self, a: Unix, b: Unix) -> Result<i64> {
+        let dtai = |unix| self.dtai_at_unix(unix).map(i64::from);
+        // check coverage first so the subtraction cannot overflow
+        let (dtai_a, dtai_b) = (dtai(a)?, dtai(b)?);
+        Ok(b.0 - a.0 + dtai_b - dtai_a)
     }
 
-    /// Get the number of [`LeapSec`][] elements
-    pub fn len(&self) -> usize {
-        self.0.len()
+    /// Check whether a UTC time of day exists on a `date`, so that
+    /// timestamps with a seconds field of 60 can be validated. For
+    /// example,
+    ///
+    ///     # use leapsecs::*;
+    ///     # use std::str::FromStr;
+    ///     let list = LeapSecs::from_str("6+6-999?")?;
+    ///     let june = Gregorian(1972, 6, 30);
+    ///     assert_eq!(list.classify_second(june, 23, 59, 60)?,
+    ///                UtcSecond::Inserted);
+    ///     assert_eq!(list.classify_second(june, 12, 0, 60)?,
+    ///                UtcSecond::Invalid);
+    ///     let december = Gregorian(1972, 12, 31);
+    ///     assert_eq!(list.classify_second(december, 23, 59, 59)?,
+    ///                UtcSecond::Skipped);
+    ///     assert_eq!(list.classify_second(december, 23, 59, 60)?,
+    ///                UtcSecond::Invalid);
+    ///     # Ok::<(), Error>(())
+    ///
+    /// Fails with [`Error::Uncovered`][] if the `date` is not covered
+    /// by the list, because there is no way to know whether it ends
+    /// with a leap second.
+    ///
+    pub fn classify_second(
+        &self,
+        date: Gregorian,
+        hh: u8,
+        mm: u8,
+        ss: u8,
+    ) -> Result<UtcSecond> {
+        self.check_covered(date)?;
+        let next = date.mjd().succ();
+        let sign = self
+            .iter()
+            .find(|leap| leap.mjd() == next)
+            .map_or(Leap::Zero, |leap| leap.sign());
+        Ok(match (hh, mm, ss, sign) {
+            (23, 59, 60, Leap::Pos) => UtcSecond::Inserted,
+            (23, 59, 59, Leap::Neg) => UtcSecond::Skipped,
+            (0..=23, 0..=59, 0..=59, _) => UtcSecond::Normal,
+            _ => UtcSecond::Invalid,
+        })
     }
-}
-
-impl Index<usize> for LeapSecs {
-    type Output = LeapSec;
 
-    fn index(&self, i: usize) -> &LeapSec {
-        self.get(i).unwrap()
+    /// Returns true if `hh:mm:ss` on the `date` is a positive leap
+    /// second, see [`LeapSecs::classify_second()`][].
+    ///
+    pub fn is_leap_second(
+        &self,
+        date: Gregorian,
+        hh: u8,
+        mm: u8,
+        ss: u8,
+    ) -> Result<bool> {
+        let second = self.classify_second(date, hh, mm, ss)?;
+        Ok(second == UtcSecond::Inserted)
     }
-}
 
-impl<'a> IntoIterator for &'a LeapSecs {
-    type Item = &'a LeapSec;
-    type IntoIter = std::slice::Iter<'a, LeapSec>;
-
-    fn into_iter(self) -> Self::IntoIter {
-        self.0.iter()
+    /// Convenience method for getting a [`LeapSecBuilder`][]
+    pub fn builder() -> LeapSecBuilder {
+        Default::default()
     }
-}
 
-//  _                  ___          ___      _ _    _
-// | |   ___ __ _ _ __/ __| ___ ___| _ )_  _(_) |__| |___ _ _
-// | |__/ -_) _` | '_ \__ \/ -_) __| _ \ || | | / _` / -_) '_|
-// |____\___\__,_| .__/___/\___\___|___/\_,_|_|_\__,_\___|_|
-//               |_|
-
-/// Construct a [`LeapSecs`][] list, checking it for validity.
-///
-/// There are two primary ways to use a [`LeapSecBuilder`][], depending on
-/// the format of the source data.
-///
-/// The parsers for the compact leap second list formats use
-/// [`LeapSecBuilder::push_gap()`][].
-///
-/// The [`nist`][] parser uses [`LeapSecBuilder::push_date()`][] and
-/// [`LeapSecBuilder::push_exp()`][].
-///
-/// It isn't an error to use both ways to construct a list, but why would
-/// you?
-///
-#[derive(Clone, Debug, Eq, PartialEq)]
-pub struct LeapSecBuilder(Vec<LeapSec>);
-
-impl Default for LeapSecBuilder {
-    fn default() -> LeapSecBuilder {
-        LeapSecBuilder::new()
+    /// Get the expiry date of the list.
+    pub fn expires(&self) -> MJD {
+        self.list.last().unwrap().mjd()
     }
-}
 
-impl LeapSecBuilder {
-    /// Get a new [`LeapSecBuilder`][]
-    pub fn new() -> LeapSecBuilder {
-        LeapSecBuilder(Vec::new())
+    /// Returns true if the list has expired according to the given
+    /// [`Clock`][]. The list is still valid on its expiry date.
+    ///
+    pub fn is_expired_at<C: Clock>(&self, clock: C) -> bool {
+        self.expires() < clock.today()
     }
 
-    /// Do the final consistency checks on the [`LeapSecBuilder`][] and
-    /// if they pass, return the completed  [`LeapSecs`][] list.
+    /// Returns true if the list will have expired `days` days from
+    /// now, so that monitoring can give advance notice.
     ///
-    pub fn finish(mut self) -> Result<LeapSecs> {
-        let last = self.last()?;
-        if last.sign != Exp {
-            Err(Error::Truncated)
-        } else if last.mjd() < MJD::today() {
-            Err(Error::Expired(last.date()))
-        } else {
-            self.0.shrink_to_fit();
-            Ok(LeapSecs(self.0))
-        }
+    /// This requires the `std` feature.
+    ///
+    #[cfg(feature = "std")]
+    pub fn expires_within(&self, days: i32) -> bool {
+        self.expires_within_at(days, SystemClock)
     }
 
-    fn last(&self) -> Result<LeapSec> {
-        match self.0.last() {
-            Some(&last) => Ok(last),
-            None => Err(Error::Empty),
-        }
-    }
-    fn push_start(&mut self) {
-        self.0.push(LeapSec::start());
+    /// Returns true if the list will have expired `days` days after
+    /// the given [`Clock`][]'s date.
+    ///
+    pub fn expires_within_at<C: Clock>(&self, days: i32, clock: C) -> bool {
+        self.is_expired_at(clock.today() + days)
     }
 
-    fn push_leap_sec(
-        &mut self,
-        mut last: LeapSec,
-        mut gap: i32,
-        sign: Leap,
-        month: i32,
-        dtai: Option<i16>,
-    ) -> Result<()> {
-        if last.sign == Exp {
-            return Err(Error::LeapAfterExp(last.date(), date_of(month, 1)));
-        }
-        if last.sign == Zero && last.month != 0 {
-            gap += last.gap as i32;
-            self.0.pop();
-            last = self.last()?;
-        }
-        let gap = match gap {
-            1..=999 => gap as u16,
-            _ => return Err(Error::Gap(last.date(), gap, date_of(month, 1))),
-        };
-        let month = u16::try_from(month)?;
-        assert_eq!(last.month + gap, month);
-        assert_eq!(sign == Exp, dtai == None);
-        self.0.push(LeapSec { gap, sign, month, dtai });
-        Ok(())
+    /// Get an element of the list, or [`None`][] if the index is out
+    /// of range
+    pub fn get(&self, i: usize) -> Option<&LeapSec> {
+        self.list.get(i)
     }
 
-    /// Add an entry to the list
-    ///
-    /// `gap` is the time between the last entry and this one, measured in
-    /// months
+    /// Get the entry that governs a particular date: the same as
+    /// [`LeapSecs::before()`][] but for an [`MJD`][].
     ///
-    /// `sign` is what kind of leap second this is.
-    ///
-    /// When `sign` is [`Leap::Zero`][], `push_gap()` builds a single entry
-    /// in multiple steps.
+    /// Returns [`None`][] if the date is before 1972, or the last
+    /// entry in the list if the date is after its expiry time.
     ///
-    /// When `sign` is [`Leap::Exp`][], `push_gap()` adds the expiry date as
-    /// the last entry.
+    pub fn get_by_mjd(&self, mjd: MJD) -> Option<&LeapSec> {
+        self.before(Gregorian::from(mjd))
+    }
+
+    /// Get the NTP leap indicator for a particular date, which says
+    /// whether there is a leap second at the end of the last day of
+    /// that date's month.
     ///
-    /// When using `push_gap()`, there is no need to explicitly add the
-    /// first (non-leap-second) entry in the list.
+    /// After the list's expiry date this returns
+    /// [`LeapIndicator::Unsynchronized`][].
     ///
-    pub fn push_gap(&mut self, gap: i32, sign: Leap) -> Result<()> {
-        if self.0.is_empty() {
-            self.push_start();
+    pub fn leap_indicator_at(&self, mjd: MJD) -> LeapIndicator {
+        if self.is_expired_at(mjd) {
+            return LeapIndicator::Unsynchronized;
         }
-        let last = self.last()?;
-        let month = last.month as i32 + gap;
-        let ldtai = last.dtai()?;
-        let dtai = match sign {
-            Zero => Some(ldtai),
-            Neg => Some(ldtai - 1),
-            Pos => Some(ldtai + 1),
-            Exp => None,
+        let Gregorian(year, month, _) = Gregorian::from(mjd);
+        let next = if month == 12 {
+            Gregorian(year + 1, 1, 1)
+        } else {
+            Gregorian(year, month + 1, 1)
         };
-        self.push_leap_sec(last, gap, sign, month, dtai)
+        match self.after(Gregorian::from(mjd)) {
+            Some(leap) if leap.date() == next => match leap.sign {
+                Neg => LeapIndicator::Delete,
+                Pos => LeapIndicator::Insert,
+                Zero | Exp => LeapIndicator::NoWarning,
+            },
+            _ => LeapIndicator::NoWarning,
+        }
     }
 
-    /// Add the expiry date to the list.
+    /// Get the most recent actual leap second in the list, or
+    /// [`None`][] if there have been none.
     ///
-    /// The date must be the 28th of the month.
+    pub fn last_leap(&self) -> Option<&LeapSec> {
+        self.iter().rev().find(|leap| matches!(leap.sign, Neg | Pos))
+    }
+
+    /// Get the next date after `after` when a leap second might take
+    /// effect, at the end of June or December.
     ///
-    /// This must be done last, before calling [`LeapSecBuilder::finish()`][]
+    /// See [`LeapSecs::next_possible_leap_with()`][] for details.
     ///
-    pub fn push_exp(&mut self, date: Gregorian) -> Result<()> {
-        let month = month_of(date, EXPIRES_DATE)?;
-        let last = self.last()?;
-        let gap = month - last.month as i32;
-        self.push_leap_sec(last, gap, Exp, month, None)
+    pub fn next_possible_leap(&self, after: MJD) -> Option<MJD> {
+        self.next_possible_leap_with(after, LeapConvention::default())
     }
 
-    /// Add an entry to the list
+    /// Get the next date after `after` when a leap second might take
+    /// effect, at the end of a month allowed by the `convention`.
     ///
-    /// `date` must be the first of the month immediately after the leap
-    /// second.
+    /// The date is the day after the leap second, like
+    /// [`LeapSec::mjd()`][]. The list rules out leap seconds until it
+    /// expires, so this is the next leap second in the list if there
+    /// is one, or the first possible date after the list expires.
+    /// Returns [`None`][] if `after` is before 1972, when UTC did not
+    /// have leap seconds. For example,
     ///
-    /// `dtai` is the difference between UTC and TAI starting after the leap
-    /// second at the given `date`. [`LeapSec::dtai()`][] discusses why DTAI
-    /// values use `i16`.
+    ///     # use leapsecs::*;
+    ///     # use std::str::FromStr;
+    ///     let list = LeapSecs::from_str("6+6+999?")?;
+    ///     let date = Gregorian(1972, 3, 1).mjd();
+    ///     let next = list.next_possible_leap(date);
+    ///     assert_eq!(next, Some(Gregorian(1972, 7, 1).mjd()));
+    ///     let date = Gregorian(2000, 1, 1).mjd();
+    ///     let next = list.next_possible_leap(date);
+    ///     assert_eq!(next, Some(Gregorian(2056, 7, 1).mjd()));
+    ///     let monthly = LeapConvention::Monthly;
+    ///     let next = list.next_possible_leap_with(date, monthly);
+    ///     assert_eq!(next, Some(Gregorian(2056, 5, 1).mjd()));
+    ///     # Ok::<(), Error>(())
+    ///
+    pub fn next_possible_leap_with(
+        &self,
+        after: MJD,
+        convention: LeapConvention,
+    ) -> Option<MJD> {
+        if after < UTC_START_1972 {
+            return None;
+        }
+        let scheduled = self
+            .iter()
+            .find(|leap| leap.mjd() > after && matches!(leap.sign, Neg | Pos));
+        if let Some(leap) = scheduled {
+            return Some(leap.mjd());
+        }
+        let Gregorian(year, month, _) =
+            Gregorian::from(after.max(self.expires()));
+        let months = convention.months();
+        // months since year 0 of the first of the next possible month
+        let next = (year * 12 + month - 1).div_euclid(months) * months + months;
+        Some(Gregorian(next.div_euclid(12), next.rem_euclid(12) + 1, 1).mjd())
+    }
+
+    /// Returns true if [`LeapSecs::len()`][] is zero
+    pub fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
+
+    /// Get an iterator over the [`LeapSec`][] elements
+    pub fn iter(&self) -> core::slice::Iter<'_, LeapSec> {
+        self.into_iter()
+    }
+
+    /// Count how often each gap appears in the list, in months,
+    /// including the gap before the expiry date.
+    ///
+    /// The compact binary format is shortest when most gaps are small
+    /// multiples of 6 months, and a long gap takes several bytes, so
+    /// this shows how well a list will encode without encoding it.
+    /// For example, this list is shorter as text:
+    ///
+    ///     # use leapsecs::*;
+    ///     # use std::str::FromStr;
+    ///     let list = LeapSecs::from_str("6+6+12+999?")?;
+    ///     let gaps: Vec<_> = list.gap_histogram().into_iter().collect();
+    ///     assert_eq!(gaps, [(6, 2), (12, 1), (999, 1)]);
+    ///     assert_eq!(bin::encoded_len(&list), 14);
+    ///     assert_eq!(list.to_string().len(), 11);
+    ///     # Ok::<(), Error>(())
+    ///
+    pub fn gap_histogram(&self) -> alloc::collections::BTreeMap<u16, usize> {
+        let mut histogram = alloc::collections::BTreeMap::new();
+        for leap in self.iter().skip(1) {
+            *histogram.entry(leap.gap()).or_insert(0) += 1;
+        }
+        histogram
+    }
+
+    /// Get an iterator over the periods between leap seconds, as
+    /// half-open ranges of [`MJD`][]s with the DTAI that is valid
+    /// during each range.
+    ///
+    /// The ranges are contiguous. The first range starts at the
+    /// beginning of 1972, and the last range ends after the list's
+    /// expiry date (which is the last day the list is valid).
+    ///
+    pub fn segments(
+        &self,
+    ) -> impl Iterator<Item = (core::ops::Range<MJD>, i16)> + '_ {
+        self.list.windows(2).map(|pair| {
+            let (this, next) = (pair[0], pair[1]);
+            let end =
+                if next.sign == Exp { next.mjd() + 1 } else { next.mjd() };
+            // only the last entry lacks a DTAI
+            (this.mjd()..end, this.dtai.unwrap())
+        })
+    }
+
+    /// Get an iterator over the entries in the list, each with the
+    /// number of SI seconds that elapsed between the start of 1972
+    /// (1972-01-01T00:00:10 TAI) and the start of the entry's
+    /// [`LeapSec::date()`][].
+    ///
+    /// This is the number of days times 86400 plus the number of leap
+    /// seconds, i.e. the change in DTAI since 1972, for example,
+    ///
+    ///     # use leapsecs::*;
+    ///     let list = LeapSecs::from_str_lenient("6+6-12+7?")?;
+    ///     let elapsed: Vec<i64> = list.elapsed().map(|(_, s)| s).collect();
+    ///     assert_eq!(elapsed, [0, 15_724_801, 31_622_400, 63_158_401,
+    ///                          83_808_001]);
+    ///     # Ok::<(), Error>(())
+    ///
+    pub fn elapsed(&self) -> impl Iterator<Item = (LeapSec, i64)> + '_ {
+        let mut dtai = 10;
+        self.list.iter().map(move |&leap| {
+            dtai = leap.dtai().unwrap_or(dtai);
+            let days = i64::from(leap.mjd() - UTC_START_1972);
+            (leap, days * 86400 + i64::from(dtai - 10))
+        })
+    }
+
+    /// Get the number of [`LeapSec`][] elements
+    pub fn len(&self) -> usize {
+        self.list.len()
+    }
+
+    /// Check that this list agrees with the history in an older
+    /// `pinned` list that is already trusted.
+    ///
+    /// Every entry in the pinned list must be present in this list,
+    /// and this list must not have any leap seconds on or before the
+    /// pinned list's expiry date, nor expire earlier than it.
+    ///
+    /// This is to stop an update that rewrites the past from being
+    /// installed. On failure, [`Error::History`][] contains the first
+    /// entry where the lists diverge, pinned first.
+    ///
+    pub fn verify_history(&self, pinned: &LeapSecs) -> Result<()> {
+        // a shorter list always differs before its end, because
+        // only the last entry of either list can be an expiry date
+        for (&old, &new) in pinned.iter().zip(self.iter()) {
+            if old != new && (old.sign != Exp || new.date() <= old.date()) {
+                return Err(Error::History(old, new));
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns true if `other` is a conservative update of this list:
+    /// it has the same leap seconds up to this list's expiry date,
+    /// and it expires on or after this list, as checked by
+    /// [`LeapSecs::verify_history()`][].
+    ///
+    /// A list is a prefix of itself.
+    ///
+    pub fn is_prefix_of(&self, other: &LeapSecs) -> bool {
+        other.verify_history(self).is_ok()
+    }
+
+    /// Returns true if this list is a conservative update of `other`,
+    /// i.e. `other.is_prefix_of(self)`.
+    ///
+    /// An update daemon can use this to decide whether a newly
+    /// fetched list is safe to install: if not, it is corrupt, or it
+    /// is an old list being replayed.
+    ///
+    pub fn extends(&self, other: &LeapSecs) -> bool {
+        other.is_prefix_of(self)
+    }
+
+    /// Returns true if the list's expiry date was not given by its
+    /// source, but was guessed by [`LeapSecBuilder::finish_inferred()`][].
+    ///
+    pub fn expiry_inferred(&self) -> bool {
+        self.inferred
+    }
+
+    /// Get the date when the list was last updated, if its source
+    /// said so, such as the `#$` line in the NIST format.
+    ///
+    pub fn updated(&self) -> Option<MJD> {
+        self.updated
+    }
+
+    /// Set the date returned by [`LeapSecs::updated()`][], for lists
+    /// from sources that do not include it.
+    ///
+    pub fn with_updated(mut self, updated: MJD) -> LeapSecs {
+        self.updated = Some(updated);
+        self
+    }
+
+    /// Get the hint for refreshing the list that was set by
+    /// [`LeapSecs::with_refresh()`][].
+    ///
+    pub fn refresh(&self) -> Option<&str> {
+        self.refresh.as_deref()
+    }
+
+    /// Set a hint for refreshing the list, such as a URL or a
+    /// command, to be included in [`Error::Uncovered`][] so that
+    /// applications can tell their users what to do about it.
+    ///
+    pub fn with_refresh(mut self, hint: &str) -> LeapSecs {
+        self.refresh = Some(hint.into());
+        self
+    }
+
+    /// Return a copy of the list with another entry added to the
+    /// end, such as one made by [`LeapSec::try_new()`][] or parsed
+    /// from its text form.
+    ///
+    /// A [`Leap::Pos`][] or [`Leap::Neg`][] leap second is added after
+    /// the last leap second, and the list keeps its expiry date, so
+    /// the leap second must be before it. A [`Leap::Exp`][] entry
+    /// replaces the expiry date. The new list is checked as if by
+    /// [`LeapSecBuilder::finish_lenient()`][], and the DTAI of a new
+    /// leap second must differ by one from the last leap second in
+    /// the direction of its sign, for example,
+    ///
+    ///     # use leapsecs::*;
+    ///     # use std::str::FromStr;
+    ///     let list = LeapSecs::from_str("6+6+999?")?;
+    ///     let leap = "1973-07-01 +1 DTAI 13".parse()?;
+    ///     let list = list.with_appended(leap)?;
+    ///     assert_eq!(list.to_string(), "6+6+6+993?");
+    ///     let leap = "1974-01-01 +1 DTAI 13".parse()?;
+    ///     assert!(list.with_appended(leap).is_err());
+    ///     # Ok::<(), Error>(())
+    ///
+    /// Fails with [`Error::LeapAfterExp`][] if a leap second is after
+    /// the expiry date, and with [`Error::FalseStart`][] if the entry
+    /// is [`Leap::Zero`][].
+    ///
+    pub fn with_appended(&self, entry: LeapSec) -> Result<LeapSecs> {
+        let expires = Gregorian::from(self.expires());
+        let mut builder = LeapSecs::builder();
+        let mut last = LeapSec::start();
+        for &leap in self.iter().filter(|leap| leap.sign != Exp) {
+            builder.push_date(leap.date(), leap.dtai()?)?;
+            last = leap;
+        }
+        match entry.sign {
+            Exp => builder.push_exp(entry.date())?,
+            Zero => {
+                return Err(Error::FalseStart(entry.date(), entry.dtai()?));
+            }
+            Neg | Pos => {
+                if entry.date() > expires {
+                    return Err(Error::LeapAfterExp(expires, entry.date()));
+                }
+                let (before, after) = (last.dtai()?, entry.dtai()?);
+                if (entry.sign == Pos) != (after > before) {
+                    let (from, to) = (last.date(), entry.date());
+                    return Err(Error::WrongLeap(from, before, to, after));
+                }
+                builder.push_date(entry.date(), after)?;
+                builder.push_exp(expires)?;
+            }
+        }
+        Ok(builder.finish_lenient()?.with_metadata_of(self))
+    }
+
+    /// Return a copy of the list with a made-up leap second, for
+    /// testing how software copes with one that has not happened,
+    /// such as a negative leap second.
+    ///
+    /// The `date` is the first of the month after the leap second,
+    /// like [`LeapSec::date()`][], and the DTAI is one more or less
+    /// than the last leap second's, according to the `sign`. If the
+    /// leap second is after the list's expiry date, the expiry date
+    /// moves to the 28th of the fifth month after `date`, just before
+    /// the next opportunity for a leap second, for example,
+    ///
+    ///     # use leapsecs::*;
+    ///     # use std::str::FromStr;
+    ///     let list = LeapSecs::from_str_lenient("6+6+5?")?;
+    ///     let list = list.with_hypothetical(Gregorian(1974, 1, 1), Leap::Neg)?;
+    ///     assert_eq!(list.to_string(), "6+6+12-5?");
+    ///     assert_eq!(list.last_leap().unwrap().dtai()?, 11);
+    ///     # Ok::<(), Error>(())
+    ///
+    /// Otherwise this is the same as [`LeapSecs::with_appended()`][],
+    /// and it fails in the same ways, for instance if `date` is not
+    /// after the last leap second. Fails with [`Error::NotLeap`][] if
+    /// the `sign` is [`Leap::Zero`][] or [`Leap::Exp`][].
+    ///
+    pub fn with_hypothetical(
+        &self,
+        date: Gregorian,
+        sign: Leap,
+    ) -> Result<LeapSecs> {
+        let step = match sign {
+            Neg => -1,
+            Pos => 1,
+            Zero | Exp => return Err(Error::NotLeap(date, sign)),
+        };
+        let last = self.iter().rev().find(|leap| leap.sign != Exp);
+        let dtai = last.map_or(Ok(10), |leap| leap.dtai())? + step;
+        let list = match date > Gregorian::from(self.expires()) {
+            true => {
+                let expires = Gregorian(date.0, date.1, 28).add_months(5);
+                self.with_appended(LeapSec::try_new(expires, Exp, 0)?)?
+            }
+            false => self.clone(),
+        };
+        let list = list.with_appended(LeapSec::try_new(date, sign, dtai)?)?;
+        Ok(list.with_metadata_of(self))
+    }
+
+    /// Return a copy of the list updated by an IERS Bulletin C, with
+    /// the announced leap second if there is one, and the new expiry
+    /// date, for example,
+    ///
+    ///     # use leapsecs::*;
+    ///     # use std::str::FromStr;
+    ///     let list = LeapSecs::from_str_lenient("6+6+5?")?;
+    ///     let text = "A positive leap second will be introduced \
+    ///                 at the end of June 1973.";
+    ///     let list = list.apply_bulletin(&bulletin::parse(text)?)?;
+    ///     assert_eq!(list.to_string(), "6+6+6+5?");
+    ///     # Ok::<(), Error>(())
+    ///
+    /// This is the same as [`Announcement::apply()`][], and fails in
+    /// the same ways.
+    ///
+    pub fn apply_bulletin(&self, bulletin: &Announcement) -> Result<LeapSecs> {
+        bulletin.apply(self)
+    }
+
+    /// Get a copy of the list that stops at `date`, for devices that
+    /// do not need to know about later leap seconds.
+    ///
+    /// The new list expires on the last 28th of a month on or before
+    /// `date` that is not in the same month as a leap second, and has
+    /// no leap seconds after that. If the list already
+    /// expires by then, it is returned unchanged. For example,
+    ///
+    ///     # use leapsecs::*;
+    ///     # use std::str::FromStr;
+    ///     let list = LeapSecs::from_str("6+6+12+999?")?;
+    ///     let short = list.truncate_after(Gregorian(1973, 3, 1).mjd())?;
+    ///     assert_eq!(short.to_string(), "6+6+1?");
+    ///     # Ok::<(), Error>(())
+    ///
+    /// The new list is checked as if by
+    /// [`LeapSecBuilder::finish_lenient()`][], so it is marked as
+    /// expired if `date` is in the past. Fails with
+    /// [`Error::TooEarly`][] if `date` is before 1972-02-28.
+    ///
+    /// There is no way to drop the leap seconds at the start of the
+    /// list, because every list starts at 1972-01-01 with DTAI 10 so
+    /// that it can be checked; they take about one byte each in the
+    /// compact binary format.
+    ///
+    pub fn truncate_after(&self, date: MJD) -> Result<LeapSecs> {
+        if self.expires() <= date {
+            return Ok(self.clone());
+        }
+        let Gregorian(year, month, day) = Gregorian::from(date);
+        let expires = Gregorian(year, month, 28);
+        let mut expires =
+            if day < 28 { expires.add_months(-1) } else { expires };
+        // the list can't expire in the same month as a leap second
+        while self.iter().any(|leap| {
+            leap.sign != Exp
+                && leap.date() == Gregorian(expires.0, expires.1, 1)
+        }) {
+            expires = expires.add_months(-1);
+        }
+        // the earliest possible expiry date is a month after the start
+        if expires < Gregorian(1972, 2, 28) {
+            return Err(Error::TooEarly(expires));
+        }
+        let mut builder = LeapSecs::builder();
+        for &leap in self.iter() {
+            if leap.sign == Exp || leap.date() > expires {
+                break;
+            }
+            builder.push_date(leap.date(), leap.dtai()?)?;
+        }
+        builder.push_exp(expires)?;
+        Ok(builder.finish_lenient()?.with_metadata_of(self))
+    }
+
+    /// Get the entries that matter from `date` onwards, for devices
+    /// that do not need to know about earlier leap seconds.
+    ///
+    /// The first entry is the last leap second on or before `date`,
+    /// or the start of the list, so its DTAI is the base for the
+    /// rest; the last entry is the expiry date. For example,
+    ///
+    ///     # use leapsecs::*;
+    ///     # use std::str::FromStr;
+    ///     let list = LeapSecs::from_str("6+6+12+999?")?;
+    ///     let recent = list.since(Gregorian(1973, 3, 1).mjd())?;
+    ///     assert_eq!(recent.len(), 3);
+    ///     assert_eq!(recent[0].date(), Gregorian(1973, 1, 1));
+    ///     assert_eq!(recent[0].dtai()?, 12);
+    ///     assert!(recent[2].is_expiry());
+    ///     # Ok::<(), Error>(())
+    ///
+    /// The entries keep their [`LeapSec::gap()`][] and
+    /// [`LeapSec::month_index()`][] from the whole list. They cannot
+    /// be made into a [`LeapSecs`][] list of their own, because every
+    /// list starts at 1972-01-01 with DTAI 10, as described under
+    /// [`LeapSecs::truncate_after()`][].
+    ///
+    /// Fails with [`Error::TooEarly`][] if `date` is before 1972, and
+    /// with [`Error::Expired`][] if it is after the list expires.
+    ///
+    pub fn since(&self, date: MJD) -> Result<&[LeapSec]> {
+        let day = Gregorian::from(date);
+        if date < UTC_START_1972 {
+            return Err(Error::TooEarly(day));
+        }
+        if self.expires() < date {
+            return Err(Error::Expired(Gregorian::from(self.expires())));
+        }
+        // the start is on or before the date, so this always matches
+        let first = self
+            .iter()
+            .rposition(|leap| leap.sign != Exp && leap.date() <= day)
+            .unwrap_or(0);
+        Ok(&self.list[first..])
+    }
+
+    // keep the information about where a list came from in a list
+    // derived from it
+    fn with_metadata_of(mut self, other: &LeapSecs) -> LeapSecs {
+        self.updated = other.updated;
+        self.refresh = other.refresh.clone();
+        self.provenance = other.provenance.clone();
+        self
+    }
+
+    /// Build a list from `(date, dtai)` pairs, as in the data lines of
+    /// a NIST `leap-seconds.list` file, followed by the expiry date.
+    ///
+    /// Each pair is added as if by [`LeapSecBuilder::push_date()`][],
+    /// starting with `(1972-01-01, 10)`. The last pair is the expiry
+    /// date, which must be the 28th of a month, and its DTAI must be
+    /// the same as the last leap second's, otherwise this fails with
+    /// [`Error::WrongLeap`][]. The list is checked as if by
+    /// [`LeapSecBuilder::finish()`][].
+    ///
+    ///     # use leapsecs::*;
+    ///     let list = LeapSecs::try_from_dates(vec![
+    ///         (Gregorian(1972, 1, 1), 10),
+    ///         (Gregorian(1972, 7, 1), 11),
+    ///         (Gregorian(2050, 6, 28), 11),
+    ///     ])?;
+    ///     assert_eq!(list.to_string(), "6+935?");
+    ///     # Ok::<(), Error>(())
+    ///
+    /// [`LeapSecs`][] also implements `TryFrom<Vec<(Gregorian, i16)>>`
+    /// in the same way.
+    ///
+    pub fn try_from_dates<I>(dates: I) -> Result<LeapSecs>
+    where
+        I: IntoIterator<Item = (Gregorian, i16)>,
+    {
+        let mut builder = LeapSecs::builder();
+        let mut dates = dates.into_iter().peekable();
+        while let Some((date, dtai)) = dates.next() {
+            if dates.peek().is_some() {
+                builder.push_date(date, dtai)?;
+                continue;
+            }
+            let last = builder.last_entry()?;
+            if last.dtai()? != dtai {
+                let (from, before) = (last.date(), last.dtai()?);
+                return Err(Error::WrongLeap(from, before, date, dtai));
+            }
+            builder.push_exp(date)?;
+        }
+        builder.finish()
+    }
+
+    /// Get where the list came from, if it was read by one of the
+    /// parsers or set by [`LeapSecs::with_provenance()`][].
+    ///
+    /// This is for logging, so that when a list turns out to be bad
+    /// or out of date, operators can tell which source it came from.
+    ///
+    pub fn provenance(&self) -> Option<&Provenance> {
+        self.provenance.as_ref()
+    }
+
+    /// Set the value returned by [`LeapSecs::provenance()`][].
+    ///
+    pub fn with_provenance(mut self, provenance: Provenance) -> LeapSecs {
+        self.provenance = Some(provenance);
+        self
+    }
+
+    // the parsers call this, and readers that know more about the
+    // origin overwrite it afterwards
+    pub(crate) fn with_literal(self, format: Format) -> LeapSecs {
+        let origin = Origin::Literal;
+        let security = Security::Local;
+        self.with_provenance(Provenance { format, origin, security })
+    }
+
+    /// Check that converting the list through each of the formats in
+    /// `chain` in turn, and parsing it back, would give the same list
+    /// with the same [`LeapSecs::updated()`][] and
+    /// [`LeapSecs::expiry_inferred()`][].
+    ///
+    /// All the formats preserve the leap seconds and the expiry date
+    /// exactly, because expiry dates are always the 28th of a month,
+    /// so the differences are in the metadata. Fails with
+    /// [`Error::Lossy`][] at the first format that cannot represent
+    /// something about the list.
+    ///
+    /// This is a strict mode for conversions: call it before
+    /// converting to refuse lossy conversions.
+    ///
+    pub fn check_lossless(&self, chain: &[Format]) -> Result<()> {
+        for &format in chain {
+            if self.inferred {
+                return Err(Error::Lossy(format, "inferred expiry date"));
+            }
+            match (format, self.updated) {
+                (Format::Binary, Some(_)) | (Format::Text, Some(_)) => {
+                    return Err(Error::Lossy(format, "updated date"))
+                }
+                (Format::Nist, None) => {
+                    return Err(Error::Lossy(format, "missing updated date"))
+                }
+                _ => (),
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns true if converting the list through the formats in
+    /// `chain` is lossless, as described under
+    /// [`LeapSecs::check_lossless()`][].
+    ///
+    pub fn is_lossless(&self, chain: &[Format]) -> bool {
+        self.check_lossless(chain).is_ok()
+    }
+
+    /// Returns true if the list was constructed by a lenient parser
+    /// or [`LeapSecBuilder::finish_lenient()`][] after it had expired.
+    ///
+    pub fn was_expired(&self) -> bool {
+        self.expired
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Index<usize> for LeapSecs {
+    type Output = LeapSec;
+
+    fn index(&self, i: usize) -> &LeapSec {
+        self.get(i).unwrap()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'a> IntoIterator for &'a LeapSecs {
+    type Item = &'a LeapSec;
+    type IntoIter = core::slice::Iter<'a, LeapSec>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.list.iter()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl core::convert::TryFrom<Vec<(Gregorian, i16)>> for LeapSecs {
+    type Error = Error;
+    fn try_from(dates: Vec<(Gregorian, i16)>) -> Result<LeapSecs> {
+        LeapSecs::try_from_dates(dates)
+    }
+}
+
+//   ___
+//  / __|_____ _____ _ _ __ _ __ _ ___
+// | (__/ _ \ V / -_) '_/ _` / _` / -_)
+//  \___\___/\_/\___|_| \__,_\__, \___|
+//                          |___/
+
+/// The period covered by a [`LeapSecs`][] list, attached to an
+/// [`Error::Uncovered`][] when asked about a date outside it.
+///
+/// It is displayed as a message that says which dates the list
+/// covers, followed by the refresh hint if there is one.
+///
+#[cfg(feature = "alloc")]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Coverage {
+    /// The date that is not covered
+    pub date: Gregorian,
+    /// The first date covered by the list, at the start of 1972
+    pub first: Gregorian,
+    /// The last date covered by the list
+    pub expires: Gregorian,
+    /// How to refresh the list, from [`LeapSecs::with_refresh()`][]
+    pub refresh: Option<alloc::string::String>,
+}
+
+#[cfg(feature = "alloc")]
+impl Coverage {
+    /// Returns true if the date is after the list expired, or false
+    /// if it is before 1972.
+    ///
+    pub fn is_expired(&self) -> bool {
+        self.date > self.expires
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl core::fmt::Display for Coverage {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{} is not covered by the leap seconds list", self.date)?;
+        write!(f, ", which is valid from {}", self.first)?;
+        write!(f, " to {}", self.expires)?;
+        match &self.refresh {
+            Some(hint) => write!(f, "; to refresh it: {}", hint),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Whether a UTC time of day exists on a particular date, from
+/// [`LeapSecs::classify_second()`][].
+///
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum UtcSecond {
+    /// An ordinary second, from 00:00:00 to 23:59:59
+    Normal,
+    /// 23:59:60 on a day that ends with a positive leap second
+    Inserted,
+    /// 23:59:59 on a day that ends with a negative leap second, which
+    /// does not exist
+    Skipped,
+    /// Out of range, such as 24:00:00, or 23:59:60 on a day without
+    /// a positive leap second
+    Invalid,
+}
+
+//    _       ___   __
+//   /_\   __/ _ \ / _|
+//  / _ \ (_-< (_) |  _|
+// /_/ \_\/__/\___/|_|
+
+/// A view of a [`LeapSecs`][] list as of a particular date.
+///
+/// This answers queries as if it were the given date, which makes it
+/// possible to reproduce the behaviour of systems in the past.
+///
+/// An [`AsOf`][] view is obtained from [`LeapSecs::as_of()`][].
+///
+#[cfg(feature = "alloc")]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct AsOf<'a> {
+    list: &'a LeapSecs,
+    today: Gregorian,
+}
+
+#[cfg(feature = "alloc")]
+impl<'a> AsOf<'a> {
+    /// Get the date of this view
+    pub fn date(self) -> Gregorian {
+        self.today
+    }
+
+    /// Get the difference between UTC and TAI on the view's date.
+    pub fn dtai(self) -> Result<i16> {
+        self.dtai_at(self.today)
+    }
+
+    /// Get the difference between UTC and TAI on a particular `date`,
+    /// which fails if the list had expired as of the view's date.
+    ///
+    pub fn dtai_at(self, date: Gregorian) -> Result<i16> {
+        if self.is_expired() {
+            Err(Error::Expired(Gregorian::from(self.list.expires())))
+        } else {
+            self.list.dtai_at(date)
+        }
+    }
+
+    /// Returns true if the list had expired as of the view's date.
+    pub fn is_expired(self) -> bool {
+        self.list.is_expired_at(MJD::from(self.today))
+    }
+
+    /// Get the next leap second after the view's date, or [`None`][]
+    /// if the list has no more leap seconds before it expires.
+    ///
+    pub fn next_leap(self) -> Option<&'a LeapSec> {
+        self.list.after(self.today).filter(|leap| match leap.sign() {
+            Neg | Pos => true,
+            Zero | Exp => false,
+        })
+    }
+}
+
+//  _                  ___          ___      _ _    _
+// | |   ___ __ _ _ __/ __| ___ ___| _ )_  _(_) |__| |___ _ _
+// | |__/ -_) _` | '_ \__ \/ -_) __| _ \ || | | / _` / -_) '_|
+// |____\___\__,_| .__/___/\___\___|___/\_,_|_|_\__,_\___|_|
+//               |_|
+
+/// Construct a [`LeapSecs`][] list, checking it for validity.
+///
+/// There are two primary ways to use a [`LeapSecBuilder`][], depending on
+/// the format of the source data.
+///
+/// The parsers for the compact leap second list formats use
+/// [`LeapSecBuilder::push_gap()`][].
+///
+/// The [`nist`][] parser uses [`LeapSecBuilder::push_date()`][] and
+/// [`LeapSecBuilder::push_exp()`][].
+///
+/// It isn't an error to use both ways to construct a list, but why would
+/// you?
+///
+#[cfg(feature = "alloc")]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LeapSecBuilder {
+    list: Vec<LeapSec>,
+    max_gap: u16,
+}
+
+#[cfg(feature = "alloc")]
+impl Default for LeapSecBuilder {
+    fn default() -> LeapSecBuilder {
+        LeapSecBuilder::new()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl LeapSecBuilder {
+    /// Get a new [`LeapSecBuilder`][]
+    pub fn new() -> LeapSecBuilder {
+        LeapSecBuilder { list: Vec::new(), max_gap: MAX_GAP }
+    }
+
+    /// Allow gaps between leap seconds of up to `months`, instead of
+    /// the usual limit of 999 months (83 years).
+    ///
+    /// If leap seconds are abolished, a list might legitimately have
+    /// an expiry date more than 999 months after its last leap
+    /// second. Such a list can be written in the compact text format
+    /// with 4 digit gaps, which the parser accepts when
+    /// [`txt::ParseOptions::wide_gaps`][] is set.
+    ///
+    /// The compact binary format can represent longer gaps, but the
+    /// decoder rejects them.
+    ///
+    ///     # use leapsecs::*;
+    ///     let mut builder = LeapSecs::builder().with_max_gap(1200);
+    ///     builder.push_gap(6, Leap::Pos)?;
+    ///     builder.push_gap(1200, Leap::Exp)?;
+    ///     let list = builder.finish_lenient()?;
+    ///     assert_eq!(list.to_string(), "6+1200?");
+    ///     let mut builder = LeapSecs::builder();
+    ///     builder.push_gap(6, Leap::Pos)?;
+    ///     assert!(builder.push_gap(1200, Leap::Exp).is_err());
+    ///     # Ok::<(), Error>(())
+    ///
+    pub fn with_max_gap(mut self, months: u16) -> LeapSecBuilder {
+        self.max_gap = months;
+        self
+    }
+
+    /// Do the final consistency checks on the [`LeapSecBuilder`][] and
+    /// if they pass, return the completed  [`LeapSecs`][] list.
+    ///
+    /// The list is checked for expiry against the [`SystemClock`][].
+    /// Without the `std` feature there is no system clock, so the list
+    /// is not checked for expiry: you should use
+    /// [`LeapSecBuilder::finish_at()`][] instead.
+    ///
+    pub fn finish(self) -> Result<LeapSecs> {
+        #[cfg(feature = "std")]
+        {
+            self.finish_at(SystemClock)
+        }
+        #[cfg(not(feature = "std"))]
+        {
+            self.finish_expired()
+        }
+    }
+
+    /// Like [`LeapSecBuilder::finish()`][], but check for expiry
+    /// against the given [`Clock`][].
+    ///
+    /// An [`MJD`][] can be used as a fixed clock, so you can check
+    /// historical lists as if it were that date, and so that the
+    /// result does not depend on when it runs, for unit tests and
+    /// reproducible pipelines.
+    ///
+    ///     # use leapsecs::*;
+    ///     let mut builder = LeapSecs::builder();
+    ///     builder.push_gap(6, Leap::Pos)?;
+    ///     builder.push_gap(11, Leap::Exp)?;
+    ///     let expires = Gregorian(1973, 6, 28).mjd();
+    ///     let list = builder.clone().finish_at(expires)?;
+    ///     assert_eq!(list.expires(), expires);
+    ///     let late = builder.finish_at(expires + 1);
+    ///     assert!(matches!(late, Err(Error::Expired(_))));
+    ///     # Ok::<(), Error>(())
+    ///
+    pub fn finish_at<C: Clock>(self, clock: C) -> Result<LeapSecs> {
+        let list = self.finish_expired()?;
+        if list.is_expired_at(clock) {
+            Err(Error::Expired(Gregorian::from(list.expires())))
+        } else {
+            Ok(list)
+        }
+    }
+
+    /// Like [`LeapSecBuilder::finish()`][], but also call `check` on
+    /// each entry in the list, including the first and last entries.
+    ///
+    /// This allows you to enforce local policies. If `check` returns
+    /// `false` the entry is rejected with [`Error::Policy`][]. For
+    /// example, to insist that leap seconds only happen at the end of
+    /// June or December,
+    ///
+    ///     # use leapsecs::*;
+    ///     let mut builder = LeapSecs::builder();
+    ///     builder.push_gap(6, Leap::Pos)?;
+    ///     builder.push_gap(5, Leap::Pos)?;
+    ///     builder.push_gap(999, Leap::Exp)?;
+    ///     let result = builder.finish_with(|leap| match leap.sign() {
+    ///         Leap::Neg | Leap::Pos => [1, 7].contains(&leap.date().month()),
+    ///         Leap::Zero | Leap::Exp => true,
+    ///     });
+    ///     assert!(matches!(result, Err(Error::Policy(_))));
+    ///     # Ok::<(), Error>(())
+    ///
+    pub fn finish_with<F>(self, mut check: F) -> Result<LeapSecs>
+    where
+        F: FnMut(&LeapSec) -> bool,
+    {
+        if let Some(&leap) = self.list.iter().find(|leap| !check(leap)) {
+            return Err(Error::Policy(leap));
+        }
+        self.finish()
+    }
+
+    /// Like [`LeapSecBuilder::finish()`][], except that if the list
+    /// has expired it is returned anyway, marked so that
+    /// [`LeapSecs::was_expired()`][] returns true.
+    ///
+    /// This is for examining historical lists.
+    ///
+    pub fn finish_lenient(self) -> Result<LeapSecs> {
+        #[cfg(feature = "std")]
+        {
+            self.finish_lenient_at(SystemClock)
+        }
+        #[cfg(not(feature = "std"))]
+        {
+            self.finish_expired()
+        }
+    }
+
+    /// Like [`LeapSecBuilder::finish_lenient()`][], but check whether
+    /// the list has expired against the given [`Clock`][], so that
+    /// [`LeapSecs::was_expired()`][] does not depend on the system
+    /// clock.
+    ///
+    pub fn finish_lenient_at<C: Clock>(self, clock: C) -> Result<LeapSecs> {
+        let mut list = self.finish_expired()?;
+        list.expired = list.is_expired_at(clock);
+        Ok(list)
+    }
+
+    /// Like [`LeapSecBuilder::finish_at()`][], but with an
+    /// [`ExpiryHorizon`][] that says how close to its expiry date the
+    /// list can be before it is rejected, and before `warn` is
+    /// called with a [`Warning`][], for example,
+    ///
+    ///     # use leapsecs::*;
+    ///     let today = Gregorian(2021, 1, 1).mjd();
+    ///     let mut builder = LeapSecs::builder();
+    ///     builder.push_gap(6, Leap::Pos)?;
+    ///     builder.push_gap(582, Leap::Exp)?;
+    ///     let mut warnings = vec![];
+    ///     let horizon = ExpiryHorizon { warn_days: 30, fail_days: 0 };
+    ///     let list = builder.finish_horizon(horizon, today, |w| warnings.push(w))?;
+    ///     assert!(!list.was_expired());
+    ///     assert_eq!(warnings, [Warning::ExpiresSoon(Gregorian(2021, 1, 28), 27)]);
+    ///     # Ok::<(), Error>(())
+    ///
+    /// If the `fail_days` are negative, a list that expired fewer than
+    /// that many days ago is accepted, marked so that
+    /// [`LeapSecs::was_expired()`][] returns true.
+    ///
+    pub fn finish_horizon<C, F>(
+        self,
+        horizon: ExpiryHorizon,
+        clock: C,
+        mut warn: F,
+    ) -> Result<LeapSecs>
+    where
+        C: Clock,
+        F: FnMut(Warning),
+    {
+        let today = clock.today();
+        let mut list = self.finish_expired()?;
+        let expires = Gregorian::from(list.expires());
+        if list.expires_within_at(horizon.fail_days, today) {
+            return Err(Error::Expired(expires));
+        }
+        list.expired = list.is_expired_at(today);
+        if list.expires_within_at(horizon.warn_days, today) {
+            warn(Warning::ExpiresSoon(expires, list.expires() - today));
+        }
+        Ok(list)
+    }
+
+    /// Like [`LeapSecBuilder::finish()`][], but also return warnings
+    /// about anything suspicious in the list that is not bad enough
+    /// to reject it, so that consumers of third-party data can log
+    /// them. See [`LeapSecBuilder::finish_with_warnings_at()`][].
+    ///
+    /// This requires the `std` feature.
+    ///
+    #[cfg(feature = "std")]
+    pub fn finish_with_warnings(self) -> Result<(LeapSecs, Vec<Warning>)> {
+        self.finish_with_warnings_at(SystemClock)
+    }
+
+    /// Like [`LeapSecBuilder::finish_at()`][], but also return a list
+    /// of warnings about:
+    ///
+    ///   * a list that expires soon, as with the default
+    ///     [`ExpiryHorizon`][];
+    ///
+    ///   * an expiry date more than 18 months from today, which is
+    ///     further ahead than the IERS announces;
+    ///
+    ///   * a gap of more than 10 years between leap seconds;
+    ///
+    ///   * a negative leap second, which has never happened;
+    ///
+    ///   * a leap second at the end of a month other than June or
+    ///     December, which has never happened either.
+    ///
+    /// For example,
+    ///
+    ///     # use leapsecs::*;
+    ///     let today = Gregorian(1972, 1, 1).mjd();
+    ///     let mut builder = LeapSecs::builder();
+    ///     builder.push_gap(6, Leap::Neg)?;
+    ///     builder.push_gap(6, Leap::Exp)?;
+    ///     let (list, warnings) = builder.finish_with_warnings_at(today)?;
+    ///     assert_eq!(warnings, [Warning::NegativeLeap(Gregorian(1972, 7, 1))]);
+    ///     # Ok::<(), Error>(())
+    ///
+    pub fn finish_with_warnings_at<C: Clock>(
+        self,
+        clock: C,
+    ) -> Result<(LeapSecs, Vec<Warning>)> {
+        let today = clock.today();
+        let mut warnings = Vec::new();
+        let horizon = ExpiryHorizon::default();
+        let list = self.finish_horizon(horizon, today, |w| warnings.push(w))?;
+        let expires = Gregorian::from(list.expires());
+        if expires > Gregorian::from(today).add_months(FAR_EXPIRY) {
+            warnings.push(Warning::FarExpiry(expires, list.expires() - today));
+        }
+        let mut last = LeapSec::start();
+        for &leap in list.iter().skip(1) {
+            if let Exp | Zero = leap.sign {
+                continue;
+            }
+            let months = leap.month as i32 - last.month as i32;
+            if months > LONG_GAP {
+                let (from, to) = (last.date(), leap.date());
+                warnings.push(Warning::LongGap(from, to, months));
+            }
+            if leap.sign == Neg {
+                warnings.push(Warning::NegativeLeap(leap.date()));
+            }
+            if !leap.is_preferred_month() {
+                warnings.push(Warning::UnusualMonth(leap.date()));
+            }
+            last = leap;
+        }
+        Ok((list, warnings))
+    }
+
+    /// Finish a list from a source that does not say when it expires,
+    /// such as `tai-utc.dat`, by adding an expiry date chosen by the
+    /// `policy`.
+    ///
+    /// The list is marked so that [`LeapSecs::expiry_inferred()`][]
+    /// returns true. Such sources are usually old, so the list is
+    /// returned even if it has expired, as if by
+    /// [`LeapSecBuilder::finish_lenient()`][].
+    ///
+    pub fn finish_inferred(mut self, policy: ExpiryPolicy) -> Result<LeapSecs> {
+        let last = self.last_entry()?;
+        let month = last.month as i32;
+        let month = match policy {
+            // June is month 5 and December is month 11, and the
+            // expiry must be strictly after the last entry
+            ExpiryPolicy::NextHalfYear => month + 6 - (month + 1) % 6,
+            ExpiryPolicy::Months(months) => month + months,
+        };
+        self.push_leap_sec(last, month - last.month as i32, Exp, month, None)?;
+        let mut list = self.finish_lenient()?;
+        list.inferred = true;
+        Ok(list)
+    }
+
+    // all the checks except for expiry
+    fn finish_expired(mut self) -> Result<LeapSecs> {
+        self.check_finished()?;
+        self.list.shrink_to_fit();
+        Ok(LeapSecs {
+            list: self.list,
+            expired: false,
+            inferred: false,
+            updated: None,
+            refresh: None,
+            provenance: None,
+        })
+    }
+
+    /// Add an entry to the list
+    ///
+    /// `gap` is the time between the last entry and this one, measured in
+    /// months
+    ///
+    /// `sign` is what kind of leap second this is.
+    ///
+    /// When `sign` is [`Leap::Zero`][], `push_gap()` builds a single entry
+    /// in multiple steps.
+    ///
+    /// When `sign` is [`Leap::Exp`][], `push_gap()` adds the expiry date as
+    /// the last entry.
+    ///
+    /// When using `push_gap()`, there is no need to explicitly add the
+    /// first (non-leap-second) entry in the list.
+    ///
+    pub fn push_gap(&mut self, gap: i32, sign: Leap) -> Result<()> {
+        Entries::push_gap(self, gap, sign)
+    }
+
+    /// Add the expiry date to the list.
+    ///
+    /// The date must be the 28th of the month.
+    ///
+    /// This must be done last, before calling [`LeapSecBuilder::finish()`][]
+    ///
+    pub fn push_exp(&mut self, date: Gregorian) -> Result<()> {
+        Entries::push_exp(self, date)
+    }
+
+    /// Add an entry to the list
+    ///
+    /// `date` must be the first of the month immediately after the leap
+    /// second.
+    ///
+    /// `dtai` is the difference between UTC and TAI starting after the leap
+    /// second at the given `date`. [`LeapSec::dtai()`][] discusses why DTAI
+    /// values use `i16`.
     ///
     pub fn push_date(&mut self, date: Gregorian, dtai: i16) -> Result<()> {
+        Entries::push_date(self, date, dtai)
+    }
+}
+
+/// How [`LeapSecBuilder::finish_horizon()`][] treats a list that is
+/// close to its expiry date.
+///
+/// The default is to warn four weeks before expiry and fail after
+/// the expiry date, like [`LeapSecBuilder::finish()`][].
+///
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct ExpiryHorizon {
+    /// Warn if the list will have expired this many days from now
+    pub warn_days: i32,
+    /// Fail if the list will have expired this many days from now
+    pub fail_days: i32,
+}
+
+impl Default for ExpiryHorizon {
+    fn default() -> ExpiryHorizon {
+        ExpiryHorizon { warn_days: 28, fail_days: 0 }
+    }
+}
+
+// months ahead that an expiry date is suspicious
+#[cfg(feature = "alloc")]
+const FAR_EXPIRY: i32 = 18;
+
+// months between leap seconds that is suspicious
+#[cfg(feature = "alloc")]
+const LONG_GAP: i32 = 120;
+
+/// Something worth telling an operator about a list that is not bad
+/// enough to be an [`enum@Error`][], from
+/// [`LeapSecBuilder::finish_horizon()`][],
+/// [`LeapSecBuilder::finish_with_warnings()`][], or
+/// [`nist::read_str_warn()`][].
+///
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Warning {
+    /// The list expires on this date, in this many days from now, or
+    /// it has expired if the number is negative.
+    ExpiresSoon(Gregorian, i32),
+    /// The list expires on this date, this many days from now, which
+    /// is more than 18 months ahead.
+    FarExpiry(Gregorian, i32),
+    /// There are more than 10 years between the leap seconds on
+    /// these dates, with the number of months between them.
+    LongGap(Gregorian, Gregorian, i32),
+    /// There is a negative leap second before this date.
+    NegativeLeap(Gregorian),
+    /// A NIST `leap-seconds.list` has a line that looks like a
+    /// directive but is not one that the parser knows about. It has
+    /// the line number and the line.
+    #[cfg(feature = "std")]
+    UnknownDirective(usize, String),
+    /// There is a leap second before this date that is not at the end
+    /// of June or December, see [`LeapSec::is_preferred_month()`][].
+    UnusualMonth(Gregorian),
+}
+
+impl core::fmt::Display for Warning {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match *self {
+            Warning::ExpiresSoon(date, days) if days < 0 => write!(
+                f,
+                "leap seconds list expired {} days ago ({})",
+                -days, date
+            ),
+            Warning::ExpiresSoon(date, days) => write!(
+                f,
+                "leap seconds list expires in {} days ({})",
+                days, date
+            ),
+            Warning::FarExpiry(date, days) => write!(
+                f,
+                "leap seconds list expires suspiciously far ahead, \
+                 in {} days ({})",
+                days, date
+            ),
+            Warning::LongGap(from, to, months) => write!(
+                f,
+                "no leap seconds for {} months from {} to {}",
+                months, from, to
+            ),
+            Warning::NegativeLeap(date) => {
+                write!(f, "negative leap second before {}", date)
+            }
+            #[cfg(feature = "std")]
+            Warning::UnknownDirective(number, ref line) => {
+                write!(f, "unknown directive on line {}: {}", number, line)
+            }
+            Warning::UnusualMonth(date) => write!(
+                f,
+                "leap second before {} is not at the end of June or December",
+                date
+            ),
+        }
+    }
+}
+
+/// How [`LeapSecBuilder::finish_inferred()`][] chooses an expiry
+/// date for a list whose source does not have one.
+///
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ExpiryPolicy {
+    /// The next 28th of June or December after the last entry in the
+    /// list, which is the soonest that another leap second could
+    /// have happened, given six months notice.
+    NextHalfYear,
+    /// The 28th of the month this many months after the last entry.
+    Months(i32),
+}
+
+/// Which months can end with a leap second.
+///
+/// ITU-R TF.460 says leap seconds should preferably be at the end of
+/// June or December, then at the end of March or September, and
+/// otherwise at the end of any month. A convention can be enforced
+/// with [`LeapSecBuilder::finish_with()`][], for example,
+///
+///     # use leapsecs::*;
+///     let mut builder = LeapSecs::builder();
+///     builder.push_gap(6, Leap::Pos)?;
+///     builder.push_gap(3, Leap::Pos)?;
+///     builder.push_gap(999, Leap::Exp)?;
+///     let result = builder.finish_with(|leap| {
+///         LeapConvention::HalfYearly.allows(leap)
+///     });
+///     assert!(matches!(result, Err(Error::Policy(_))));
+///     # Ok::<(), Error>(())
+///
+/// It is also used by [`LeapSecs::next_possible_leap_with()`][].
+///
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum LeapConvention {
+    /// The end of June or December, which is where all the leap
+    /// seconds so far have been.
+    #[default]
+    HalfYearly,
+    /// The end of March, June, September, or December.
+    Quarterly,
+    /// The end of any month.
+    Monthly,
+}
+
+impl LeapConvention {
+    // the number of months between possible leap seconds
+    fn months(self) -> i32 {
+        match self {
+            LeapConvention::HalfYearly => 6,
+            LeapConvention::Quarterly => 3,
+            LeapConvention::Monthly => 1,
+        }
+    }
+
+    /// Returns true if the `leap` is at the end of a month that this
+    /// convention allows, or if it is not a leap second.
+    ///
+    pub fn allows(self, leap: &LeapSec) -> bool {
+        match leap.sign {
+            Neg | Pos => (leap.date().month() - 1) % self.months() == 0,
+            Zero | Exp => true,
+        }
+    }
+}
+
+/// The formats that a list can be converted to, for checking
+/// conversions with [`LeapSecs::check_lossless()`][], and that it
+/// can be parsed from, in its [`Provenance`][].
+///
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Format {
+    /// The compact binary format, including DNS RDATA
+    Binary,
+    /// The JSON format, which has an optional updated date
+    Json,
+    /// The NIST `leap-seconds.list` format, which requires an updated
+    /// date
+    Nist,
+    /// The compact text format
+    Text,
+}
+
+impl core::fmt::Display for Format {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(match self {
+            Format::Binary => "compact binary",
+            Format::Json => "JSON",
+            Format::Nist => "NIST",
+            Format::Text => "compact text",
+        })
+    }
+}
+
+/// Where a list came from, from [`LeapSecs::provenance()`][].
+///
+/// It is displayed like,
+///
+/// ```text
+/// NIST list from https://hpiers.obspm.fr/iers/bul/bulc/ntp/leap-seconds.list
+/// ```
+///
+#[cfg(feature = "alloc")]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Provenance {
+    /// The format the list was parsed from
+    pub format: Format,
+    /// Where the data came from
+    pub origin: Origin,
+    /// How well the data was protected on its way
+    pub security: Security,
+}
+
+#[cfg(feature = "alloc")]
+impl core::fmt::Display for Provenance {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{} list from {}", self.format, self.origin)?;
+        match self.origin {
+            Origin::Url(_) => write!(f, " ({})", self.security),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// How well the transport that delivered a list protected it from
+/// tampering, in increasing order of preference.
+///
+#[derive(Copy, Clone, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub enum Security {
+    /// Plain FTP or HTTP, which anyone on the path can tamper with
+    Insecure,
+    /// TLS, such as HTTPS, with the usual certificate checks
+    Tls,
+    /// TLS with a pinned public key
+    Pinned,
+    /// A local file or data supplied by the caller, which is as
+    /// trustworthy as the system itself
+    Local,
+}
+
+impl core::fmt::Display for Security {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(match self {
+            Security::Insecure => "insecure",
+            Security::Tls => "TLS",
+            Security::Pinned => "pinned TLS",
+            Security::Local => "local",
+        })
+    }
+}
+
+/// Where the data in a [`Provenance`][] came from.
+///
+#[cfg(feature = "alloc")]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Origin {
+    /// A file, with its name
+    File(alloc::string::String),
+    /// A string or bytes supplied by the caller
+    Literal,
+    /// A URL
+    Url(alloc::string::String),
+}
+
+#[cfg(feature = "alloc")]
+impl core::fmt::Display for Origin {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Origin::File(name) => write!(f, "file {}", name),
+            Origin::Literal => write!(f, "a literal"),
+            Origin::Url(url) => write!(f, "{}", url),
+        }
+    }
+}
+
+//  ___     _       _
+// | __|_ _| |_ _ _(_)___ ___
+// | _|| ' \  _| '_| / -_|_-<
+// |___|_||_\__|_| |_\___/__/
+
+// The consistency checks are shared between the heap-allocated
+// LeapSecBuilder and the fixed-size LeapSecsFixed, which only differ
+// in how they store the entries.
+
+pub(crate) trait Entries {
+    fn entries(&self) -> &[LeapSec];
+    fn add_entry(&mut self, leap: LeapSec) -> Result<()>;
+    fn pop_entry(&mut self);
+
+    fn max_gap(&self) -> u16 {
+        MAX_GAP
+    }
+
+    fn last_entry(&self) -> Result<LeapSec> {
+        self.entries().last().copied().ok_or(Error::Empty)
+    }
+
+    fn push_start(&mut self) -> Result<()> {
+        self.add_entry(LeapSec::start())
+    }
+
+    fn push_leap_sec(
+        &mut self,
+        mut last: LeapSec,
+        mut gap: i32,
+        sign: Leap,
+        month: i32,
+        dtai: Option<i16>,
+    ) -> Result<()> {
+        if last.sign == Exp {
+            return Err(Error::LeapAfterExp(last.date(), date_of(month, 1)));
+        }
+        if last.sign == Zero && last.month != 0 {
+            gap += last.gap as i32;
+            self.pop_entry();
+            last = self.last_entry()?;
+        }
+        let gap = match u16::try_from(gap) {
+            Ok(gap) if 1 <= gap && gap <= self.max_gap() => gap,
+            _ => return Err(Error::Gap(last.date(), gap, date_of(month, 1))),
+        };
+        let month = u16::try_from(month)?;
+        assert_eq!(last.month + gap, month);
+        assert_eq!(sign == Exp, dtai.is_none());
+        self.add_entry(LeapSec { gap, sign, month, dtai })
+    }
+
+    // see LeapSecBuilder::push_gap()
+    fn push_gap(&mut self, gap: i32, sign: Leap) -> Result<()> {
+        if self.entries().is_empty() {
+            self.push_start()?;
+        }
+        let last = self.last_entry()?;
+        let month = last.month as i32 + gap;
+        let ldtai = i32::from(last.dtai()?);
+        // a long enough run of leap seconds can overflow DTAI
+        let dtai = match sign {
+            Zero => Some(i16::try_from(ldtai)?),
+            Neg => Some(i16::try_from(ldtai - 1)?),
+            Pos => Some(i16::try_from(ldtai + 1)?),
+            Exp => None,
+        };
+        self.push_leap_sec(last, gap, sign, month, dtai)
+    }
+
+    // see LeapSecBuilder::push_exp()
+    #[cfg(feature = "alloc")]
+    fn push_exp(&mut self, date: Gregorian) -> Result<()> {
+        let month = month_of(date, EXPIRES_DATE)?;
+        let last = self.last_entry()?;
+        let gap = month - last.month as i32;
+        self.push_leap_sec(last, gap, Exp, month, None)
+    }
+
+    // see LeapSecBuilder::push_date()
+    #[cfg(feature = "alloc")]
+    fn push_date(&mut self, date: Gregorian, dtai: i16) -> Result<()> {
         let month = month_of(date, 1)?;
-        let last = if let Ok(last) = self.last() {
+        let last = if let Ok(last) = self.last_entry() {
             last
         } else if month == 0 && dtai == 10 {
-            self.push_start();
-            return Ok(());
+            return self.push_start();
         } else {
             return Err(Error::FalseStart(date, dtai));
         };
@@ -558,18 +2566,136 @@ impl LeapSecBuilder {
         };
         self.push_leap_sec(last, gap, sign, month, Some(dtai))
     }
+
+    fn check_finished(&self) -> Result<()> {
+        if self.last_entry()?.sign != Exp {
+            return Err(Error::Truncated);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Entries for LeapSecBuilder {
+    fn entries(&self) -> &[LeapSec] {
+        &self.list
+    }
+
+    fn add_entry(&mut self, leap: LeapSec) -> Result<()> {
+        self.list.push(leap);
+        Ok(())
+    }
+
+    fn pop_entry(&mut self) {
+        self.list.pop();
+    }
+
+    fn max_gap(&self) -> u16 {
+        self.max_gap
+    }
+}
+
+// The streaming encoders and bin::LeapSecsRef only keep the last two
+// entries, which is enough for the checks: push_leap_sec() looks at the last entry, and
+// at the one before when it merges a long gap.
+
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct Window {
+    list: [LeapSec; 2],
+    len: usize,
+}
+
+impl Default for Window {
+    fn default() -> Window {
+        Window { list: [LeapSec::start(); 2], len: 0 }
+    }
 }
 
-#[cfg(test)]
+impl Entries for Window {
+    fn entries(&self) -> &[LeapSec] {
+        &self.list[..self.len]
+    }
+
+    fn add_entry(&mut self, leap: LeapSec) -> Result<()> {
+        if self.len == 2 {
+            self.list[0] = self.list[1];
+            self.list[1] = leap;
+        } else {
+            self.list[self.len] = leap;
+            self.len += 1;
+        }
+        Ok(())
+    }
+
+    fn pop_entry(&mut self) {
+        self.len -= 1;
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Window {
+    // the most recent complete entry, if the last push finished one
+    pub(crate) fn finished(&self) -> Option<LeapSec> {
+        self.last_entry().ok().filter(|leap| leap.sign != Zero)
+    }
+}
+
+fn before(list: &[LeapSec], date: Gregorian) -> Option<&LeapSec> {
+    let mut prev = None;
+    for leap in list {
+        if leap.date() > date {
+            return prev;
+        } else {
+            prev = Some(leap);
+        }
+    }
+    list.last()
+}
+
+fn dtai_at(list: &[LeapSec], date: Gregorian) -> Result<i16> {
+    match before(list, date) {
+        Some(leap) => leap.dtai(),
+        None => Err(Error::TooEarly(date)),
+    }
+}
+
+// check that a date is covered by a list without a refresh hint,
+// like LeapSecs::check_covered(), falling back to simpler errors
+// when there is no alloc for a Coverage
+
+fn check_covered(date: Gregorian, expires: MJD) -> Result<()> {
+    let mjd = MJD::from(date);
+    if UTC_START_1972 <= mjd && mjd <= expires {
+        return Ok(());
+    }
+    #[cfg(feature = "alloc")]
+    {
+        Err(Error::Uncovered(Coverage {
+            date,
+            first: Gregorian::from(UTC_START_1972),
+            expires: Gregorian::from(expires),
+            refresh: None,
+        }))
+    }
+    #[cfg(not(feature = "alloc"))]
+    {
+        if mjd < UTC_START_1972 {
+            Err(Error::TooEarly(date))
+        } else {
+            Err(Error::Expired(Gregorian::from(expires)))
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
 mod lib_test {
     use crate::*;
-    use std::str::FromStr;
 
     #[test]
     fn test() {
-        let text = "6+6+12+12+12+12+12+12+12+18+12+12+24+30+24+\
-                    12+18+12+12+18+18+18+84+36+42+36+18+59?";
-        let list = LeapSecs::from_str(text).unwrap();
+        let text = history!("59?");
+        let list = LeapSecs::from_str_at(text, Gregorian(2021, 1, 1).mjd());
+        let list = list.unwrap();
         let mut it = list.iter().peekable();
         let mut prev = None;
         while let this @ Some(_) = it.next() {
@@ -582,6 +2708,7 @@ mod lib_test {
             assert_eq!(prev, list.before(before), "before {}", before);
             assert_eq!(prev, list.before(yesterday), "before {}", yesterday);
             assert_eq!(this, list.before(today), "before {}", today);
+            assert_eq!(this, list.get_by_mjd(inst), "get_by_mjd {}", inst);
             assert_eq!(this, list.before(after), "before {}", after);
             assert_eq!(this, list.after(before), "after {}", before);
             assert_eq!(this, list.after(yesterday), "after {}", yesterday);
@@ -590,4 +2717,411 @@ mod lib_test {
             prev = this;
         }
     }
+
+    #[test]
+    fn ordering() {
+        use std::collections::{BTreeSet, HashSet};
+
+        let history = ["6+6+2?", "6+6+5?", "6+6+6+5?", "6+6+6+12+99?"];
+        let lists: Vec<_> = history
+            .iter()
+            .map(|text| LeapSecs::from_str_lenient(text).unwrap())
+            .collect();
+        for pair in lists.windows(2) {
+            assert!(pair[0] < pair[1]);
+        }
+        let mut probes = lists.clone();
+        probes.extend(lists.iter().cloned().rev());
+        let hashed: HashSet<_> = probes.iter().cloned().collect();
+        let sorted: BTreeSet<_> = probes.into_iter().collect();
+        assert_eq!(hashed.len(), lists.len());
+        assert!(sorted.into_iter().eq(lists));
+
+        // an expiry date sorts after a leap second in the same month
+        let leap = LeapSecs::from_str_lenient("6+6+6+6?").unwrap()[3];
+        let exp = LeapSecs::from_str_lenient("6+6+6?").unwrap()[3];
+        assert_eq!(leap.date().month(), exp.date().month());
+        assert!(leap < exp);
+        assert!(Leap::Zero < Leap::Neg && Leap::Pos < Leap::Exp);
+    }
+
+    #[test]
+    fn segments() {
+        let text = history!("59?");
+        let list = LeapSecs::from_str_lenient(text).unwrap();
+        let segments: Vec<_> = list.segments().collect();
+        assert_eq!(list.len() - 1, segments.len());
+        assert_eq!((UTC_START_1972, 10), (segments[0].0.start, 10));
+        for (pair, leap) in segments.windows(2).zip(list.iter().skip(1)) {
+            assert_eq!(pair[0].0.end, pair[1].0.start);
+            assert_eq!(pair[0].1 + 1, pair[1].1);
+            assert_eq!(Some(pair[1].1), leap.dtai().ok());
+        }
+        let last = segments.last().unwrap();
+        assert_eq!((list.expires() + 1, 37), (last.0.end, last.1));
+
+        let elapsed: Vec<_> = list.elapsed().collect();
+        assert_eq!(list.len(), elapsed.len());
+        assert_eq!((list[0], 0), elapsed[0]);
+        let leap = elapsed[list.len() - 2];
+        assert_eq!(Gregorian(2017, 1, 1), leap.0.date());
+        assert_eq!(16437 * 86400 + 27, leap.1);
+        // the last segment includes the expiry date
+        let pairs = elapsed.windows(2).zip(&segments).take(segments.len() - 1);
+        for (pair, seg) in pairs {
+            let days = i64::from(seg.0.end - seg.0.start);
+            let leap = i64::from(pair[1].0.sign() == Leap::Pos);
+            assert_eq!(days * 86400 + leap, pair[1].1 - pair[0].1);
+        }
+    }
+
+    #[test]
+    fn verify_history() {
+        let pinned = LeapSecs::from_str_lenient("6+6+12+59?").unwrap();
+        for &(text, ok) in &[
+            ("6+6+12+59?", true),
+            ("6+6+12+65?", true),
+            ("6+6+12+60+12?", true),
+            ("6+6+12+59+12?", false),
+            ("6+6+12+53?", false),
+            ("6+6+13+59?", false),
+            ("6+6-12+59?", false),
+            ("6+6?", false),
+        ] {
+            let list = LeapSecs::from_str_lenient(text).unwrap();
+            assert_eq!(ok, list.verify_history(&pinned).is_ok(), "{}", text);
+            assert_eq!(ok, list.extends(&pinned), "{}", text);
+            assert_eq!(ok, pinned.is_prefix_of(&list), "{}", text);
+        }
+        let older = LeapSecs::from_str_lenient("6+6+12+53?").unwrap();
+        assert!(older.is_prefix_of(&pinned) && pinned.extends(&older));
+        assert!(!pinned.is_prefix_of(&older) && !older.extends(&pinned));
+    }
+
+    #[test]
+    fn hypothetical() {
+        let updated = Gregorian(1973, 1, 1).mjd();
+        let list = LeapSecs::from_str_lenient("6+6+12+59?").unwrap();
+        let list = list.with_updated(updated).with_refresh("make update");
+        let date = Gregorian(1980, 1, 1);
+        let got = list.with_hypothetical(date, Leap::Neg).unwrap();
+        assert_eq!("6+6+12+72-5?", got.to_string());
+        assert_eq!(Some(updated), got.updated());
+        assert_eq!(Some("make update"), got.refresh());
+        assert_eq!(list.provenance(), got.provenance());
+        for &sign in &[Leap::Zero, Leap::Exp] {
+            let err = list.with_hypothetical(date, sign).unwrap_err();
+            assert!(
+                matches!(err, Error::NotLeap(d, s) if d == date && s == sign)
+            );
+        }
+    }
+
+    #[test]
+    fn appended() {
+        let list = LeapSecs::from_str_lenient("6+6+12+59?").unwrap();
+        for leap in &list {
+            let text = leap.to_string();
+            let parsed: LeapSec = text.parse().unwrap();
+            assert_eq!(text, parsed.to_string());
+            assert_eq!(
+                (leap.date(), leap.sign()),
+                (parsed.date(), parsed.sign())
+            );
+        }
+        let neg: LeapSec = "1974-07-01 -1 DTAI 12".parse().unwrap();
+        let got = list.with_appended(neg).unwrap();
+        assert_eq!("6+6+12+6-53?", got.to_string());
+        let exp = LeapSec::try_new(Gregorian(1980, 1, 28), Leap::Exp, 0);
+        let got = got.with_appended(exp.unwrap()).unwrap();
+        assert_eq!("6+6+12+6-66?", got.to_string());
+        let wrong: LeapSec = "1974-07-01 +1 DTAI 12".parse().unwrap();
+        let err = list.with_appended(wrong);
+        assert!(matches!(err, Err(Error::WrongLeap(..))));
+        let late: LeapSec = "1980-01-01 +1 DTAI 14".parse().unwrap();
+        let err = list.with_appended(late);
+        assert!(matches!(err, Err(Error::LeapAfterExp(..))));
+        let err = list.with_appended(list[0]);
+        assert!(matches!(err, Err(Error::FalseStart(..))));
+        let updated = Gregorian(1973, 1, 1).mjd();
+        let dated = list.with_updated(updated).with_literal(Format::Binary);
+        let got = dated.with_appended(neg).unwrap();
+        assert_eq!(Some(updated), got.updated());
+        assert_eq!(dated.provenance(), got.provenance());
+        let mut dates = vec![
+            (Gregorian(1972, 1, 1), 10),
+            (Gregorian(1972, 7, 1), 11),
+            (Gregorian(1973, 1, 1), 12),
+            (Gregorian(1974, 1, 1), 13),
+            (Gregorian(1978, 12, 28), 13),
+        ];
+        let got = LeapSecs::try_from_dates(dates.iter().copied());
+        assert!(matches!(got, Err(Error::Expired(_))));
+        dates.last_mut().unwrap().0 = Gregorian(2050, 1, 28);
+        let got = LeapSecs::try_from(dates.clone()).unwrap();
+        assert_eq!("6+6+12+912?", got.to_string());
+        dates.last_mut().unwrap().1 = 14;
+        let err = LeapSecs::try_from(dates);
+        assert!(matches!(err, Err(Error::WrongLeap(..))));
+        let start = (Gregorian(1972, 1, 1), 11);
+        let err = LeapSecs::try_from(vec![start, (Gregorian(2050, 1, 28), 11)]);
+        assert!(matches!(err, Err(Error::FalseStart(..))));
+        assert!(LeapSecs::try_from_dates(vec![]).is_err());
+        for bad in &[
+            "",
+            "1974-07-01",
+            "1974-7-01 +1 DTAI 13",
+            "1974-07-01 +2 DTAI 13",
+            "1974-07-01 +1 UTC 13",
+            "1974-07-01 +1 DTAI x",
+            "1974-07-01 ?? 13",
+            "1974-13-01 +1 DTAI 13",
+            "1974-07-28 +1 DTAI 13",
+            "1974-07-01 DTAI 10",
+        ] {
+            assert!(bad.parse::<LeapSec>().is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn horizon() {
+        let text = "6+6+12+59?";
+        let list = LeapSecs::from_str_lenient(text).unwrap();
+        let expires = list.expires();
+        assert!(!list.expires_within_at(0, expires));
+        assert!(list.expires_within_at(1, expires));
+        assert!(list.expires_within_at(0, expires + 1));
+
+        let build = || {
+            let mut builder = LeapSecs::builder();
+            for leap in list.iter().skip(1) {
+                builder.push_gap(leap.gap() as i32, leap.sign()).unwrap();
+            }
+            builder
+        };
+        let mut warnings = vec![];
+        let horizon = ExpiryHorizon::default();
+        let finish = |clock, warnings: &mut Vec<Warning>| {
+            build().finish_horizon(horizon, clock, |w| warnings.push(w))
+        };
+        assert!(finish(expires - 28, &mut warnings).is_ok());
+        assert!(warnings.is_empty());
+        assert!(finish(expires - 27, &mut warnings).is_ok());
+        let date = Gregorian::from(expires);
+        assert_eq!(warnings, [Warning::ExpiresSoon(date, 27)]);
+        let err = finish(expires + 1, &mut warnings);
+        assert!(matches!(err, Err(Error::Expired(_))));
+
+        let grace = ExpiryHorizon { warn_days: 0, fail_days: -7 };
+        let mut warnings = vec![];
+        let list = build()
+            .finish_horizon(grace, expires + 7, |w| warnings.push(w))
+            .unwrap();
+        assert!(list.was_expired());
+        let want = "leap seconds list expired 7 days ago (1978-12-28)";
+        assert_eq!(want, warnings[0].to_string());
+        let err = build().finish_horizon(grace, expires + 8, |_| ());
+        assert!(matches!(err, Err(Error::Expired(_))));
+    }
+
+    #[test]
+    fn leap_indicator() {
+        let list = LeapSecs::from_str_lenient("6+6-12+7?").unwrap();
+        for &(date, li) in &[
+            (Gregorian(1972, 5, 31), LeapIndicator::NoWarning),
+            (Gregorian(1972, 6, 1), LeapIndicator::Insert),
+            (Gregorian(1972, 6, 30), LeapIndicator::Insert),
+            (Gregorian(1972, 7, 1), LeapIndicator::NoWarning),
+            (Gregorian(1972, 12, 31), LeapIndicator::Delete),
+            (Gregorian(1974, 1, 1), LeapIndicator::NoWarning),
+            (Gregorian(1974, 8, 28), LeapIndicator::NoWarning),
+            (Gregorian(1974, 8, 29), LeapIndicator::Unsynchronized),
+        ] {
+            assert_eq!(li, list.leap_indicator_at(date.mjd()), "{}", date);
+        }
+        assert_eq!(3, LeapIndicator::Unsynchronized as u8);
+    }
+
+    #[test]
+    fn next_possible_leap() {
+        let text = history!("59?");
+        let list = LeapSecs::from_str_lenient(text).unwrap();
+        let last = list.last_leap().unwrap();
+        assert_eq!(Gregorian(2017, 1, 1), last.date());
+        let after = |y, m, d| Gregorian(y, m, d).mjd();
+        for &(date, convention, next) in &[
+            (after(1971, 12, 31), LeapConvention::HalfYearly, None),
+            (after(1972, 1, 1), LeapConvention::HalfYearly, Some((1972, 7))),
+            (after(1972, 7, 1), LeapConvention::Monthly, Some((1973, 1))),
+            (after(2016, 12, 31), LeapConvention::Quarterly, Some((2017, 1))),
+            (after(2017, 1, 1), LeapConvention::HalfYearly, Some((2022, 1))),
+            (after(2017, 1, 1), LeapConvention::Quarterly, Some((2022, 1))),
+            (after(2017, 1, 1), LeapConvention::Monthly, Some((2022, 1))),
+            (after(2022, 1, 1), LeapConvention::HalfYearly, Some((2022, 7))),
+            (after(2022, 1, 1), LeapConvention::Quarterly, Some((2022, 4))),
+            (after(2022, 1, 1), LeapConvention::Monthly, Some((2022, 2))),
+            (after(2022, 6, 30), LeapConvention::HalfYearly, Some((2022, 7))),
+        ] {
+            let want = next.map(|(y, m)| Gregorian(y, m, 1).mjd());
+            assert_eq!(want, list.next_possible_leap_with(date, convention));
+        }
+        let start = LeapSecs::from_str_lenient("6?").unwrap();
+        assert!(start.last_leap().is_none());
+    }
+
+    #[test]
+    fn truncate_after() {
+        let text = history!("59?");
+        let list = LeapSecs::from_str_lenient(text).unwrap();
+        let date = Gregorian(2010, 6, 27).mjd();
+        let short = list.truncate_after(date).unwrap();
+        assert!(short.is_prefix_of(&list));
+        assert!(short.was_expired());
+        assert_eq!(Gregorian(2010, 5, 28).mjd(), short.expires());
+        assert_eq!(list.dtai_at(Gregorian(2009, 1, 1)).ok(), Some(34));
+        assert_eq!(short.dtai_at(Gregorian(2009, 1, 1)).ok(), Some(34));
+        assert!(short.len_bytes() < list.len_bytes());
+        let date = Gregorian(2012, 7, 28).mjd();
+        let short = list.truncate_after(date).unwrap();
+        assert_eq!(Gregorian(2012, 6, 28).mjd(), short.expires());
+        assert_eq!(Some(34), short.dtai_at(Gregorian(2012, 6, 28)).ok());
+        let later = Gregorian(2030, 1, 1).mjd();
+        assert_eq!(list, list.truncate_after(later).unwrap());
+        let early = list.truncate_after(Gregorian(1972, 1, 27).mjd());
+        assert!(matches!(early, Err(Error::TooEarly(_))));
+        let early = list.truncate_after(Gregorian(1972, 2, 27).mjd());
+        assert!(matches!(early, Err(Error::TooEarly(_))));
+        let first = list.truncate_after(Gregorian(1972, 2, 28).mjd());
+        assert_eq!("1?", first.unwrap().to_string());
+
+        let updated = Gregorian(2016, 7, 1).mjd();
+        let list = list.with_updated(updated).with_refresh("make update");
+        let list = list.with_literal(Format::Text);
+        let short = list.truncate_after(date).unwrap();
+        assert_eq!(Some(updated), short.updated());
+        assert_eq!(Some("make update"), short.refresh());
+        assert_eq!(list.provenance(), short.provenance());
+    }
+
+    #[test]
+    fn since() {
+        let list = LeapSecs::from_str_lenient("6+6+12-12+999?").unwrap();
+        let all = list.since(Gregorian(1972, 1, 1).mjd()).unwrap();
+        assert_eq!(list.iter().as_slice(), all);
+        let recent = list.since(Gregorian(1974, 1, 1).mjd()).unwrap();
+        assert_eq!(&list.iter().as_slice()[3..], recent);
+        assert_eq!(11, recent[0].dtai().unwrap());
+        let recent = list.since(Gregorian(1973, 12, 31).mjd()).unwrap();
+        assert_eq!(12, recent[0].dtai().unwrap());
+        let expiry = list.since(list.expires()).unwrap();
+        assert_eq!(2, expiry.len());
+        let err = list.since(list.expires() + 1).unwrap_err();
+        assert!(matches!(err, Error::Expired(_)));
+        let err = list.since(Gregorian(1971, 12, 31).mjd()).unwrap_err();
+        assert!(matches!(err, Error::TooEarly(_)));
+    }
+
+    #[test]
+    fn warnings() {
+        let mut builder = LeapSecs::builder();
+        for &(gap, sign) in &[
+            (6, Leap::Pos),
+            (6, Leap::Pos),
+            (132, Leap::Pos),
+            (6, Leap::Neg),
+            (3, Leap::Pos),
+            (27, Leap::Exp),
+        ] {
+            builder.push_gap(gap, sign).unwrap();
+        }
+        let today = Gregorian(1984, 6, 1).mjd();
+        let (list, warnings) = builder.finish_with_warnings_at(today).unwrap();
+        let want = [
+            Warning::FarExpiry(Gregorian(1987, 1, 28), 971),
+            Warning::LongGap(Gregorian(1973, 1, 1), Gregorian(1984, 1, 1), 132),
+            Warning::NegativeLeap(Gregorian(1984, 7, 1)),
+            Warning::UnusualMonth(Gregorian(1984, 10, 1)),
+        ];
+        assert_eq!(warnings, want);
+        assert_eq!("6+6+132+6-3+27?", list.to_string());
+        assert!(!list[5].is_preferred_month());
+        assert!(LeapConvention::Quarterly.allows(&list[5]));
+        assert!(list.iter().take(5).all(|leap| leap.is_preferred_month()));
+        assert_eq!(
+            "no leap seconds for 132 months from 1973-01-01 to 1984-01-01",
+            want[1].to_string()
+        );
+
+        let text = history!("59?");
+        let mut builder = LeapSecs::builder();
+        for leap in LeapSecs::from_str_lenient(text).unwrap().iter().skip(1) {
+            builder.push_gap(leap.gap() as i32, leap.sign()).unwrap();
+        }
+        let today = Gregorian(2021, 1, 1).mjd();
+        let (_, warnings) = builder.finish_with_warnings_at(today).unwrap();
+        assert_eq!(warnings, []);
+    }
+
+    #[test]
+    fn finish_inferred() {
+        for &(text, policy, expires) in &[
+            ("6+6+", ExpiryPolicy::NextHalfYear, Gregorian(1973, 6, 28)),
+            ("6+6+6+", ExpiryPolicy::NextHalfYear, Gregorian(1973, 12, 28)),
+            ("6+6+", ExpiryPolicy::Months(1), Gregorian(1973, 2, 28)),
+        ] {
+            let mut builder = LeapSecs::builder();
+            for _ in text.split_terminator('+') {
+                builder.push_gap(6, Leap::Pos).unwrap();
+            }
+            let list = builder.finish_inferred(policy).unwrap();
+            assert!(list.expiry_inferred());
+            assert_eq!(expires.mjd(), list.expires(), "{}", text);
+        }
+        // a last entry in June or December expires six months later
+        for &(gap, expires) in
+            &[(5, Gregorian(1972, 12, 28)), (11, Gregorian(1973, 6, 28))]
+        {
+            let mut builder = LeapSecs::builder();
+            builder.push_gap(gap, Leap::Pos).unwrap();
+            let list = builder.finish_inferred(ExpiryPolicy::NextHalfYear);
+            assert_eq!(expires.mjd(), list.unwrap().expires());
+        }
+        let list = LeapSecs::from_str_lenient("6+6+7?").unwrap();
+        assert!(!list.expiry_inferred());
+        let mut builder = LeapSecs::builder();
+        builder.push_gap(6, Leap::Pos).unwrap();
+        assert!(builder.finish_inferred(ExpiryPolicy::Months(0)).is_err());
+    }
+
+    #[test]
+    fn as_of() {
+        let text = history!("59?");
+        let list = LeapSecs::from_str_lenient(text).unwrap();
+        let view = list.as_of(Gregorian(2016, 12, 31));
+        assert!(!view.is_expired());
+        assert_eq!(36, view.dtai().unwrap());
+        assert_eq!(37, view.dtai_at(Gregorian(2017, 1, 1)).unwrap());
+        assert_eq!(Gregorian(2017, 1, 1), view.next_leap().unwrap().date());
+        let view = list.as_of(Gregorian(2017, 1, 1));
+        assert_eq!(None, view.next_leap());
+        assert!(view.dtai_at(Gregorian(1971, 12, 31)).is_err());
+        let view = list.as_of(Gregorian(2022, 1, 1));
+        assert!(view.is_expired());
+        assert!(view.dtai().is_err());
+    }
+
+    #[test]
+    fn extreme_unix() {
+        let list = LeapSecs::from_str_lenient(history!("59?")).unwrap();
+        let day = 86400;
+        let max = (i64::from(i32::MAX) - 40587) * day;
+        let min = (i64::from(i32::MIN) - 40587) * day;
+        for &unix in &[i64::MIN, min - 1, min, max, max + day, i64::MAX] {
+            assert!(list.dtai_at_unix(Unix(unix)).is_err(), "{}", unix);
+            let between = list.duration_between(Unix(0), Unix(unix));
+            assert!(between.is_err(), "{}", unix);
+        }
+        let between = list.duration_between(Unix(i64::MIN), Unix(i64::MAX));
+        assert!(between.is_err());
+    }
 }
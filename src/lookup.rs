@@ -0,0 +1,379 @@
+//! Bulk timestamp conversion
+//! =========================
+//!
+//! [`LeapSecs::compile()`][] turns a list into a [`Lookup`][] table
+//! that is optimized for converting large numbers of timestamps, for
+//! example,
+//!
+//!     # use leapsecs::*;
+//!     # use std::str::FromStr;
+//!     let list = LeapSecs::from_str("6+6+999?")?;
+//!     let lookup = list.compile();
+//!     let mut stamps = [78_796_799, 78_796_800, 94_694_400];
+//!     lookup.convert_slice(&mut stamps)?;
+//!     assert_eq!(stamps, [78_796_809, 78_796_811, 94_694_412]);
+//!     # Ok::<(), Error>(())
+//!
+//! Timestamps are converted from POSIX time, in seconds since 1970
+//! not counting leap seconds, to TAI seconds since the same epoch, by
+//! adding the DTAI that applies at that time. A timestamp at midnight
+//! at the start of a [`LeapSec::date()`][] gets the new DTAI.
+//!
+//! [`LeapSecs::to_lookup_table()`][] makes a simpler sorted table
+//! for [`search()`][], which can also be generated as Rust source
+//! for embedding in other programs.
+//!
+//! The table stores the start time of each DTAI in Eytzinger order,
+//! i.e. like a binary heap, so that a search runs through the table
+//! from front to back in a fixed number of steps with no
+//! unpredictable branches.
+
+use crate::*;
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+// POSIX timestamp at the start of a date
+fn posix(mjd: MJD) -> i64 {
+    Unix::from(mjd).0
+}
+
+// the date containing a POSIX timestamp, which fails if the
+// timestamp is too far away to have an MJD
+fn date(posix: i64) -> Result<Gregorian> {
+    use core::convert::TryFrom;
+    Gregorian::try_from(Unix(posix))
+}
+
+/// An immutable lookup table for converting timestamps in bulk,
+/// made by [`LeapSecs::compile()`][]
+///
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Lookup {
+    // start times in Eytzinger order, indexed from 1
+    start: Box<[i64]>,
+    // the DTAI before each start time, in the same order
+    before: Box<[i64]>,
+    // the DTAI after the last start time
+    last: i64,
+    // the earliest valid timestamp
+    first: i64,
+    // the first timestamp after the list expires
+    expires: i64,
+    // from LeapSecs::refresh()
+    refresh: Option<String>,
+}
+
+// fill in the Eytzinger layout by an in-order traversal of the
+// implicit binary tree
+
+fn eytzinger(
+    sorted: &[(i64, i64)],
+    out: &mut Lookup,
+    mut i: usize,
+    k: usize,
+) -> usize {
+    if k < out.start.len() {
+        i = eytzinger(sorted, out, i, 2 * k);
+        out.start[k] = sorted[i].0;
+        out.before[k] = sorted[i].1;
+        i = eytzinger(sorted, out, i + 1, 2 * k + 1);
+    }
+    i
+}
+
+impl LeapSecs {
+    /// Make a [`Lookup`][] table for converting timestamps in bulk.
+    ///
+    pub fn compile(&self) -> Lookup {
+        let mut sorted = vec![];
+        let mut dtai = 10;
+        for leap in self.iter().skip(1) {
+            if let Ok(next) = leap.dtai() {
+                sorted.push((posix(leap.mjd()), dtai));
+                dtai = next as i64;
+            }
+        }
+        let mut lookup = Lookup {
+            start: vec![0; sorted.len() + 1].into_boxed_slice(),
+            before: vec![0; sorted.len() + 1].into_boxed_slice(),
+            last: dtai,
+            first: posix(UTC_START_1972),
+            expires: posix(self.expires() + 1),
+            refresh: self.refresh().map(String::from),
+        };
+        eytzinger(&sorted, &mut lookup, 0, 1);
+        lookup
+    }
+
+    /// Make a function that converts a POSIX timestamp to TAI, or
+    /// returns [`None`][] if the timestamp is not covered by the list.
+    ///
+    /// The function owns a [`Lookup`][] table, so it can be moved to
+    /// another thread without keeping the list, for example,
+    ///
+    ///     # use leapsecs::*;
+    ///     # use std::str::FromStr;
+    ///     let list = LeapSecs::from_str("6+6+999?")?;
+    ///     let to_tai = list.to_fn();
+    ///     let worker = std::thread::spawn(move || to_tai(78_796_800));
+    ///     assert_eq!(Some(78_796_811), worker.join().unwrap());
+    ///     # Ok::<(), Error>(())
+    ///
+    pub fn to_fn(&self) -> impl Fn(i64) -> Option<i64> + Send + Sync + 'static {
+        let lookup = self.compile();
+        move |posix| posix.checked_add(lookup.dtai_at(posix).ok()? as i64)
+    }
+
+    /// Make a flat table of the POSIX timestamps when each DTAI
+    /// starts, sorted by time, for use with [`search()`][].
+    ///
+    /// The first entry is the start of 1972, when DTAI was 10. The
+    /// table does not say when the list expires, so a caller should
+    /// keep [`LeapSecs::expires()`][] alongside it. For example,
+    ///
+    ///     # use leapsecs::*;
+    ///     # use std::str::FromStr;
+    ///     let list = LeapSecs::from_str("6+6+999?")?;
+    ///     let table = list.to_lookup_table();
+    ///     assert_eq!(table, [(63_072_000, 10), (78_796_800, 11),
+    ///                        (94_694_400, 12)]);
+    ///     assert_eq!(lookup::search(&table, 78_796_799), Some(10));
+    ///     # Ok::<(), Error>(())
+    ///
+    pub fn to_lookup_table(&self) -> Vec<(i64, i16)> {
+        let mut table = vec![(0, 0); self.lookup_table_len()];
+        // the table is exactly the right size
+        self.write_lookup_table(&mut table).unwrap();
+        table
+    }
+
+    /// Get the number of entries in [`LeapSecs::to_lookup_table()`][].
+    ///
+    pub fn lookup_table_len(&self) -> usize {
+        self.iter().filter(|leap| leap.dtai().is_ok()).count()
+    }
+
+    /// Write the [`LeapSecs::to_lookup_table()`][] into a slice
+    /// supplied by the caller, and return the number of entries.
+    ///
+    /// Fails with [`Error::Full`][] if the slice is too short.
+    ///
+    pub fn write_lookup_table(&self, out: &mut [(i64, i16)]) -> Result<usize> {
+        let mut entries = self
+            .iter()
+            .filter_map(|leap| Some((posix(leap.mjd()), leap.dtai().ok()?)));
+        let mut len = 0;
+        for (slot, entry) in out.iter_mut().zip(&mut entries) {
+            *slot = entry;
+            len += 1;
+        }
+        match entries.next() {
+            Some(_) => Err(Error::Full(out.len())),
+            None => Ok(len),
+        }
+    }
+
+    /// Generate Rust source for the [`LeapSecs::to_lookup_table()`][]
+    /// as a `const` array called `name`, with the expiry time as a
+    /// `const` called `name` followed by `_EXPIRES`, which is the
+    /// first POSIX timestamp after the list expires.
+    ///
+    /// This is for build scripts that embed the table in other code,
+    /// which can search it with a copy of [`search()`][].
+    ///
+    ///     # use leapsecs::*;
+    ///     # use std::str::FromStr;
+    ///     let list = LeapSecs::from_str("6+6+999?")?;
+    ///     let code = list.lookup_table_rs("LEAPS");
+    ///     assert_eq!(code, "\
+    ///     pub const LEAPS: [(i64, i16); 3] = [
+    ///         (63072000, 10),
+    ///         (78796800, 11),
+    ///         (94694400, 12),
+    ///     ];
+    ///     pub const LEAPS_EXPIRES: i64 = 2724192000;
+    ///     ");
+    ///     # Ok::<(), Error>(())
+    ///
+    pub fn lookup_table_rs(&self, name: &str) -> String {
+        let table = self.to_lookup_table();
+        let mut out =
+            format!("pub const {}: [(i64, i16); {}] = [\n", name, table.len());
+        for (start, dtai) in table {
+            out += &format!("    ({}, {}),\n", start, dtai);
+        }
+        out += &format!(
+            "];\npub const {}_EXPIRES: i64 = {};\n",
+            name,
+            posix(self.expires() + 1)
+        );
+        out
+    }
+}
+
+/// Find the DTAI at a POSIX timestamp in a table from
+/// [`LeapSecs::to_lookup_table()`][], or [`None`][] if the timestamp
+/// is before the start of the table.
+///
+/// This is a binary search with a fixed number of steps for a given
+/// table length, so it has no unpredictable branches. It does not
+/// know when the list expires, so the caller must check that.
+///
+pub fn search(table: &[(i64, i16)], posix: i64) -> Option<i16> {
+    let mut base = 0;
+    let mut len = table.len();
+    while len > 1 {
+        let half = len / 2;
+        if table[base + half].0 <= posix {
+            base += half;
+        }
+        len -= half;
+    }
+    match table.get(base) {
+        Some(&(start, dtai)) if start <= posix => Some(dtai),
+        _ => None,
+    }
+}
+
+impl Lookup {
+    // get the DTAI at a POSIX timestamp, without checking that the
+    // timestamp is covered by the list
+    fn dtai(&self, posix: i64) -> i64 {
+        let mut k = 1;
+        while k < self.start.len() {
+            k = 2 * k + (self.start[k] <= posix) as usize;
+        }
+        // go back up to the first start time after the timestamp
+        k >>= k.trailing_ones() + 1;
+        if k == 0 {
+            self.last
+        } else {
+            self.before[k]
+        }
+    }
+
+    fn check(&self, posix: i64) -> Result<()> {
+        if posix < self.first || posix >= self.expires {
+            Err(Error::Uncovered(Coverage {
+                date: date(posix)?,
+                first: date(self.first)?,
+                expires: date(self.expires - 1)?,
+                refresh: self.refresh.clone(),
+            }))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Get the difference between UTC and TAI at a POSIX timestamp,
+    /// like [`LeapSecs::dtai_at()`][].
+    ///
+    /// Fails with [`Error::Uncovered`][] before 1972 or after the
+    /// list's expiry date, or [`Error::FromInt`][] if the timestamp
+    /// is too far away to have a date.
+    ///
+    pub fn dtai_at(&self, posix: i64) -> Result<i16> {
+        self.check(posix)?;
+        Ok(self.dtai(posix) as i16)
+    }
+
+    /// Convert a slice of POSIX timestamps to TAI in place.
+    ///
+    /// All the timestamps are checked as for [`Lookup::dtai_at()`][]
+    /// before any of them are converted, so the slice is unchanged if
+    /// this fails.
+    ///
+    pub fn convert_slice(&self, stamps: &mut [i64]) -> Result<()> {
+        let min = stamps.iter().copied().min();
+        let max = stamps.iter().copied().max();
+        if let (Some(min), Some(max)) = (min, max) {
+            self.check(min)?;
+            self.check(max)?;
+        }
+        for stamp in stamps.iter_mut() {
+            *stamp += self.dtai(*stamp);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod test {
+    use crate::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test() {
+        let text = history!("999?");
+        let list = LeapSecs::from_str(text).unwrap();
+        let lookup = list.compile();
+        let first = Unix::from(UTC_START_1972).0;
+        let mut stamps = vec![];
+        for leap in list.iter().take(list.len() - 1) {
+            let midnight = Unix::from(leap.mjd()).0;
+            for &delta in &[-86400, -1, 0, 1, 86399] {
+                let stamp = midnight + delta;
+                let want = list.dtai_at_unix(Unix(stamp));
+                assert_eq!(want.ok(), lookup.dtai_at(stamp).ok());
+                if stamp >= first {
+                    stamps.push(stamp);
+                }
+            }
+        }
+        // still valid on the expiry date
+        let expires = Unix::from(list.expires()).0;
+        assert_eq!(37, lookup.dtai_at(expires + 86399).unwrap());
+        assert!(lookup.dtai_at(expires + 86400).is_err());
+        let mut converted = stamps.clone();
+        lookup.convert_slice(&mut converted).unwrap();
+        for (utc, tai) in stamps.iter().zip(&converted) {
+            assert_eq!(lookup.dtai_at(*utc).unwrap() as i64, tai - utc);
+        }
+        let mut bad = [first, first - 1];
+        let err = lookup.convert_slice(&mut bad);
+        assert!(matches!(err, Err(Error::Uncovered(c)) if !c.is_expired()));
+        assert_eq!(bad, [first, first - 1]);
+        let table = list.to_lookup_table();
+        assert_eq!(table.len(), list.lookup_table_len());
+        for stamp in stamps.iter().chain(&[expires]) {
+            let want = lookup.dtai_at(*stamp).ok();
+            assert_eq!(want, lookup::search(&table, *stamp));
+        }
+        assert_eq!(None, lookup::search(&table, first - 1));
+        assert_eq!(None, lookup::search(&[], first));
+        let mut short = [(0, 0); 10];
+        let err = list.write_lookup_table(&mut short);
+        assert!(matches!(err, Err(Error::Full(10))));
+        assert_eq!(short[..], table[..10]);
+        let to_tai = list.to_fn();
+        drop(list);
+        for (utc, tai) in stamps.iter().zip(&converted) {
+            assert_eq!(Some(*tai), to_tai(*utc));
+        }
+        assert_eq!(None, to_tai(first - 1));
+        assert_eq!(None, to_tai(i64::MAX));
+        assert_eq!(None, to_tai(i64::MIN));
+        let max = (i64::from(i32::MAX) - 40587) * 86400;
+        for &stamp in &[i64::MIN, -max, max, max + 86400, i64::MAX] {
+            assert!(lookup.dtai_at(stamp).is_err(), "{}", stamp);
+        }
+        let mut bad = [first, i64::MAX];
+        assert!(lookup.convert_slice(&mut bad).is_err());
+        let mut bad = [i64::MIN, first];
+        assert!(lookup.convert_slice(&mut bad).is_err());
+        assert_eq!(bad, [i64::MIN, first]);
+        let expired = LeapSecs::from_str_lenient("6+6+12?").unwrap();
+        let expired = expired.with_refresh("leapsecs fetch");
+        let mut bad = [first, 1_000_000_000];
+        let err = expired.compile().convert_slice(&mut bad).unwrap_err();
+        let want = expired.coverage(Gregorian(2001, 9, 9));
+        assert!(matches!(&err, Error::Uncovered(c) if *c == want));
+        let msg = "2001-09-09 is not covered by the leap seconds list, \
+                   which is valid from 1972-01-01 to 1974-01-28; \
+                   to refresh it: leapsecs fetch";
+        assert_eq!(msg, err.to_string());
+    }
+}
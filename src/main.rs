@@ -1,9 +1,331 @@
+use leapsecs::nist::Store;
 use leapsecs::*;
+use ring::digest::{digest, SHA256};
+use std::convert::TryFrom;
+use std::io::Write;
+use std::str::FromStr;
 
 fn main() -> anyhow::Result<()> {
-    let list = nist::read()?;
-    println!("{}", nist::format(&list, MJD::today())?);
-    println!("{}", &list);
-    println!("{:X}", &list);
+    let mut args = std::env::args().skip(1);
+    match args.next().as_deref() {
+        None => {
+            let list = nist::read()?;
+            println!("{}", nist::format(&list)?);
+            println!("{}", &list);
+            println!("{:X}", &list);
+        }
+        Some("bin") => {
+            let list = nist::read()?;
+            std::io::stdout().write_all(&Vec::<u8>::from(&list))?;
+        }
+        Some("convert") => convert(args)?,
+        Some("doctor") => doctor()?,
+        Some("fetch") => fetch(args)?,
+        Some("hash") => hash(args.next().as_deref())?,
+        Some("to-csv") => to_csv(args.next().as_deref())?,
+        Some("to-ics") => to_ics(args.next().as_deref())?,
+        #[cfg(feature = "json")]
+        Some("to-json") => to_json(args.next().as_deref())?,
+        Some(cmd) => anyhow::bail!("unknown command {}", cmd),
+    }
+    Ok(())
+}
+
+// read a list in any format, with its checksum if it is a NIST file
+fn read(file: Option<&str>) -> anyhow::Result<(LeapSecs, Option<nist::Hash>)> {
+    let file = match file {
+        None => return read_cached(),
+        Some(file) => file,
+    };
+    let data = std::fs::read(file)?;
+    if let Ok(text) = std::str::from_utf8(&data) {
+        if text.starts_with('#') {
+            let (list, hash) = nist::read_str_hash(text)?;
+            return Ok((list, Some(hash)));
+        } else if let Ok(list) = LeapSecs::from_str(text.trim_end()) {
+            return Ok((list, None));
+        }
+    }
+    Ok((LeapSecs::try_from(&data[..])?, None))
+}
+
+// read the cached list, fetching it if need be, with the checksum of
+// the cached copy
+fn read_cached() -> anyhow::Result<(LeapSecs, Option<nist::Hash>)> {
+    let store = nist::FileStore::default();
+    let list = nist::read_with(&store)?;
+    let hash = match store.load()? {
+        Some(data) => Some(nist::read_str_hash(std::str::from_utf8(&data)?)?.1),
+        None => None,
+    };
+    Ok((list, hash))
+}
+
+// read a pinned list in any format, even if it has expired
+fn read_lenient(file: &str) -> anyhow::Result<LeapSecs> {
+    let data = std::fs::read(file)?;
+    if let Ok(text) = std::str::from_utf8(&data) {
+        if text.starts_with('#') {
+            return Ok(nist::read_str_lenient(text)?);
+        } else if let Ok(list) = LeapSecs::from_str_lenient(text.trim_end()) {
+            return Ok(list);
+        }
+    }
+    Ok(LeapSecs::try_from_bytes_lenient(&data)?)
+}
+
+// the formats that convert can write
+#[cfg(feature = "json")]
+const FORMATS: &str = "bin, json, nist, txt";
+#[cfg(not(feature = "json"))]
+const FORMATS: &str = "bin, nist, txt";
+
+// convert a list to another format, optionally refusing if the
+// conversion would lose information
+fn convert(args: impl Iterator<Item = String>) -> anyhow::Result<()> {
+    let mut strict = false;
+    let mut format = None;
+    let mut file = None;
+    for arg in args {
+        match arg.as_str() {
+            "--strict" => strict = true,
+            "bin" if format.is_none() => format = Some(Format::Binary),
+            #[cfg(feature = "json")]
+            "json" if format.is_none() => format = Some(Format::Json),
+            "nist" if format.is_none() => format = Some(Format::Nist),
+            "txt" if format.is_none() => format = Some(Format::Text),
+            _ if format.is_some() && file.is_none() => file = Some(arg),
+            _ => anyhow::bail!("unknown argument {}", arg),
+        }
+    }
+    let format = match format {
+        Some(format) => format,
+        None => anyhow::bail!("convert needs a format: {}", FORMATS),
+    };
+    let (list, _) = read(file.as_deref())?;
+    if strict {
+        list.check_lossless(&[format])?;
+    }
+    let out = match format {
+        Format::Binary => Vec::<u8>::from(&list),
+        #[cfg(feature = "json")]
+        Format::Json => (json::to_string(&list)? + "\n").into_bytes(),
+        // the argument parser only accepts json with the json feature
+        #[cfg(not(feature = "json"))]
+        Format::Json => unreachable!(),
+        Format::Nist => nist::format(&list)?.into_bytes(),
+        Format::Text => format!("{}\n", list).into_bytes(),
+    };
+    std::io::stdout().write_all(&out)?;
+    Ok(())
+}
+
+// a spreadsheet-friendly table, in the layout of the csv module
+fn to_csv(file: Option<&str>) -> anyhow::Result<()> {
+    let (list, _) = read(file)?;
+    list.to_csv(&mut std::io::stdout().lock())?;
+    Ok(())
+}
+
+// iCalendar dates are ISO 8601 without punctuation
+fn ics_date(date: Gregorian) -> String {
+    format!("{:04}{:02}{:02}", date.year(), date.month(), date.day())
+}
+
+// an all-day event on the last day of each month that ends with a
+// leap second, and on the expiry date
+fn to_ics(file: Option<&str>) -> anyhow::Result<()> {
+    let (list, _) = read(file)?;
+    let stamp =
+        ics_date(Gregorian::from(list.updated().unwrap_or_else(MJD::today)));
+    let mut out = String::new();
+    let mut line = |text: String| {
+        out.push_str(&text);
+        out.push_str("\r\n");
+    };
+    line("BEGIN:VCALENDAR".to_string());
+    line("VERSION:2.0".to_string());
+    line("PRODID:-//leapsecs//leap seconds//EN".to_string());
+    line("CALSCALE:GREGORIAN".to_string());
+    for leap in list.iter().skip(1) {
+        let (day, summary) = match leap.sign() {
+            Leap::Exp => (leap.date(), "Leap second list expires".to_string()),
+            sign => (
+                Gregorian::from(leap.mjd() - 1),
+                format!(
+                    "{} leap second, TAI - UTC = {}",
+                    if sign == Leap::Neg { "Negative" } else { "Positive" },
+                    leap.dtai()?
+                ),
+            ),
+        };
+        line("BEGIN:VEVENT".to_string());
+        line(format!("UID:{}-{:?}@leapsecs", ics_date(day), leap.sign()));
+        line(format!("DTSTAMP:{}T000000Z", stamp));
+        line(format!("DTSTART;VALUE=DATE:{}", ics_date(day)));
+        line(format!("SUMMARY:{}", summary));
+        line("TRANSP:TRANSPARENT".to_string());
+        line("END:VEVENT".to_string());
+    }
+    line("END:VCALENDAR".to_string());
+    std::io::stdout().write_all(out.as_bytes())?;
+    Ok(())
+}
+
+#[cfg(feature = "json")]
+fn to_json(file: Option<&str>) -> anyhow::Result<()> {
+    let (list, _) = read(file)?;
+    println!("{}", json::to_string(&list)?);
+    Ok(())
+}
+
+// download the NIST list and copy it to stdout, optionally refusing
+// if it does not agree with the history in a pinned list
+fn fetch(mut args: impl Iterator<Item = String>) -> anyhow::Result<()> {
+    let pinned = match args.next().as_deref() {
+        None => None,
+        Some("--verify-against") => match args.next() {
+            Some(file) => Some(read_lenient(&file)?),
+            None => anyhow::bail!("--verify-against needs a file name"),
+        },
+        Some(arg) => anyhow::bail!("unknown argument {}", arg),
+    };
+    let data = nist::fetch()?;
+    let list = nist::read_bytes(&data)?;
+    if let Some(pinned) = pinned {
+        list.verify_history(&pinned)?;
+    }
+    std::io::stdout().write_all(&data)?;
+    Ok(())
+}
+
+// where operating systems commonly install leap second lists
+const SYSTEM_FILES: &[&str] = &[
+    "/usr/share/zoneinfo/leap-seconds.list",
+    "/etc/ntp.leapseconds",
+    "/var/lib/ntp/leap-seconds.list",
+    "/var/lib/ntpsec/leap-seconds.list",
+];
+
+// check why the leap second list might be stale, and say what to do
+fn doctor() -> anyhow::Result<()> {
+    let today = MJD::today();
+    let mut problems = 0;
+    let mut report = |ok: bool, what: String| {
+        problems += !ok as usize;
+        println!("{}  {}", if ok { "ok  " } else { "FAIL" }, what);
+    };
+    let mut lists = vec![];
+
+    // the mirrors check the list, so failures include bad lists
+    match nist::Mirrors::default().fetch() {
+        Ok((url, data)) => {
+            report(true, format!("fetched {}", url));
+            lists.push(("NIST", nist::read_bytes(&data)?));
+        }
+        Err(err) => report(
+            false,
+            format!("cannot fetch source, check network access: {:#}", err),
+        ),
+    }
+
+    let probe = format!("{}.doctor", nist::NIST_FILE);
+    match std::fs::write(&probe, b"")
+        .and_then(|()| std::fs::remove_file(&probe))
+    {
+        Ok(()) => report(true, "cache directory is writable".to_string()),
+        Err(err) => report(
+            false,
+            format!(
+                "cache directory is not writable, check permissions: {}",
+                err
+            ),
+        ),
+    }
+
+    let mut files = vec![nist::NIST_FILE];
+    files.extend(SYSTEM_FILES);
+    for &file in &files {
+        let age = std::fs::metadata(file)
+            .and_then(|meta| meta.modified())
+            .map(|time| time.elapsed().unwrap_or_default().as_secs() / 86400);
+        let age = match age {
+            Ok(age) => age,
+            Err(_) if file == nist::NIST_FILE => {
+                report(true, format!("no cached {} yet", file));
+                continue;
+            }
+            Err(_) => continue,
+        };
+        match nist::read_file_lenient(file) {
+            Ok(list) if list.is_expired_at(today) => report(
+                false,
+                format!(
+                    "{} expired {}, {} days old, replace it",
+                    file,
+                    Gregorian::from(list.expires()),
+                    age
+                ),
+            ),
+            Ok(list) => {
+                report(
+                    true,
+                    format!(
+                        "{} expires {}, {} days old",
+                        file,
+                        Gregorian::from(list.expires()),
+                        age
+                    ),
+                );
+                lists.push((file, list));
+            }
+            Err(err) => {
+                report(false, format!("{} is unreadable: {:#}", file, err))
+            }
+        }
+    }
+
+    // a list cannot be updated in the future, and lists are published
+    // about six months before they expire, so the clock is suspicious
+    // if it is outside that range
+    for (name, list) in &lists {
+        let earliest = list.updated().unwrap_or_else(|| list.expires() - 366);
+        if today < earliest {
+            report(
+                false,
+                format!(
+                    "clock says {} but {} list was updated {}, check the clock",
+                    Gregorian::from(today),
+                    name,
+                    Gregorian::from(earliest)
+                ),
+            );
+        }
+    }
+    if lists.is_empty() {
+        report(false, "no usable leap second list was found".to_string());
+    }
+
+    if problems > 0 {
+        anyhow::bail!("{} problem(s) found", problems);
+    }
+    Ok(())
+}
+
+fn hash(file: Option<&str>) -> anyhow::Result<()> {
+    let (list, nist) = read(file)?;
+    let bytes = Vec::<u8>::from(&list);
+    match nist {
+        Some(hash) => println!("nist    {}", hash),
+        None => println!("nist    -"),
+    }
+    print!("sha256  ");
+    for byte in digest(&SHA256, &bytes).as_ref() {
+        print!("{:02x}", byte);
+    }
+    println!();
+    println!("hex     {:X}", list);
+    println!("base64  {}", dns::base64_encode(&bytes));
+    println!("text    {}", list);
     Ok(())
 }
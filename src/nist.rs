@@ -1,34 +1,109 @@
-// fetch and parse the NIST leap-seconds.list
+// parse and format the NIST leap-seconds.list
+//
+// Fetching and caching the list is in the io submodule, which needs
+// the `fetch` feature, so that the parser does not depend on curl.
 
-use anyhow::Context;
-use std::convert::TryInto;
-use std::io::Read;
+use std::convert::{TryFrom, TryInto};
 
 use crate::*;
 
+// With the `log` feature, report what the fetch and cache functions
+// are doing via the `log` crate, so that applications can route and
+// filter the messages; without it, stay quiet.
+macro_rules! event {
+    ($level:ident, $($arg:tt)+) => {{
+        #[cfg(feature = "log")]
+        log::$level!($($arg)+);
+        #[cfg(not(feature = "log"))]
+        let _ = format_args!($($arg)+);
+    }};
+}
+
 mod fmt;
+#[cfg(feature = "fetch")]
+mod io;
 mod parse;
+#[cfg(feature = "fetch")]
+mod update;
 
-pub use fmt::format;
+pub use fmt::NTPD_WARNING_DAYS;
+pub use fmt::{format, format_preserving, format_with, format_with_last_line};
+#[cfg(feature = "fetch")]
+pub use io::*;
+#[cfg(feature = "fetch")]
+pub use update::{Updater, REFRESH_SECS, RETRY_SECS};
 
-const NIST_FILE: &str = "leap-seconds.list";
-const NIST_URL: &str = "ftp://ftp.boulder.nist.gov/pub/time/leap-seconds.list";
-
-pub fn read() -> anyhow::Result<LeapSecs> {
-    Ok(read_bytes(&load_file(NIST_FILE).or_else(save_url)?)?)
+// IERS Bulletin C announcements are parsed by the crate's bulletin
+// module, which does not need std; this is where NIST list
+// maintainers look for it
+pub mod bulletin_c {
+    pub use crate::bulletin::{parse, Announcement};
 }
 
+////////////////////////////////////////////////////////////////////////
+
 pub fn read_bytes(data: &[u8]) -> Result<LeapSecs> {
     read_str(std::str::from_utf8(data)?)
 }
 
-pub fn read_file(name: &str) -> anyhow::Result<LeapSecs> {
-    Ok(read_bytes(&load_file(name)?)?)
+pub fn read_bytes_at<C: Clock>(data: &[u8], clock: C) -> Result<LeapSecs> {
+    read_str_at(std::str::from_utf8(data)?, clock)
 }
 
 pub fn read_str(text: &str) -> Result<LeapSecs> {
-    match parse::parse(&text) {
-        Ok((_, unchecked)) => unchecked.try_into(),
+    read_str_at(text, SystemClock)
+}
+
+// check for expiry using the given clock instead of the system clock
+pub fn read_str_at<C: Clock>(text: &str, clock: C) -> Result<LeapSecs> {
+    fmt::check(parse(text)?, |list| list.finish_at(clock))
+}
+
+pub fn read_bytes_lenient(data: &[u8]) -> Result<LeapSecs> {
+    read_str_lenient(std::str::from_utf8(data)?)
+}
+
+// accept expired lists, see LeapSecs::was_expired()
+pub fn read_str_lenient(text: &str) -> Result<LeapSecs> {
+    fmt::check(parse(text)?, LeapSecBuilder::finish_lenient)
+}
+
+// Unknown `#x` directives are ignored by the other read functions;
+// this one reports them to `warn` as Warning::UnknownDirective, with
+// their line numbers.
+pub fn read_str_warn<F>(text: &str, mut warn: F) -> Result<LeapSecs>
+where
+    F: FnMut(Warning),
+{
+    let unchecked = parse(text)?;
+    for (len, line) in &unchecked.directives {
+        let number = text[..text.len() - len].matches('\n').count() + 1;
+        warn(Warning::UnknownDirective(number, line.clone()));
+    }
+    fmt::check(unchecked, LeapSecBuilder::finish)
+}
+
+// also return the checksum from the `#h` line
+pub fn read_str_hash(text: &str) -> Result<(LeapSecs, Hash)> {
+    let unchecked = parse(text)?;
+    let hash = unchecked.hash.clone();
+    Ok((fmt::check(unchecked, LeapSecBuilder::finish)?, hash))
+}
+
+// also return the strongest checksum that was verified, so that
+// callers can insist on SHA-256
+pub fn read_str_checksum(text: &str) -> Result<(LeapSecs, Checksum)> {
+    let unchecked = parse(text)?;
+    let checksum = match unchecked.hash256 {
+        Some(_) => Checksum::Sha256,
+        None => Checksum::Sha1,
+    };
+    Ok((fmt::check(unchecked, LeapSecBuilder::finish)?, checksum))
+}
+
+fn parse(text: &str) -> Result<UncheckedList> {
+    match parse::parse(text) {
+        Ok((_, unchecked)) => Ok(unchecked),
         Err(nom::Err::Error(err)) => {
             Err(Error::Nom(nom::error::convert_error(text, err)))
         }
@@ -39,80 +114,526 @@ pub fn read_str(text: &str) -> Result<LeapSecs> {
     }
 }
 
-pub fn read_url(url: &str) -> anyhow::Result<LeapSecs> {
-    Ok(read_bytes(&load_url(url)?)?)
+// like read_verified() but with data and signature from elsewhere
+#[cfg(feature = "pgp")]
+pub fn read_bytes_verified(
+    data: &[u8],
+    signature: &[u8],
+    keys: &[pgp::PublicKey],
+) -> Result<LeapSecs> {
+    pgp::verify(data, signature, keys)?;
+    read_bytes(data)
 }
 
 ////////////////////////////////////////////////////////////////////////
 
-// public for error reporting
+/// A SHA-1 or SHA-256 checksum of a NIST `leap-seconds.list`.
+///
+/// It is displayed like the `#h` line, as space-separated groups of
+/// 8 hex digits; `{:X}` is the same in upper case. It parses from hex
+/// with or without spaces or a leading `#h`, and uses a plain lower
+/// case hex string with serde.
+///
+///     # use leapsecs::*;
+///     # use std::convert::TryFrom;
+///     let line = "#h 4a5c0a9e 7b5cf2e3 3f8e8a1a a1f3d8b5 6fdd4d54";
+///     let hash: nist::Hash = line.parse()?;
+///     assert_eq!(format!("#h {}", hash), line);
+///     let bytes = <[u8; 20]>::try_from(&hash)?;
+///     assert_eq!(nist::Hash::from(bytes), hash);
+///     # Ok::<(), Error>(())
+///
 #[derive(Clone, Debug, Default, Eq, PartialEq)]
-pub struct Hash([u32; 5]);
+pub struct Hash(Vec<u8>);
 
-// timestamp, DTAI, date
-type UncheckedLeap = (i64, i16, Gregorian);
+impl Hash {
+    /// Make a SHA-1 [`Hash`][] from its bytes.
+    pub fn from_bytes(bytes: [u8; 20]) -> Hash {
+        Hash(bytes.to_vec())
+    }
 
-#[derive(Clone, Debug, Default)]
-struct UncheckedList {
-    pub updated: i64,
-    pub expires: i64,
-    pub leapsecs: Vec<UncheckedLeap>,
-    pub hash: Hash,
+    /// Make a SHA-256 [`Hash`][] from its bytes.
+    pub fn from_sha256_bytes(bytes: [u8; 32]) -> Hash {
+        Hash(bytes.to_vec())
+    }
+
+    // big-endian 32 bit words, as in the NIST format
+    fn from_words(words: &[u32]) -> Hash {
+        Hash(words.iter().flat_map(|word| word.to_be_bytes()).collect())
+    }
+
+    /// Get the bytes of the hash, 20 for SHA-1 or 32 for SHA-256.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl From<[u8; 20]> for Hash {
+    fn from(bytes: [u8; 20]) -> Hash {
+        Hash::from_bytes(bytes)
+    }
+}
+
+impl From<[u8; 32]> for Hash {
+    fn from(bytes: [u8; 32]) -> Hash {
+        Hash::from_sha256_bytes(bytes)
+    }
 }
 
-fn save_url(_: anyhow::Error) -> anyhow::Result<Vec<u8>> {
-    eprintln!("fetching {}", NIST_URL);
-    let data = load_url(NIST_URL)?;
-    std::fs::write(NIST_FILE, &data)
-        .with_context(|| format!("failed to write {}", NIST_FILE))?;
-    Ok(data)
+/// Get the hash from a `ring` digest, such as the result of
+/// `ring::digest::digest(&SHA1_FOR_LEGACY_USE_ONLY, data)`.
+///
+impl From<ring::digest::Digest> for Hash {
+    fn from(digest: ring::digest::Digest) -> Hash {
+        Hash(digest.as_ref().to_vec())
+    }
 }
 
-fn load_file(name: &str) -> anyhow::Result<Vec<u8>> {
-    let ctx = || format!("failed to read {}", name);
-    let mut fh = std::fs::File::open(name).with_context(ctx)?;
-    let mut data = Vec::new();
-    fh.read_to_end(&mut data).with_context(ctx)?;
-    Ok(data)
+/// Get the bytes of a SHA-1 hash. Fails with [`Error::Truncated`][]
+/// if it is a SHA-256 hash.
+///
+impl TryFrom<&Hash> for [u8; 20] {
+    type Error = Error;
+
+    fn try_from(hash: &Hash) -> Result<[u8; 20]> {
+        hash.as_bytes().try_into().map_err(|_| Error::Truncated)
+    }
 }
 
-fn load_url(url: &str) -> anyhow::Result<Vec<u8>> {
-    let mut data = Vec::new();
-    curl_get(&url, &mut data)
-        .with_context(|| format!("failed to fetch {}", &url))?;
-    Ok(data)
+/// Get the bytes of a SHA-256 hash. Fails with [`Error::Truncated`][]
+/// if it is a SHA-1 hash.
+///
+impl TryFrom<&Hash> for [u8; 32] {
+    type Error = Error;
+
+    fn try_from(hash: &Hash) -> Result<[u8; 32]> {
+        hash.as_bytes().try_into().map_err(|_| Error::Truncated)
+    }
 }
 
-fn curl_get(url: &str, buffer: &mut Vec<u8>) -> anyhow::Result<()> {
-    let mut ua = curl::easy::Easy::new();
-    ua.useragent(&format!(
-        "leapsecs/0 curl/{}",
-        curl::Version::get().version()
-    ))?;
-    ua.fail_on_error(true)?;
-    ua.url(url)?;
-    let mut xfer = ua.transfer();
-    xfer.write_function(|chunk| {
-        buffer.extend_from_slice(chunk);
-        Ok(chunk.len())
-    })?;
-    xfer.perform()?;
-    Ok(())
+impl std::str::FromStr for Hash {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Hash> {
+        let s = s.trim_start();
+        let s = s.strip_prefix("#h").unwrap_or(s);
+        let mut digits = vec![];
+        for c in s.chars().filter(|c| !c.is_ascii_whitespace()) {
+            let digit = c.to_digit(16).ok_or(Error::FromStr("[0-9a-f]", c))?;
+            digits.push(digit as u8);
+        }
+        if digits.len() != 40 && digits.len() != 64 {
+            return Err(Error::Truncated);
+        }
+        Ok(Hash(digits.chunks(2).map(|pair| pair[0] << 4 | pair[1]).collect()))
+    }
+}
+
+#[cfg(feature = "json")]
+impl serde::Serialize for Hash {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        let hex: String = self.0.iter().map(|b| format!("{:02x}", b)).collect();
+        serializer.serialize_str(&hex)
+    }
+}
+
+#[cfg(feature = "json")]
+impl<'de> serde::Deserialize<'de> for Hash {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Hash, D::Error> {
+        let hex = String::deserialize(deserializer)?;
+        hex.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+// The standard `#h` line has a SHA-1 hash. There can also be a
+// SHA-256 hash on a `#\tsha256` line immediately after the `#h` line;
+// it looks like a comment so that other parsers (such as ntpd, which
+// is picky about lines starting `#h`) will ignore it.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Checksum {
+    Sha1,
+    Sha256,
+}
+
+// Whether the last line of the NIST format ends with a newline, as
+// written by format_with_last_line(). Some mirrors strip it, and the
+// parser accepts files with or without it.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum LastLine {
+    Newline,
+    Bare,
+}
+
+// timestamp, DTAI, date
+type UncheckedLeap = (i64, i16, Gregorian);
+
+#[derive(Clone, Debug, Default)]
+struct UncheckedList {
+    pub updated: i64,
+    pub expires: i64,
+    pub leapsecs: Vec<UncheckedLeap>,
+    pub hash: Hash,
+    pub hash256: Option<Hash>,
+    // unknown directives, see parse::directive()
+    pub directives: Vec<(usize, String)>,
 }
 
 ////////////////////////////////////////////////////////////////////////
 
 #[cfg(test)]
 mod test {
-    use crate::date::*;
-    use crate::nist;
+    use crate::*;
+
+    #[test]
+    fn bulletin_c() {
+        let text = "A positive leap second will be introduced \
+                    at the end of December 2016.";
+        let news = nist::bulletin_c::parse(text).unwrap();
+        assert_eq!(bulletin::parse(text).unwrap(), news);
+        let news: nist::bulletin_c::Announcement = news;
+        assert_eq!(Gregorian(2017, 1, 1), news.effective);
+    }
 
     #[test]
     fn test() {
-        let original = nist::read().expect("get leap-seconds.list");
-        let printed = nist::format(&original, MJD::today())
-            .expect("formatting leap seconds");
-        let parsed = nist::read_str(&printed).expect("re-parsing leap-seconds");
+        let text = history!("59?");
+        let today = Gregorian(2021, 1, 1).mjd();
+        let original = LeapSecs::from_str_at(text, today).unwrap();
+        let updated = Gregorian(2020, 7, 8).mjd();
+        let original = original.with_updated(updated);
+        let printed = nist::format(&original).expect("formatting leap seconds");
+        let parsed = nist::read_str_at(&printed, today)
+            .expect("re-parsing leap-seconds");
         assert_eq!(original, parsed);
+        assert_eq!(Some(updated), parsed.updated());
+        assert_eq!(None, LeapSecs::from_str_at(text, today).unwrap().updated());
+        assert!(nist::read_str(&printed).is_err());
+        assert!(nist::read_str_lenient(&printed).unwrap().was_expired());
+
+        // timestamps from the file can be absurd
+        let far = i64::from(i32::MAX) * 86400;
+        for prefix in &["#$", "#@"] {
+            for ntp in &[far, i64::MAX] {
+                let absurd: String = printed
+                    .lines()
+                    .map(|line| match line.starts_with(prefix) {
+                        true => format!("{}\t{}\n", prefix, ntp),
+                        false => format!("{}\n", line),
+                    })
+                    .collect();
+                let err = nist::read_str_lenient(&absurd).unwrap_err();
+                assert!(matches!(err, Error::FromInt(_)), "{}{}", prefix, ntp);
+            }
+        }
+    }
+
+    #[test]
+    fn updated_time_of_day() {
+        // the IERS list of January 2026 was updated at 11:14:18
+        let list = LeapSecs::from_str_lenient(history!("119?")).unwrap();
+        let printed = nist::format(&list).unwrap();
+        let iers: String = printed
+            .lines()
+            .map(|line| match line.get(..2) {
+                Some("#$") => "#$\t3976686858\n".into(),
+                Some("#h") => "#h\t2e101270 4e6749f8 2f1792b7 \
+                               14a0c188 36bb19d6\n"
+                    .into(),
+                _ => format!("{}\n", line),
+            })
+            .collect();
+        let parsed = nist::read_str_lenient(&iers).unwrap();
+        assert_eq!(list, parsed);
+        assert_eq!(Some(Gregorian(2026, 1, 6).mjd()), parsed.updated());
+    }
+
+    #[cfg(all(feature = "fetch", feature = "log"))]
+    #[test]
+    fn log() {
+        use std::str::FromStr;
+        use std::sync::Mutex;
+
+        static EVENTS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+        struct Capture;
+        impl log::Log for Capture {
+            fn enabled(&self, _: &log::Metadata) -> bool {
+                true
+            }
+            fn log(&self, record: &log::Record) {
+                let event = format!("{} {}", record.level(), record.args());
+                let mut events =
+                    EVENTS.lock().unwrap_or_else(|e| e.into_inner());
+                events.push(event);
+            }
+            fn flush(&self) {}
+        }
+        log::set_logger(&Capture).unwrap();
+        log::set_max_level(log::LevelFilter::Debug);
+        let logged = |want: &str| {
+            let events = EVENTS.lock().unwrap().clone();
+            assert!(events.iter().any(|e| e.starts_with(want)), "{:?}", events);
+        };
+
+        let path = temp_path("log.list").to_str().unwrap().to_string();
+        let store = nist::FileStore::new(&path);
+        let text = history!("999?");
+        let list = LeapSecs::from_str(text).unwrap();
+        let list = list.with_updated(Gregorian(2021, 1, 7).mjd());
+        let printed = nist::format(&list).unwrap();
+        std::fs::write(&path, &printed).unwrap();
+        assert_eq!(list, nist::read_with(&store).unwrap());
+        logged(&format!("DEBUG using cached NIST list from file {}", path));
+
+        let hash = printed.lines().find(|l| l.starts_with("#h")).unwrap();
+        let corrupt = printed.replace(hash, &hash.replace('a', "b"));
+        assert!(nist::read_str(&corrupt).is_err());
+        logged("WARN SHA-1 checksum failed");
+
+        let old = LeapSecs::from_str_lenient("6+6+12?").unwrap();
+        std::fs::write(&path, nist::format(&old).unwrap()).unwrap();
+        assert!(nist::read_with(&store).is_err());
+        logged("WARN leap seconds list expired on 1974-01-28");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn quirks() {
+        use std::str::FromStr;
+
+        let text = history!("999?");
+        let list = LeapSecs::from_str(text).unwrap();
+        let list = list.with_updated(Gregorian(2021, 1, 7).mjd());
+        let printed = nist::format(&list).unwrap();
+        let mut quirky = String::new();
+        for line in printed.lines() {
+            if line.starts_with("#@") {
+                quirky += "#!mirrored from somewhere\r\n";
+            }
+            quirky += line;
+            quirky += " \r\n";
+        }
+        // no newline at the end
+        quirky.truncate(quirky.len() - 2);
+        let mut warnings = vec![];
+        let parsed = nist::read_str_warn(&quirky, |w| warnings.push(w));
+        assert_eq!(list, parsed.unwrap());
+        let before = printed.lines().take_while(|l| !l.starts_with("#@"));
+        let line = before.count() + 1;
+        let want = [Warning::UnknownDirective(
+            line,
+            "#!mirrored from somewhere".into(),
+        )];
+        assert_eq!(warnings, want);
+        assert_eq!(
+            "unknown directive on line 3: #!x",
+            Warning::UnknownDirective(3, "#!x".into()).to_string()
+        );
+        assert_eq!(list, nist::read_str(&quirky).unwrap());
+    }
+
+    #[test]
+    fn trailer() {
+        use std::str::FromStr;
+
+        let text = history!("999?");
+        let list = LeapSecs::from_str(text).unwrap();
+        let list = list.with_updated(Gregorian(2021, 1, 7).mjd());
+        let updated = Gregorian(2021, 1, 7).mjd();
+        let sha1 = nist::Checksum::Sha1;
+        let bare = nist::LastLine::Bare;
+        let printed = nist::format(&list).unwrap();
+        let stripped =
+            nist::format_with_last_line(&list, updated, sha1, bare).unwrap();
+        assert_eq!(printed.trim_end(), stripped);
+        for mirror in &[
+            stripped.clone(),
+            format!("{}\n\n", printed),
+            format!("{}#\n# mirrored\n#", printed),
+            format!("{} \n\n  ", printed),
+        ] {
+            assert_eq!(list, nist::read_str(mirror).unwrap());
+            let copy = nist::format_preserving(mirror, &list).unwrap();
+            assert_eq!(mirror, &copy);
+        }
+        let junk = format!("{}junk\n", printed);
+        assert!(nist::read_str(&junk).is_err());
+    }
+
+    #[test]
+    fn checksum() {
+        let text = history!("999?");
+        let list = LeapSecs::from_str_lenient(text).unwrap();
+        let updated = Gregorian(2021, 1, 7).mjd();
+        let list = list.with_updated(updated);
+        let plain = nist::format(&list).unwrap();
+        let strong =
+            nist::format_with(&list, updated, nist::Checksum::Sha256).unwrap();
+        assert!(strong.starts_with(&plain));
+        assert!(strong[plain.len()..].starts_with("#\tsha256\t"));
+        for &(text, checksum) in
+            &[(&plain, nist::Checksum::Sha1), (&strong, nist::Checksum::Sha256)]
+        {
+            let (parsed, got) = nist::read_str_checksum(text).unwrap();
+            assert_eq!((&list, checksum), (&parsed, got));
+            assert_eq!(list, nist::read_str(text).unwrap());
+        }
+        let pos = strong.rfind(' ').unwrap() + 1;
+        let flip = if &strong[pos..pos + 1] == "0" { "1" } else { "0" };
+        let bad = format!("{}{}{}", &strong[..pos], flip, &strong[pos + 1..]);
+        assert!(matches!(nist::read_str(&bad), Err(Error::Checksum(..))));
+    }
+
+    #[test]
+    fn lossless() {
+        use Format::*;
+
+        let text = history!("59?");
+        let updated = Gregorian(2020, 7, 8).mjd();
+        let list = LeapSecs::from_str_lenient(text).unwrap();
+        let list = list.with_updated(updated);
+        let printed = nist::format(&list).unwrap();
+        let bytes = Vec::<u8>::from(&nist::read_str_lenient(&printed).unwrap());
+        let binary = LeapSecs::try_from_bytes_lenient(&bytes).unwrap();
+        let reprinted =
+            nist::format_with(&binary, updated, nist::Checksum::Sha1).unwrap();
+        assert_eq!(printed, reprinted);
+        let text = binary.to_string();
+        let parsed = LeapSecs::from_str_lenient(&text).unwrap();
+        assert_eq!(list.expires(), parsed.expires());
+        assert_eq!(Gregorian(2021, 12, 28), Gregorian::from(parsed.expires()));
+
+        assert!(list.is_lossless(&[Nist, Json, Nist]));
+        assert!(!list.is_lossless(&[Nist, Binary, Nist]));
+        assert!(matches!(
+            list.check_lossless(&[Json, Text]),
+            Err(Error::Lossy(Text, _))
+        ));
+        assert!(binary.is_lossless(&[Binary, Text, Json]));
+        assert!(!binary.is_lossless(&[Binary, Nist]));
+        let mut builder = LeapSecs::builder();
+        for leap in binary.iter().skip(1).take(binary.len() - 2) {
+            builder.push_gap(leap.gap() as i32, leap.sign()).unwrap();
+        }
+        let inferred = builder.finish_inferred(ExpiryPolicy::Months(6));
+        assert!(!inferred.unwrap().is_lossless(&[Binary]));
+    }
+
+    #[test]
+    fn hash() {
+        use std::convert::{TryFrom, TryInto};
+        use std::str::FromStr;
+        let hex = "4a5c0a9e 7b5cf2e3 3f8e8a1a a1f3d8b5 6fdd4d54";
+        let hash = nist::Hash::from_str(hex).unwrap();
+        assert_eq!(hex, hash.to_string());
+        assert_eq!(hex.to_uppercase(), format!("{:X}", hash));
+        let bytes: [u8; 20] = hash.as_bytes().try_into().unwrap();
+        assert_eq!(hash, nist::Hash::from_bytes(bytes));
+        assert_eq!(bytes, <[u8; 20]>::try_from(&hash).unwrap());
+        assert!(<[u8; 32]>::try_from(&hash).is_err());
+        let line = format!("#h {}", hex);
+        assert_eq!(hash, nist::Hash::from_str(&line).unwrap());
+        let digest = ring::digest::digest(&ring::digest::SHA256, b"");
+        let hash256 = nist::Hash::from(digest);
+        assert_eq!(32, <[u8; 32]>::try_from(&hash256).unwrap().len());
+        assert!(hash256.to_string().starts_with("e3b0c442 98fc1c14 "));
+        let packed = hex.replace(' ', "").to_uppercase();
+        assert_eq!(hash, nist::Hash::from_str(&packed).unwrap());
+        assert!(nist::Hash::from_str(&hex[1..]).is_err());
+        assert!(nist::Hash::from_str(&hex.replace('a', "g")).is_err());
+        #[cfg(feature = "json")]
+        {
+            let json = serde_json::to_string(&hash).unwrap();
+            assert_eq!(format!("{:?}", packed.to_lowercase()), json);
+            let parsed: nist::Hash = serde_json::from_str(&json).unwrap();
+            assert_eq!(hash, parsed);
+        }
+    }
+
+    #[test]
+    fn ntpd() {
+        let list = LeapSecs::from_str_lenient("6+6+12?").unwrap();
+        assert_eq!(Gregorian(1973, 12, 29).mjd(), list.ntpd_warning_date());
+        let updated = Gregorian(1973, 12, 28).mjd();
+        let header = list.render_ntpd_leapfile_header(updated).unwrap();
+        let printed =
+            nist::format_with(&list, updated, nist::Checksum::Sha1).unwrap();
+        assert!(printed.starts_with(&header));
+        assert!(header.contains("#@\t2337552000\n"));
+        assert!(matches!(
+            list.render_ntpd_leapfile_header(updated + 1),
+            Err(Error::NtpdWarning(..))
+        ));
+    }
+
+    #[test]
+    fn provenance() {
+        use core::convert::TryFrom;
+        use std::str::FromStr;
+
+        let text = history!("999?");
+        let list = LeapSecs::from_str(text).unwrap();
+        let literal = |format| Provenance {
+            format,
+            origin: Origin::Literal,
+            security: Security::Local,
+        };
+        assert_eq!(Some(&literal(Format::Text)), list.provenance());
+        let binary = LeapSecs::try_from(&Vec::from(&list)[..]).unwrap();
+        assert_eq!(Some(&literal(Format::Binary)), binary.provenance());
+        let list = list.with_updated(Gregorian(2021, 1, 7).mjd());
+        let printed = nist::format(&list).unwrap();
+        let parsed = nist::read_str(&printed).unwrap();
+        assert_eq!(Some(&literal(Format::Nist)), parsed.provenance());
+
+        #[cfg(feature = "fetch")]
+        {
+            let name = temp_path("provenance.list");
+            let name = name.to_str().unwrap();
+            std::fs::write(name, &printed).unwrap();
+            let parsed = nist::read_file(name).unwrap();
+            std::fs::remove_file(name).unwrap();
+            let want = format!("NIST list from file {}", name);
+            assert_eq!(want, parsed.provenance().unwrap().to_string());
+        }
+    }
+
+    #[test]
+    fn preserving() {
+        use std::str::FromStr;
+
+        let old = history!("59?");
+        let old = LeapSecs::from_str_lenient(old).unwrap();
+        let old = old.with_updated(Gregorian(2020, 7, 8).mjd());
+        let mut template = String::new();
+        for line in nist::format(&old).unwrap().lines() {
+            if line.starts_with("#$") {
+                template += "#\tSee https://hpiers.obspm.fr/ for details\r\n";
+            }
+            if line.starts_with("#h") {
+                template += "#\tThe hash covers the lines above\r\n";
+            }
+            template += line;
+            template += "\r\n";
+        }
+        let new = history!("60+999?");
+        let new = LeapSecs::from_str(new).unwrap();
+        let new = new.with_updated(Gregorian(2021, 1, 7).mjd());
+        let printed = nist::format_preserving(&template, &new).unwrap();
+        assert!(printed.contains("#\tSee https://hpiers.obspm.fr/"));
+        assert!(printed.contains("#\tThe hash covers the lines above\r\n#h"));
+        assert!(printed.contains("#\tupdated 2021-01-07\r\n"));
+        assert!(!printed.contains("sha256"));
+        assert_eq!(template.lines().count() + 1, printed.lines().count());
+        assert_eq!(new, nist::read_str(&printed).unwrap());
+        assert_eq!(nist::format(&new).unwrap(), {
+            let plain = nist::format(&old).unwrap();
+            nist::format_preserving(&plain, &new).unwrap()
+        });
+        assert!(nist::format_preserving("# nothing\n", &new).is_err());
     }
 }
@@ -2,107 +2,259 @@ use ring::digest::*;
 use std::convert::{TryFrom, TryInto};
 use std::fmt::Write;
 
-use super::Hash;
+use super::{Checksum, Hash, LastLine, UncheckedList};
 use crate::*;
 
+impl Hash {
+    fn words(&self) -> impl Iterator<Item = u32> + '_ {
+        self.as_bytes().chunks(4).map(|word| {
+            // panic if the digest is not a whole number of words
+            u32::from_be_bytes(word.try_into().unwrap())
+        })
+    }
+}
+
 impl std::fmt::Display for Hash {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let [h0, h1, h2, h3, h4] = self.0;
-        write!(f, "{:08x} {:08x} {:08x} {:08x} {:08x}", h0, h1, h2, h3, h4)
+        std::fmt::LowerHex::fmt(self, f)
     }
 }
 
-const NTP_EPOCH: MJD = Gregorian(1900, 1, 1).mjd();
-
-fn ntp_from(mjd: MJD) -> i64 {
-    (mjd - NTP_EPOCH) as i64 * 86400
+impl std::fmt::LowerHex for Hash {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, word) in self.words().enumerate() {
+            let sep = if i == 0 { "" } else { " " };
+            write!(f, "{}{:08x}", sep, word)?;
+        }
+        Ok(())
+    }
 }
 
-fn mjd_from(ntp: i64) -> Result<MJD> {
-    let days = i32::try_from(ntp.div_euclid(86400))?;
-    let secs = i32::try_from(ntp.rem_euclid(86400))?;
-    let mjd = NTP_EPOCH + days;
-    if secs != 0 {
-        Err(Error::Midnight(ntp, mjd, secs))
-    } else {
-        Ok(mjd)
+impl std::fmt::UpperHex for Hash {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, word) in self.words().enumerate() {
+            let sep = if i == 0 { "" } else { " " };
+            write!(f, "{}{:08X}", sep, word)?;
+        }
+        Ok(())
     }
 }
 
-pub fn format(list: &LeapSecs, updated_mjd: MJD) -> Result<String> {
+// ntpd warns when its leap seconds file will expire in less than
+// this many days, see check_leap_file() in ntpd/ntp_util.c
+pub const NTPD_WARNING_DAYS: i32 = 31;
+
+fn header(list: &LeapSecs, updated_mjd: MJD) -> Result<String> {
     let mut out = String::new();
     let expires_mjd = list.expires();
     let updated_date = Gregorian::from(updated_mjd);
     let expires_date = Gregorian::from(expires_mjd);
-    let updated_ntp = ntp_from(updated_mjd);
-    let expires_ntp = ntp_from(expires_mjd);
+    let updated_ntp = NTP::from(updated_mjd);
+    let expires_ntp = NTP::from(expires_mjd);
     write!(out, "#\tupdated {}\n#$\t{}\n#\n", updated_date, updated_ntp)?;
     write!(out, "#\texpires {}\n#@\t{}\n#\n", expires_date, expires_ntp)?;
+    Ok(out)
+}
+
+impl LeapSecs {
+    /// Get the date when ntpd will start warning that the list is
+    /// about to expire.
+    ///
+    /// ntpd reads the expiry time from the `#@` line of the NIST
+    /// format, and treats the list as expired from the start of that
+    /// day, which is the day before this crate does. It warns when
+    /// fewer than [`nist::NTPD_WARNING_DAYS`][] whole days remain.
+    ///
+    pub fn ntpd_warning_date(&self) -> MJD {
+        self.expires() - (NTPD_WARNING_DAYS - 1)
+    }
+
+    /// Render the `#$` updated and `#@` expires lines that start a
+    /// NIST format file for ntpd, as written by [`nist::format_with()`][].
+    ///
+    /// Fails with [`Error::NtpdWarning`][] if the `updated` date is on
+    /// or after [`LeapSecs::ntpd_warning_date()`][], because ntpd
+    /// would complain as soon as the file was installed.
+    ///
+    pub fn render_ntpd_leapfile_header(&self, updated: MJD) -> Result<String> {
+        let warning = self.ntpd_warning_date();
+        if updated >= warning {
+            let (updated, warning) = (updated.into(), warning.into());
+            return Err(Error::NtpdWarning(updated, warning));
+        }
+        header(self, updated)
+    }
+}
+
+// the updated date is from the list if it has one, or today
+pub fn format(list: &LeapSecs) -> Result<String> {
+    let updated = list.updated().unwrap_or_else(MJD::today);
+    format_with(list, updated, Checksum::Sha1)
+}
+
+// Checksum::Sha256 appends a SHA-256 line after the standard SHA-1
+pub fn format_with(
+    list: &LeapSecs,
+    updated_mjd: MJD,
+    checksum: Checksum,
+) -> Result<String> {
+    format_with_last_line(list, updated_mjd, checksum, LastLine::Newline)
+}
+
+// like format_with(), and LastLine::Bare leaves out the final newline
+pub fn format_with_last_line(
+    list: &LeapSecs,
+    updated_mjd: MJD,
+    checksum: Checksum,
+    last: LastLine,
+) -> Result<String> {
+    let mut out = header(list, updated_mjd)?;
+    let updated_ntp = NTP::from(updated_mjd).0;
+    leap_lines(&mut out, list, "\n")?;
+    let hashin = hashin(list, updated_ntp)?;
+    write!(out, "#\n#h\t{}\n", sha1(&hashin))?;
+    if checksum == Checksum::Sha256 {
+        writeln!(out, "#\tsha256\t{}", sha256(&hashin))?;
+    }
+    if last == LastLine::Bare {
+        out.pop();
+    }
+    Ok(out)
+}
+
+fn leap_lines(out: &mut String, list: &LeapSecs, newline: &str) -> Result<()> {
     for &leap in list.iter().take(list.len() - 1) {
         let date = Gregorian::from(leap.mjd());
         let month = [
             "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep",
             "Oct", "Nov", "Dec",
         ][(date.month() - 1) as usize];
-        writeln!(
+        write!(
             out,
-            "{}\t{}\t# {} {} {}",
-            ntp_from(leap.mjd()),
+            "{}\t{}\t# {} {} {}{}",
+            NTP::from(leap.mjd()),
             leap.dtai().unwrap(),
             date.day(),
             month,
-            date.year()
+            date.year(),
+            newline
         )?;
     }
-    let hash = sha1(&hashin(list, updated_ntp)?);
-    write!(out, "#\n#h\t{}\n", hash)?;
-    Ok(out)
+    Ok(())
+}
+
+// the whitespace after a directive
+fn separator(rest: &str) -> &str {
+    &rest[..rest.len() - rest.trim_start().len()]
 }
 
-impl TryFrom<super::UncheckedList> for LeapSecs {
-    type Error = Error;
-    fn try_from(u: super::UncheckedList) -> Result<LeapSecs> {
-        let mut list = LeapSecs::builder();
-        for (ntp, dtai, date) in u.leapsecs {
-            let mjd = mjd_from(ntp)?;
-            if mjd != MJD::from(date) {
-                return Err(Error::TimeDate(ntp, mjd, date));
-            } else {
-                list.push_date(date, dtai)?
+// Format an updated list in the same layout as an existing NIST file,
+// keeping its comments, unknown directives, and line endings, so that
+// a diff against the original shows only what changed. The `#$`,
+// `#@`, and `#h` lines and the leap seconds are replaced, as are the
+// `updated` and `expires` comments that format() writes. There is a
+// SHA-256 line if the original had one. Blank lines and comments after
+// the hashes are kept, and so is a missing newline at the end.
+pub fn format_preserving(template: &str, list: &LeapSecs) -> Result<String> {
+    let original = super::parse(template)?;
+    let updated_mjd = list.updated().unwrap_or_else(MJD::today);
+    let updated_ntp = NTP::from(updated_mjd);
+    let expires_mjd = list.expires();
+    let hashin = hashin(list, updated_ntp.0)?;
+    let newline = if template.contains("\r\n") { "\r\n" } else { "\n" };
+    let mut out = String::new();
+    let mut leaps_done = false;
+    for line in template.lines() {
+        let is_sha256 = line.strip_prefix('#').is_some_and(|rest| {
+            rest.starts_with(char::is_whitespace)
+                && rest.trim_start().starts_with("sha256")
+        });
+        if let Some(rest) = line.strip_prefix("#$") {
+            write!(out, "#${}{}", separator(rest), updated_ntp)?;
+        } else if let Some(rest) = line.strip_prefix("#@") {
+            write!(out, "#@{}{}", separator(rest), NTP::from(expires_mjd))?;
+        } else if let Some(rest) = line.strip_prefix("#h") {
+            write!(out, "#h{}{}", separator(rest), sha1(&hashin))?;
+            if original.hash256.is_some() {
+                write!(out, "{}#\tsha256\t{}", newline, sha256(&hashin))?;
             }
+        } else if line.starts_with("#\tupdated ") {
+            write!(out, "#\tupdated {}", Gregorian::from(updated_mjd))?;
+        } else if line.starts_with("#\texpires ") {
+            write!(out, "#\texpires {}", Gregorian::from(expires_mjd))?;
+        } else if is_sha256 {
+            continue;
+        } else if line.starts_with(|c: char| c.is_ascii_digit()) {
+            if !leaps_done {
+                leap_lines(&mut out, list, newline)?;
+                leaps_done = true;
+            }
+            continue;
+        } else {
+            out += line;
         }
-        let _check = mjd_from(u.updated)?;
-        let expires = mjd_from(u.expires)?;
-        list.push_exp(Gregorian::from(expires))?;
-        let list = list.finish()?;
-        let hashin = hashin(&list, u.updated)?;
-        let calculated = sha1(&hashin);
-        if u.hash != calculated {
-            Err(Error::Checksum(u.hash, calculated, hashin))
+        out += newline;
+    }
+    if !template.ends_with('\n') {
+        out.truncate(out.len() - newline.len());
+    }
+    Ok(out)
+}
+
+// verify an unchecked list, using `finish` to check its expiry time
+pub(super) fn check<F>(u: UncheckedList, finish: F) -> Result<LeapSecs>
+where
+    F: FnOnce(LeapSecBuilder) -> Result<LeapSecs>,
+{
+    let mut list = LeapSecs::builder();
+    for (ntp, dtai, date) in u.leapsecs {
+        let mjd = MJD::try_from(NTP(ntp))?;
+        if mjd != MJD::from(date) {
+            return Err(Error::TimeDate(ntp, mjd, date));
         } else {
-            Ok(list)
+            list.push_date(date, dtai)?
+        }
+    }
+    // the update time is not always midnight (e.g. the IERS list of
+    // January 2026) so this is the date containing that time
+    let updated = NTP_EPOCH.checked_add_days(u.updated.div_euclid(86400))?;
+    let expires = MJD::try_from(NTP(u.expires))?;
+    list.push_exp(Gregorian::from(expires))?;
+    let list = finish(list)?.with_updated(updated);
+    let hashin = hashin(&list, u.updated)?;
+    let calculated = sha1(&hashin);
+    if u.hash != calculated {
+        event!(warn, "SHA-1 checksum failed {} <> {}", u.hash, calculated);
+        return Err(Error::Checksum(u.hash, calculated, hashin));
+    }
+    if let Some(hash) = u.hash256 {
+        let calculated = sha256(&hashin);
+        if hash != calculated {
+            event!(warn, "SHA-256 checksum failed {} <> {}", hash, calculated);
+            return Err(Error::Checksum(hash, calculated, hashin));
         }
     }
+    Ok(list.with_literal(Format::Nist))
 }
 
 fn hashin(list: &LeapSecs, updated: i64) -> Result<String> {
-    let expires = ntp_from(list.expires());
+    let expires = NTP::from(list.expires());
     let mut hashin = String::new();
     write!(hashin, "{}{}", updated, expires)?;
     for leap in list.iter().take(list.len() - 1) {
-        write!(hashin, "{}{}", ntp_from(leap.mjd()), leap.dtai().unwrap())?;
+        write!(hashin, "{}{}", NTP::from(leap.mjd()), leap.dtai().unwrap())?;
     }
     Ok(hashin)
 }
 
 fn sha1(input: &str) -> Hash {
-    let hash = digest(&SHA1_FOR_LEGACY_USE_ONLY, input.as_bytes());
-    // panic if sha1 is not the standard size
-    let hash8: [u8; 20] = hash.as_ref().try_into().unwrap();
-    let mut hash32: Hash = Default::default();
-    for i in 0..5 {
-        let word: [u8; 4] = hash8[i * 4..i * 4 + 4].try_into().unwrap();
-        hash32.0[i] = u32::from_be_bytes(word);
-    }
-    hash32
+    hash(&SHA1_FOR_LEGACY_USE_ONLY, input)
+}
+
+fn sha256(input: &str) -> Hash {
+    hash(&SHA256, input)
+}
+
+fn hash(algorithm: &'static Algorithm, input: &str) -> Hash {
+    Hash::from(digest(algorithm, input.as_bytes()))
 }
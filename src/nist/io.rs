@@ -0,0 +1,1052 @@
+// fetch and cache the NIST leap-seconds.list, with curl
+
+use anyhow::Context;
+use std::io::Read;
+
+use super::{read_bytes, read_bytes_at, read_bytes_lenient};
+use crate::*;
+
+// the cache file in the current directory
+pub const NIST_FILE: &str = "leap-seconds.list";
+// where the list originally comes from, which is only available over
+// FTP, so it is often unreachable
+pub const NIST_URL: &str =
+    "ftp://ftp.boulder.nist.gov/pub/time/leap-seconds.list";
+// the IERS copy of the same file
+pub const IERS_URL: &str =
+    "https://hpiers.obspm.fr/iers/bul/bulc/ntp/leap-seconds.list";
+// the copy distributed with the tz database
+pub const IANA_URL: &str =
+    "https://data.iana.org/time-zones/tzdb/leap-seconds.list";
+// Meinberg's copy, for NTP servers
+pub const MEINBERG_URL: &str =
+    "https://www.meinberg.de/download/ntp/leap-seconds.list";
+// the HTTPS mirrors that read() and fetch() try, in order
+pub const MIRRORS: &[&str] = &[IERS_URL, IANA_URL, MEINBERG_URL];
+
+pub fn read() -> anyhow::Result<LeapSecs> {
+    read_with(&FileStore::default())
+}
+
+// like read(), but with the cache kept in the given store; the
+// fetched list is checked before it is stored
+pub fn read_with<S: Store + ?Sized>(store: &S) -> anyhow::Result<LeapSecs> {
+    if let Some(data) = store.load()? {
+        event!(debug, "using cached {}", store.provenance());
+        let list = read_bytes(&data).map_err(expired)?;
+        return Ok(list.with_provenance(store.provenance()));
+    }
+    Mirrors::default().read_with(store)
+}
+
+// Like read_with(), but check for a new list at `url` every time,
+// using a conditional request with the HTTP validators saved with
+// the cached list, so that a polling daemon only downloads the list
+// when it has changed. The server is not contacted at all while its
+// Cache-Control header says the cached copy is fresh.
+pub fn read_url_with<S: Store + ?Sized>(
+    store: &S,
+    url: &str,
+) -> anyhow::Result<LeapSecs> {
+    let cached = store.load()?;
+    let validators = match cached {
+        Some(_) => store.load_validators()?,
+        None => Validators::default(),
+    };
+    match (cached, fetch_conditional(url, &validators)?) {
+        (_, Fetched::Modified(data, validators)) => {
+            // check the new list before caching it
+            let provenance = self::url(url);
+            let list = read_bytes(&data)?.with_provenance(provenance.clone());
+            store.store(&data)?;
+            store.store_provenance(&provenance)?;
+            store.store_validators(&validators)?;
+            Ok(list)
+        }
+        (Some(data), Fetched::NotModified(validators)) => {
+            event!(debug, "using cached {}", store.provenance());
+            store.store_validators(&validators)?;
+            let list = read_bytes(&data).map_err(expired)?;
+            Ok(list.with_provenance(store.provenance()))
+        }
+        (None, Fetched::NotModified(_)) => anyhow::bail!("nothing fetched"),
+    }
+}
+
+////////////////////////////////////////////////////////////////////////
+
+// Where read_with() caches the NIST list, so that services can keep
+// it in a database or object store instead of a file.
+pub trait Store {
+    // the cached data, or None if there is nothing usable cached
+    fn load(&self) -> anyhow::Result<Option<Vec<u8>>>;
+    // replace the cached data with a newly fetched list
+    fn store(&self, data: &[u8]) -> anyhow::Result<()>;
+    // record where the newly fetched list came from
+    fn store_provenance(&self, provenance: &Provenance) -> anyhow::Result<()>;
+    // the provenance of a list loaded from the cache
+    fn provenance(&self) -> Provenance;
+    // the HTTP validators of the cached data; stores that do not
+    // keep them make every fetch unconditional
+    fn load_validators(&self) -> anyhow::Result<Validators> {
+        Ok(Validators::default())
+    }
+    // record the HTTP validators of newly fetched or revalidated data
+    fn store_validators(&self, _: &Validators) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+// The default store: a file, NIST_FILE in the current directory
+// unless otherwise specified, with its provenance and HTTP validators
+// alongside in files with `.provenance` and `.validators` appended to
+// its name.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FileStore {
+    pub path: String,
+}
+
+impl Default for FileStore {
+    fn default() -> FileStore {
+        FileStore { path: NIST_FILE.to_string() }
+    }
+}
+
+impl FileStore {
+    pub fn new(path: &str) -> FileStore {
+        FileStore { path: path.to_string() }
+    }
+}
+
+impl Store for FileStore {
+    // any problem reading the file means the cache needs refreshing
+    fn load(&self) -> anyhow::Result<Option<Vec<u8>>> {
+        Ok(load_file(&self.path).ok())
+    }
+
+    fn store(&self, data: &[u8]) -> anyhow::Result<()> {
+        std::fs::write(&self.path, data)
+            .with_context(|| format!("failed to write {}", self.path))
+    }
+
+    fn store_provenance(&self, provenance: &Provenance) -> anyhow::Result<()> {
+        let path = format!("{}.provenance", self.path);
+        std::fs::write(&path, format!("{}\n", provenance))
+            .with_context(|| format!("failed to write {}", path))
+    }
+
+    fn provenance(&self) -> Provenance {
+        file(&self.path)
+    }
+
+    // missing or unreadable validators mean an unconditional fetch
+    fn load_validators(&self) -> anyhow::Result<Validators> {
+        let path = format!("{}.validators", self.path);
+        let text = std::fs::read_to_string(path).unwrap_or_default();
+        Ok(Validators::from_text(&text))
+    }
+
+    fn store_validators(&self, validators: &Validators) -> anyhow::Result<()> {
+        let path = format!("{}.validators", self.path);
+        std::fs::write(&path, validators.to_string())
+            .with_context(|| format!("failed to write {}", path))
+    }
+}
+
+////////////////////////////////////////////////////////////////////////
+
+pub fn read_file(name: &str) -> anyhow::Result<LeapSecs> {
+    Ok(read_bytes(&load_file(name)?)?.with_provenance(file(name)))
+}
+
+pub fn read_file_lenient(name: &str) -> anyhow::Result<LeapSecs> {
+    Ok(read_bytes_lenient(&load_file(name)?)?.with_provenance(file(name)))
+}
+
+pub fn read_url(url: &str) -> anyhow::Result<LeapSecs> {
+    Ok(read_bytes(&load_url(url)?)?.with_provenance(self::url(url)))
+}
+
+// fetch over HTTPS, checking that the server's public key matches
+// the `pin`, in curl's format, `sha256//` followed by the base64
+// SHA-256 hash of the DER public key; several pins can be separated
+// by `;`
+pub fn read_url_pinned(url: &str, pin: &str) -> anyhow::Result<LeapSecs> {
+    let data = load_url_pinned(url, pin)?;
+    let security = Security::Pinned;
+    let provenance = Provenance { security, ..self::url(url) };
+    Ok(read_bytes(&data)?.with_provenance(provenance))
+}
+
+fn load_url_pinned(url: &str, pin: &str) -> anyhow::Result<Vec<u8>> {
+    if !url.starts_with("https://") {
+        anyhow::bail!("cannot pin the public key for {}", url);
+    }
+    let mut data = Vec::new();
+    let mut encoding = None;
+    let none = Validators::default();
+    curl_get(url, Some(pin), &none, &mut data, &mut encoding)
+        .with_context(|| format!("failed to fetch {}", &url))?;
+    decompress(data, encoding.as_deref())
+        .with_context(|| format!("failed to decompress {}", &url))
+}
+
+// how well a URL without a pinned key protects the data, going by
+// its scheme
+pub fn url_security(url: &str) -> Security {
+    let scheme = url.split_once("://").map_or("", |(scheme, _)| scheme);
+    match scheme.to_ascii_lowercase().as_str() {
+        "file" => Security::Local,
+        "ftps" | "https" => Security::Tls,
+        _ => Security::Insecure,
+    }
+}
+
+// fetch a list and its detached signature from `url` + ".asc", and
+// check the signature with the trusted keys before parsing the list
+#[cfg(feature = "pgp")]
+pub fn read_verified(
+    url: &str,
+    keys: &[pgp::PublicKey],
+) -> anyhow::Result<LeapSecs> {
+    let data = load_url(url)?;
+    let signature = load_url(&format!("{}.asc", url))?;
+    let list = super::read_bytes_verified(&data, &signature, keys)?;
+    Ok(list.with_provenance(self::url(url)))
+}
+
+// the lists from read_bytes() and friends say they came from a
+// literal, which the functions that know better replace
+
+fn file(name: &str) -> Provenance {
+    let origin = Origin::File(name.to_string());
+    Provenance { format: Format::Nist, origin, security: Security::Local }
+}
+
+fn url(url: &str) -> Provenance {
+    let origin = Origin::Url(url.to_string());
+    let security = url_security(url);
+    Provenance { format: Format::Nist, origin, security }
+}
+
+// download from the default mirrors without caching, so the caller
+// can check it before saving it
+pub fn fetch() -> anyhow::Result<Vec<u8>> {
+    Ok(Mirrors::default().fetch()?.1)
+}
+
+// An ordered list of URLs to get the leap-seconds.list from, which
+// are tried in turn until one of them has a valid list, so that one
+// unreachable server does not stop a refresh. The default is MIRRORS.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Mirrors {
+    pub urls: Vec<String>,
+}
+
+impl Default for Mirrors {
+    fn default() -> Mirrors {
+        Mirrors::new(MIRRORS)
+    }
+}
+
+impl Mirrors {
+    pub fn new(urls: &[&str]) -> Mirrors {
+        Mirrors { urls: urls.iter().map(|url| url.to_string()).collect() }
+    }
+
+    // Download from the first mirror with a valid list, returning its
+    // URL and the data. The list is checked, but unlike read_url() the
+    // data is returned as it was fetched.
+    pub fn fetch(&self) -> anyhow::Result<(String, Vec<u8>)> {
+        self.first(|url| {
+            let data = load_url(url)?;
+            read_bytes(&data)?;
+            Ok(data)
+        })
+    }
+
+    // like read_url_with(), using the first mirror that works
+    pub fn read_with<S: Store + ?Sized>(
+        &self,
+        store: &S,
+    ) -> anyhow::Result<LeapSecs> {
+        Ok(self.first(|url| read_url_with(store, url))?.1)
+    }
+
+    // the mirrors as sources for read_best() or an Updater
+    pub fn sources(&self) -> Vec<Source> {
+        self.urls.iter().map(|url| Source::Url(url.clone())).collect()
+    }
+
+    // fails with MirrorErrors if none of the mirrors works
+    fn first<T, F>(&self, mut get: F) -> anyhow::Result<(String, T)>
+    where
+        F: FnMut(&str) -> anyhow::Result<T>,
+    {
+        let mut failures = Vec::new();
+        for url in &self.urls {
+            match get(url) {
+                Ok(got) => return Ok((url.clone(), got)),
+                Err(err) => {
+                    event!(warn, "failed to read {}: {:#}", url, err);
+                    failures.push((url.clone(), err));
+                }
+            }
+        }
+        Err(MirrorErrors(failures).into())
+    }
+}
+
+// why each mirror failed, in the same order as the mirrors
+#[derive(Debug)]
+pub struct MirrorErrors(pub Vec<(String, anyhow::Error)>);
+
+impl std::fmt::Display for MirrorErrors {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "no mirror has a valid leap seconds list")?;
+        for (url, err) in &self.0 {
+            write!(f, "\n{}: {:#}", url, err)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for MirrorErrors {}
+
+// HTTP cache validators for a fetched list, so that the next fetch
+// can be a conditional request, and how long the server said that
+// the response stays fresh
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Validators {
+    // the ETag header, sent back in If-None-Match
+    pub etag: Option<String>,
+    // the Last-Modified time in POSIX seconds, sent back in
+    // If-Modified-Since
+    pub last_modified: Option<i64>,
+    // the POSIX time until which the response is fresh, from the
+    // Cache-Control max-age, or None if it must be revalidated
+    pub fresh_until: Option<i64>,
+}
+
+impl Validators {
+    // whether the cached copy can be used without asking the server
+    pub fn is_fresh_at(&self, now: Unix) -> bool {
+        self.fresh_until.is_some_and(|fresh| now.0 < fresh)
+    }
+
+    // read the format written by Display, ignoring anything malformed
+    // because validators are only hints
+    pub fn from_text(text: &str) -> Validators {
+        let mut validators = Validators::default();
+        for line in text.lines() {
+            let (name, value) = match line.split_once(':') {
+                Some((name, value)) => (name, value.trim()),
+                None => continue,
+            };
+            match name {
+                "etag" => validators.etag = Some(value.to_string()),
+                "last-modified" => {
+                    validators.last_modified = value.parse().ok()
+                }
+                "fresh-until" => validators.fresh_until = value.parse().ok(),
+                _ => (),
+            }
+        }
+        validators
+    }
+}
+
+// one `name: value` line for each validator
+impl std::fmt::Display for Validators {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(etag) = &self.etag {
+            writeln!(f, "etag: {}", etag)?;
+        }
+        if let Some(time) = self.last_modified {
+            writeln!(f, "last-modified: {}", time)?;
+        }
+        if let Some(time) = self.fresh_until {
+            writeln!(f, "fresh-until: {}", time)?;
+        }
+        Ok(())
+    }
+}
+
+// the result of fetch_conditional()
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Fetched {
+    // a new copy of the list, which has not been checked
+    Modified(Vec<u8>, Validators),
+    // the cached copy is still current; the validators might have
+    // been updated
+    NotModified(Validators),
+}
+
+// Download without caching, like fetch(), but only if the copy at
+// `url` differs from the one with the given validators. Returns
+// NotModified without contacting the server if the cached copy is
+// still fresh.
+pub fn fetch_conditional(
+    url: &str,
+    validators: &Validators,
+) -> anyhow::Result<Fetched> {
+    let now = unix_now();
+    if validators.is_fresh_at(now) {
+        event!(debug, "cached copy of {} is fresh", url);
+        return Ok(Fetched::NotModified(validators.clone()));
+    }
+    event!(info, "fetching {}", url);
+    let mut data = Vec::new();
+    let mut encoding = None;
+    let (modified, fetched) =
+        curl_get(url, None, validators, &mut data, &mut encoding)
+            .with_context(|| format!("failed to fetch {}", &url))?;
+    if !modified {
+        event!(debug, "{} is not modified", url);
+        return Ok(Fetched::NotModified(Validators {
+            etag: fetched.etag.or_else(|| validators.etag.clone()),
+            last_modified: validators.last_modified,
+            fresh_until: fetched.fresh_until,
+        }));
+    }
+    let data = decompress(data, encoding.as_deref())
+        .with_context(|| format!("failed to decompress {}", &url))?;
+    Ok(Fetched::Modified(data, fetched))
+}
+
+fn unix_now() -> Unix {
+    use std::time::SystemTime;
+    let now = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH);
+    Unix(now.map_or(0, |now| now.as_secs() as i64))
+}
+
+////////////////////////////////////////////////////////////////////////
+
+// Places that read_best() can get a list from. The NIST server is
+// often unreachable, so it is best to have a few alternatives.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Source {
+    File(String),
+    Iers,
+    Nist,
+    // a URL and its pinned public key, see read_url_pinned()
+    Pinned(String, String),
+    Url(String),
+}
+
+impl std::fmt::Display for Source {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Source::File(name) => write!(f, "{}", name),
+            Source::Iers => write!(f, "{}", IERS_URL),
+            Source::Nist => write!(f, "{}", NIST_URL),
+            Source::Pinned(url, _) => write!(f, "{}", url),
+            Source::Url(url) => write!(f, "{}", url),
+        }
+    }
+}
+
+impl Source {
+    pub fn read(&self) -> anyhow::Result<LeapSecs> {
+        self.read_at(SystemClock)
+    }
+
+    // like read(), but check for expiry using the given clock
+    pub fn read_at<C: Clock>(&self, clock: C) -> anyhow::Result<LeapSecs> {
+        let (data, provenance) = match self {
+            Source::File(name) => (load_file(name)?, file(name)),
+            Source::Iers => (load_url(IERS_URL)?, url(IERS_URL)),
+            Source::Nist => (load_url(NIST_URL)?, url(NIST_URL)),
+            Source::Pinned(pinned, pin) => {
+                let security = Security::Pinned;
+                let provenance = Provenance { security, ..url(pinned) };
+                (load_url_pinned(pinned, pin)?, provenance)
+            }
+            Source::Url(link) => (load_url(link)?, url(link)),
+        };
+        Ok(read_bytes_at(&data, clock)?.with_provenance(provenance))
+    }
+
+    pub fn security(&self) -> Security {
+        match self {
+            Source::File(_) => Security::Local,
+            Source::Iers => url_security(IERS_URL),
+            Source::Nist => url_security(NIST_URL),
+            Source::Pinned(..) => Security::Pinned,
+            Source::Url(url) => url_security(url),
+        }
+    }
+}
+
+// Which sources read_best_with() will use. By default it refuses
+// insecure transports such as the NIST FTP server.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct Policy {
+    pub allow_insecure: bool,
+}
+
+// the result of read_best(), with the reasons the other sources failed
+#[derive(Debug)]
+pub struct Best {
+    pub list: LeapSecs,
+    pub source: Source,
+    pub failures: Vec<(Source, anyhow::Error)>,
+}
+
+// Try all the sources at the same time, and return the valid list
+// with the latest expiry date; if there is a tie, the most secure
+// source wins, then the earliest. Fails if none of the sources has a
+// valid list. This allows insecure sources.
+pub fn read_best(sources: &[Source]) -> anyhow::Result<Best> {
+    read_best_with(sources, Policy { allow_insecure: true })
+}
+
+// like read_best(), but refuse insecure sources unless the policy
+// allows them
+pub fn read_best_with(
+    sources: &[Source],
+    policy: Policy,
+) -> anyhow::Result<Best> {
+    read_best_at(sources, policy, SystemClock)
+}
+
+// like read_best_with(), but check the lists for expiry using the
+// given clock
+pub fn read_best_at<C: Clock + Sync>(
+    sources: &[Source],
+    policy: Policy,
+    clock: C,
+) -> anyhow::Result<Best> {
+    let clock = &clock;
+    let read = |source: &Source| {
+        if source.security() == Security::Insecure && !policy.allow_insecure {
+            anyhow::bail!("refusing insecure transport")
+        }
+        source.read_at(clock)
+    };
+    let results: Vec<_> = std::thread::scope(|scope| {
+        let threads: Vec<_> = sources
+            .iter()
+            .map(|source| scope.spawn(move || read(source)))
+            .collect();
+        threads.into_iter().map(|thread| thread.join().unwrap()).collect()
+    });
+    let mut best: Option<(LeapSecs, Source)> = None;
+    let mut failures = Vec::new();
+    for (source, result) in sources.iter().zip(results) {
+        let list = match result {
+            Ok(list) => list,
+            Err(err) => {
+                event!(warn, "failed to read {}: {:#}", source, err);
+                failures.push((source.clone(), err));
+                continue;
+            }
+        };
+        let rank = (list.expires(), source.security());
+        match &best {
+            Some((prev, old)) if (prev.expires(), old.security()) >= rank => (),
+            _ => best = Some((list, source.clone())),
+        }
+    }
+    match best {
+        Some((list, source)) => {
+            let expires = Gregorian::from(list.expires());
+            event!(info, "best list from {} expires {}", source, expires);
+            Ok(Best { list, source, failures })
+        }
+        None => {
+            let mut msg = String::from("no valid leap seconds list");
+            for (source, err) in &failures {
+                msg += &format!("\n{}: {:#}", source, err);
+            }
+            anyhow::bail!(msg)
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////
+
+fn load_file(name: &str) -> anyhow::Result<Vec<u8>> {
+    let ctx = || format!("failed to read {}", name);
+    let mut fh = std::fs::File::open(name).with_context(ctx)?;
+    let mut data = Vec::new();
+    fh.read_to_end(&mut data).with_context(ctx)?;
+    decompress(data, None).with_context(ctx)
+}
+
+// report an expired list before passing on the error
+fn expired(err: Error) -> Error {
+    if let Error::Expired(date) = &err {
+        event!(warn, "leap seconds list expired on {}", date);
+    }
+    err
+}
+
+fn load_url(url: &str) -> anyhow::Result<Vec<u8>> {
+    event!(info, "fetching {}", url);
+    let mut data = Vec::new();
+    let mut encoding = None;
+    let none = Validators::default();
+    curl_get(url, None, &none, &mut data, &mut encoding)
+        .with_context(|| format!("failed to fetch {}", &url))?;
+    decompress(data, encoding.as_deref())
+        .with_context(|| format!("failed to decompress {}", &url))
+}
+
+// Returns whether the data was modified since the time or ETag in
+// the `cached` validators, and the response's validators, with the
+// freshness lifetime relative to now.
+fn curl_get(
+    url: &str,
+    pin: Option<&str>,
+    cached: &Validators,
+    buffer: &mut Vec<u8>,
+    encoding: &mut Option<String>,
+) -> anyhow::Result<(bool, Validators)> {
+    let mut ua = curl::easy::Easy::new();
+    ua.useragent(&format!(
+        "leapsecs/0 curl/{}",
+        curl::Version::get().version()
+    ))?;
+    ua.fail_on_error(true)?;
+    ua.url(url)?;
+    if let Some(pin) = pin {
+        ua.pinned_public_key(pin)?;
+    }
+    let mut headers = curl::easy::List::new();
+    if cfg!(feature = "gzip") {
+        headers.append("Accept-Encoding: gzip, deflate")?;
+    }
+    if let Some(etag) = &cached.etag {
+        headers.append(&format!("If-None-Match: {}", etag))?;
+    }
+    ua.http_headers(headers)?;
+    if let Some(time) = cached.last_modified {
+        ua.time_condition(curl::easy::TimeCondition::IfModifiedSince)?;
+        ua.time_value(time)?;
+    }
+    ua.fetch_filetime(true)?;
+    let mut validators = Validators::default();
+    let mut age = 0;
+    let mut xfer = ua.transfer();
+    xfer.header_function(|header| {
+        let header = String::from_utf8_lossy(header);
+        let (name, value) = match header.split_once(':') {
+            Some((name, value)) => (name.to_ascii_lowercase(), value.trim()),
+            None => return true,
+        };
+        match name.as_str() {
+            "age" => age = value.parse().unwrap_or(0),
+            "cache-control" => validators.fresh_until = max_age(value),
+            "content-encoding" => {
+                *encoding = Some(value.to_ascii_lowercase());
+            }
+            "etag" => validators.etag = Some(value.to_string()),
+            _ => (),
+        }
+        true
+    })?;
+    xfer.write_function(|chunk| {
+        buffer.extend_from_slice(chunk);
+        Ok(chunk.len())
+    })?;
+    xfer.perform()?;
+    drop(xfer);
+    let modified = ua.response_code()? != 304 && !ua.time_condition_unmet()?;
+    validators.last_modified = ua.filetime()?;
+    let now = unix_now();
+    validators.fresh_until =
+        validators.fresh_until.map(|max| fresh_until(now, max, age));
+    Ok((modified, validators))
+}
+
+// when a response stops being fresh, given its Cache-Control max-age
+// and Age headers, which come from the server, so the arithmetic
+// saturates instead of overflowing
+fn fresh_until(now: Unix, max_age: i64, age: i64) -> i64 {
+    now.0.saturating_add(max_age.saturating_sub(age))
+}
+
+// the freshness lifetime from a Cache-Control header, or None if the
+// response must be revalidated every time
+fn max_age(cache_control: &str) -> Option<i64> {
+    let mut max_age = None;
+    for directive in cache_control.split(',') {
+        let directive = directive.trim().to_ascii_lowercase();
+        if directive == "no-cache" || directive == "no-store" {
+            return None;
+        }
+        if let Some(secs) = directive.strip_prefix("max-age=") {
+            max_age = secs.trim_matches('"').parse().ok();
+        }
+    }
+    max_age
+}
+
+const GZIP_MAGIC: &[u8] = b"\x1f\x8b";
+
+// undo HTTP content encoding, or gzip compression of archived copies
+#[cfg(feature = "gzip")]
+fn decompress(
+    data: Vec<u8>,
+    encoding: Option<&str>,
+) -> anyhow::Result<Vec<u8>> {
+    use flate2::read::{GzDecoder, ZlibDecoder};
+    let mut out = Vec::new();
+    if encoding == Some("deflate") {
+        ZlibDecoder::new(&data[..]).read_to_end(&mut out)?;
+    } else if data.starts_with(GZIP_MAGIC) {
+        GzDecoder::new(&data[..]).read_to_end(&mut out)?;
+    } else {
+        return Ok(data);
+    }
+    Ok(out)
+}
+
+#[cfg(not(feature = "gzip"))]
+fn decompress(data: Vec<u8>, _: Option<&str>) -> anyhow::Result<Vec<u8>> {
+    if data.starts_with(GZIP_MAGIC) {
+        anyhow::bail!("compressed data requires the gzip feature");
+    }
+    Ok(data)
+}
+
+////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+
+    #[test]
+    fn store() {
+        use std::cell::RefCell;
+        use std::str::FromStr;
+
+        // a cache in memory, as if it were a database
+        #[derive(Default)]
+        struct Memory(RefCell<Option<Vec<u8>>>);
+
+        impl nist::Store for Memory {
+            fn load(&self) -> anyhow::Result<Option<Vec<u8>>> {
+                Ok(self.0.borrow().clone())
+            }
+            fn store(&self, data: &[u8]) -> anyhow::Result<()> {
+                *self.0.borrow_mut() = Some(data.to_vec());
+                Ok(())
+            }
+            fn store_provenance(&self, _: &Provenance) -> anyhow::Result<()> {
+                Ok(())
+            }
+            fn provenance(&self) -> Provenance {
+                let origin = Origin::Url("memory:".to_string());
+                let security = Security::Local;
+                Provenance { format: Format::Nist, origin, security }
+            }
+        }
+
+        let text = history!("999?");
+        let list = LeapSecs::from_str(text).unwrap();
+        let list = list.with_updated(Gregorian(2021, 1, 7).mjd());
+        let printed = nist::format(&list).unwrap();
+        let memory = Memory::default();
+        nist::Store::store(&memory, printed.as_bytes()).unwrap();
+        let cached = nist::read_with(&memory).unwrap();
+        assert_eq!(list, cached);
+        let want = "NIST list from memory: (local)";
+        assert_eq!(want, cached.provenance().unwrap().to_string());
+
+        let name = temp_path("store.list");
+        let store = nist::FileStore::new(name.to_str().unwrap());
+        assert!(nist::Store::load(&store).unwrap().is_none());
+        nist::Store::store(&store, printed.as_bytes()).unwrap();
+        let provenance = super::url(nist::IERS_URL);
+        nist::Store::store_provenance(&store, &provenance).unwrap();
+        assert_eq!(list, nist::read_with(&store).unwrap());
+        let saved = format!("{}.provenance", store.path);
+        let note = std::fs::read_to_string(&saved).unwrap();
+        assert_eq!(format!("{}\n", provenance), note);
+        std::fs::remove_file(&store.path).unwrap();
+        std::fs::remove_file(&saved).unwrap();
+    }
+
+    #[test]
+    fn mirrors() {
+        use nist::{MirrorErrors, Mirrors};
+        use std::str::FromStr;
+
+        let text = history!("999?");
+        let list = LeapSecs::from_str(text).unwrap();
+        let base = temp_path("mirror");
+        let path = |name: &str| format!("{}-{}.list", base.display(), name);
+        std::fs::write(path("good"), nist::format(&list).unwrap()).unwrap();
+        std::fs::write(path("bad"), "garbage").unwrap();
+        let _ = std::fs::remove_file(path("missing"));
+        let url = |name: &str| format!("file://{}", path(name));
+        let (missing, bad, good) = (url("missing"), url("bad"), url("good"));
+
+        let mirrors = Mirrors::new(&[&missing, &bad, &good]);
+        let (from, data) = mirrors.fetch().unwrap();
+        assert_eq!(from, good);
+        assert_eq!(list, nist::read_bytes(&data).unwrap());
+        assert_eq!(mirrors.sources()[2], nist::Source::Url(good));
+
+        let store = nist::FileStore::new(&path("cache"));
+        let _ = std::fs::remove_file(&store.path);
+        assert_eq!(list, mirrors.read_with(&store).unwrap());
+        assert_eq!(list, nist::read_file(&store.path).unwrap());
+
+        let mirrors = Mirrors::new(&[&missing, &bad]);
+        let err = mirrors.fetch().unwrap_err();
+        let failures = &err.downcast_ref::<MirrorErrors>().unwrap().0;
+        let urls: Vec<&str> = failures.iter().map(|f| f.0.as_str()).collect();
+        assert_eq!(urls, [&missing, &bad]);
+        let msg = err.to_string();
+        assert!(msg.starts_with("no mirror") && msg.contains(&missing));
+        assert!(Mirrors::new(&[]).fetch().is_err());
+        assert!(Mirrors::default().urls[0].starts_with("https://"));
+
+        for name in &["good", "bad", "cache"] {
+            std::fs::remove_file(path(name)).unwrap();
+        }
+        for ext in &["provenance", "validators"] {
+            std::fs::remove_file(format!("{}.{}", store.path, ext)).unwrap();
+        }
+    }
+
+    #[test]
+    fn conditional() {
+        use nist::{Fetched, Validators};
+        use std::str::FromStr;
+        use std::time::{Duration, SystemTime};
+
+        let text = history!("999?");
+        let list = LeapSecs::from_str(text).unwrap();
+        let printed = nist::format(&list).unwrap();
+        let mirror = temp_path("conditional-mirror.list");
+        std::fs::write(&mirror, &printed).unwrap();
+        let touch = |secs: u64| {
+            let file = std::fs::File::options().write(true).open(&mirror);
+            let time = SystemTime::UNIX_EPOCH + Duration::from_secs(secs);
+            file.unwrap().set_modified(time).unwrap();
+        };
+        touch(1_700_000_000);
+        let url = format!("file://{}", mirror.display());
+
+        let fetched = nist::fetch_conditional(&url, &Validators::default());
+        let validators = match fetched.unwrap() {
+            Fetched::Modified(data, validators) => {
+                assert_eq!(data, printed.as_bytes());
+                validators
+            }
+            other => panic!("{:?}", other),
+        };
+        assert_eq!(validators.last_modified, Some(1_700_000_000));
+        let fetched = nist::fetch_conditional(&url, &validators).unwrap();
+        assert!(matches!(fetched, Fetched::NotModified(_)));
+        touch(1_700_000_001);
+        let fetched = nist::fetch_conditional(&url, &validators).unwrap();
+        assert!(matches!(fetched, Fetched::Modified(..)));
+
+        // a fresh copy is used without looking at the mirror
+        let fresh = Validators { fresh_until: Some(i64::MAX), ..validators };
+        let fetched = nist::fetch_conditional("bogus:", &fresh).unwrap();
+        assert_eq!(fetched, Fetched::NotModified(fresh.clone()));
+        assert!(!fresh.is_fresh_at(Unix(i64::MAX)));
+        // the server's numbers cannot overflow the freshness time
+        let max = super::max_age("public, max-age=9223372036854775807");
+        assert_eq!(max, Some(i64::MAX));
+        let now = Unix(1_700_000_000);
+        assert_eq!(super::fresh_until(now, i64::MAX, 0), i64::MAX);
+        assert_eq!(super::fresh_until(now, i64::MAX, -1), i64::MAX);
+        let stale = super::fresh_until(now, i64::MIN, 1);
+        assert_eq!(stale, i64::MIN + 1_700_000_000);
+        let text = "etag: \"abc\"\nlast-modified: 1\nfresh-until: 2\n";
+        let parsed = Validators::from_text(text);
+        assert_eq!(parsed.etag.as_deref(), Some("\"abc\""));
+        assert_eq!(parsed.to_string(), text);
+        assert_eq!(
+            Validators::from_text("junk\nfresh-until: x\n"),
+            Validators::default()
+        );
+
+        let cache = temp_path("conditional.list");
+        let store = nist::FileStore::new(cache.to_str().unwrap());
+        let saved = format!("{}.validators", store.path);
+        let _ = std::fs::remove_file(&store.path);
+        let fetched = nist::read_url_with(&store, &url).unwrap();
+        assert_eq!(list, fetched);
+        let note = std::fs::read_to_string(&saved).unwrap();
+        assert_eq!(note, "last-modified: 1700000001\n");
+        // the cache is used when the mirror is unchanged
+        std::fs::write(&mirror, "garbage").unwrap();
+        touch(1_700_000_000);
+        assert_eq!(list, nist::read_url_with(&store, &url).unwrap());
+        touch(1_700_000_002);
+        assert!(nist::read_url_with(&store, &url).is_err());
+        for file in
+            &[&store.path, &saved, &format!("{}.provenance", store.path)]
+        {
+            std::fs::remove_file(file).unwrap();
+        }
+        std::fs::remove_file(&mirror).unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "gzip")]
+    fn gzip() {
+        use flate2::{write::GzEncoder, Compression};
+        use std::io::Write;
+
+        let text = history!("999?");
+        let today = Gregorian(2021, 1, 1).mjd();
+        let original = LeapSecs::from_str_at(text, today).unwrap();
+        let printed = nist::format(&original).unwrap();
+        let mut gz = GzEncoder::new(Vec::new(), Compression::default());
+        gz.write_all(printed.as_bytes()).unwrap();
+        let name = temp_path("test.list.gz");
+        std::fs::write(&name, gz.finish().unwrap()).unwrap();
+        let parsed = nist::read_file(name.to_str().unwrap()).unwrap();
+        std::fs::remove_file(&name).unwrap();
+        assert_eq!(original, parsed);
+    }
+
+    #[test]
+    fn updater() {
+        use nist::{Source, Updater};
+
+        let base = temp_path("updater");
+        let path = |name: &str| format!("{}-{}.list", base.display(), name);
+        let prefix = "6+6+12+12+12+12+12+12+12+18+12+12+24+30+24+";
+        let mut lists = vec![];
+        for (name, gaps) in &[("old", "997?"), ("new", "998+6?")] {
+            let list = LeapSecs::from_str_lenient(&(prefix.to_string() + gaps));
+            let list = list.unwrap().with_updated(Gregorian(1990, 1, 1).mjd());
+            std::fs::write(path(name), nist::format(&list).unwrap()).unwrap();
+            lists.push(list);
+        }
+        let old = Source::File(path("old"));
+        let new = Source::File(path("new"));
+        let missing = Source::File(path("missing"));
+        let cache = nist::FileStore::new(&path("cache"));
+        let _ = std::fs::remove_file(&cache.path);
+
+        let now = Unix::from(Gregorian(2030, 1, 1));
+        let mut updater =
+            Updater::new(cache.clone(), std::slice::from_ref(&old));
+        assert_eq!(lists[0], *updater.poll(now).unwrap());
+        let due = Unix::from(lists[0].expires() - nist::NTPD_WARNING_DAYS);
+        assert_eq!(due, updater.next_poll());
+        updater.sources = vec![new.clone()];
+        assert_eq!(lists[0], *updater.poll(Unix(now.0 + 1)).unwrap());
+        assert_eq!(lists[1], *updater.poll(due).unwrap());
+
+        // the cache is fresh, and an older list is refused
+        let mut updater =
+            Updater::new(cache.clone(), std::slice::from_ref(&missing));
+        assert_eq!(lists[1], *updater.poll(now).unwrap());
+        assert_eq!(0, updater.failures());
+        updater.sources = vec![old];
+        let due = updater.next_poll();
+        assert_eq!(lists[1], *updater.poll(due).unwrap());
+        assert_eq!(1, updater.failures());
+        let expired = Unix::from(lists[1].expires() + 1);
+        assert!(updater.poll(expired).is_err());
+        assert_eq!(2, updater.failures());
+
+        // back off after failures
+        let store = nist::FileStore::new(&path("none"));
+        let mut updater = Updater::new(store, &[missing]);
+        assert!(updater.poll(now).is_err());
+        assert_eq!(Unix(now.0 + nist::RETRY_SECS), updater.next_poll());
+        assert!(updater.poll(updater.next_poll()).is_err());
+        let backoff = 3 * nist::RETRY_SECS;
+        assert_eq!(Unix(now.0 + backoff), updater.next_poll());
+        assert_eq!(2, updater.failures());
+        assert!(updater.list().is_none());
+
+        for name in &["old", "new", "cache"] {
+            let _ = std::fs::remove_file(path(name));
+        }
+        let _ = std::fs::remove_file(format!("{}.provenance", cache.path));
+    }
+
+    #[test]
+    fn read_best() {
+        use nist::Source;
+
+        let base = temp_path("best");
+        let path = |name: &str| format!("{}-{}.list", base.display(), name);
+        let mut sources = vec![];
+        for (name, text) in &[
+            ("old", "6+6+12+12+12+12+12+12+12+18+12+12+24+30+24+999?"),
+            ("new", "6+6+12+12+12+12+12+12+12+18+12+12+24+30+24+998+6?"),
+            ("same", "6+6+12+12+12+12+12+12+12+18+12+12+24+30+24+999?"),
+        ] {
+            let list = LeapSecs::from_str_lenient(text).unwrap();
+            let list = list.with_updated(Gregorian(1990, 1, 1).mjd());
+            std::fs::write(path(name), nist::format(&list).unwrap()).unwrap();
+            sources.push(Source::File(path(name)));
+        }
+        let missing = Source::File(path("missing"));
+        sources.insert(1, missing.clone());
+
+        let best = nist::read_best(&sources).unwrap();
+        assert_eq!(sources[2], best.source);
+        assert_eq!(1, best.failures.len());
+        assert_eq!(missing, best.failures[0].0);
+        let best = nist::read_best(&[sources[3].clone(), sources[0].clone()]);
+        assert_eq!(sources[3], best.unwrap().source);
+        let err = nist::read_best(&[missing]).unwrap_err();
+        assert!(err.to_string().contains(&path("missing")));
+
+        let insecure =
+            Source::Url("http://127.0.0.1:9/leap-seconds.list".into());
+        assert_eq!(Security::Insecure, insecure.security());
+        assert_eq!(Security::Tls, Source::Iers.security());
+        let policy = nist::Policy::default();
+        let best =
+            nist::read_best_with(&[insecure, sources[0].clone()], policy);
+        let best = best.unwrap();
+        assert_eq!(sources[0], best.source);
+        let refused = format!("{}", best.failures[0].1);
+        assert_eq!("refusing insecure transport", refused);
+
+        // expiry is checked with the given clock, not the wall clock
+        let early = LeapSecs::from_str_lenient("6+6+12?").unwrap();
+        let early = early.with_updated(Gregorian(1972, 7, 1).mjd());
+        let name = path("early");
+        std::fs::write(&name, nist::format(&early).unwrap()).unwrap();
+        let early = Source::File(name.clone());
+        let today = Gregorian(1973, 1, 1).mjd();
+        let early = std::slice::from_ref(&early);
+        let best = nist::read_best_at(early, policy, today);
+        assert_eq!(early[0], best.unwrap().source);
+        assert!(nist::read_best_with(early, policy).is_err());
+        let _ = std::fs::remove_file(name);
+        if let Source::File(name) = &sources[0] {
+            let url = format!("file://{}", name);
+            let list = nist::read_url(&url).unwrap();
+            let want = format!("NIST list from {} (local)", url);
+            assert_eq!(want, list.provenance().unwrap().to_string());
+        }
+        for source in &sources {
+            if let Source::File(name) = source {
+                let _ = std::fs::remove_file(name);
+            }
+        }
+    }
+
+    // This needs network access or a fresh leap-seconds.list in the
+    // cache, so set LEAPSECS_OFFLINE in the environment to skip it
+    // when neither is available.
+    #[test]
+    fn test() {
+        if std::env::var_os("LEAPSECS_OFFLINE").is_some() {
+            return;
+        }
+        let original = nist::read().expect("get leap-seconds.list");
+        let printed = nist::format(&original).expect("formatting leap seconds");
+        let parsed = nist::read_str(&printed).expect("re-parsing leap-seconds");
+        assert_eq!(original, parsed);
+    }
+}
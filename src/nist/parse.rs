@@ -11,15 +11,15 @@ use crate::date::*;
 type Result<'a, O> =
     nom::IResult<&'a str, O, nom::error::VerboseError<&'a str>>;
 
-fn decimal<T: std::str::FromStr>(input: &str) -> Result<T> {
+fn decimal<T: std::str::FromStr>(input: &str) -> Result<'_, T> {
     map_res(digit1, T::from_str)(input)
 }
 
-fn hexword(input: &str) -> Result<u32> {
+fn hexword(input: &str) -> Result<'_, u32> {
     preceded(space1, map_res(hex_digit1, |s| u32::from_str_radix(s, 16)))(input)
 }
 
-fn month(input: &str) -> Result<i32> {
+fn month(input: &str) -> Result<'_, i32> {
     alt((
         value(1, tag("Jan")),
         value(2, tag("Feb")),
@@ -36,7 +36,7 @@ fn month(input: &str) -> Result<i32> {
     ))(input)
 }
 
-fn date(input: &str) -> Result<Gregorian> {
+fn date(input: &str) -> Result<'_, Gregorian> {
     map(
         tuple((
             preceded(space1, decimal),
@@ -47,54 +47,115 @@ fn date(input: &str) -> Result<Gregorian> {
     )(input)
 }
 
-fn empty(input: &str) -> Result<()> {
-    value((), pair(tag("#"), line_ending))(input)
+// end of line, allowing trailing whitespace and a missing newline at
+// the end of the file
+fn eol(input: &str) -> Result<'_, ()> {
+    value((), preceded(space0, alt((line_ending, eof))))(input)
 }
 
-fn comment(input: &str) -> Result<()> {
-    value((), tuple((tag("#"), space1, not_line_ending, line_ending)))(input)
+// a line with nothing on it, which is only allowed at the end
+fn blank(input: &str) -> Result<'_, ()> {
+    value((), pair(space0, line_ending))(input)
 }
 
-fn ignore(input: &str) -> Result<()> {
-    value((), many0_count(alt((empty, comment))))(input)
+fn empty(input: &str) -> Result<'_, ()> {
+    value((), pair(tag("#"), eol))(input)
 }
 
-fn updated(input: &str) -> Result<i64> {
-    delimited(pair(tag("#$"), space1), decimal, line_ending)(input)
+fn comment(input: &str) -> Result<'_, ()> {
+    value((), tuple((tag("#"), space1, not_line_ending, eol)))(input)
 }
 
-fn expires(input: &str) -> Result<i64> {
-    delimited(pair(tag("#@"), space1), decimal, line_ending)(input)
+// a line like a directive that we do not know about, with the length
+// of the input at that point so that the caller can work out the
+// line number
+fn directive(input: &str) -> Result<'_, (usize, String)> {
+    let len = input.len();
+    let line = recognize(pair(tag("#"), none_of(" \t\r\n$@h")));
+    terminated(
+        map(recognize(pair(line, not_line_ending)), move |line: &str| {
+            (len, line.trim_end().to_string())
+        }),
+        eol,
+    )(input)
+}
+
+fn ignore(input: &str) -> Result<'_, Vec<(usize, String)>> {
+    map(
+        many0(alt((
+            value(None, empty),
+            value(None, comment),
+            map(directive, Some),
+        ))),
+        |lines| lines.into_iter().flatten().collect(),
+    )(input)
+}
+
+// mirrors sometimes add blank lines or comments after the hashes
+fn trailer(input: &str) -> Result<'_, Vec<(usize, String)>> {
+    terminated(
+        map(
+            many0(alt((
+                value(None, blank),
+                value(None, empty),
+                value(None, comment),
+                map(directive, Some),
+            ))),
+            |lines| lines.into_iter().flatten().collect(),
+        ),
+        pair(space0, eof),
+    )(input)
+}
+
+fn updated(input: &str) -> Result<'_, i64> {
+    delimited(pair(tag("#$"), space1), decimal, eol)(input)
+}
+
+fn expires(input: &str) -> Result<'_, i64> {
+    delimited(pair(tag("#@"), space1), decimal, eol)(input)
 }
 
-fn leapsecs(input: &str) -> Result<Vec<UncheckedLeap>> {
+fn leapsecs(input: &str) -> Result<'_, Vec<UncheckedLeap>> {
     many1(tuple((
         terminated(decimal, space1),
         terminated(decimal, space1),
-        delimited(tag("#"), date, line_ending),
+        delimited(tag("#"), date, eol),
     )))(input)
 }
 
-fn hash(input: &str) -> Result<Hash> {
-    let mut hash: Hash = Default::default();
+fn hash(input: &str) -> Result<'_, Hash> {
+    let mut words = [0; 5];
     let (rest, ()) =
-        delimited(tag("#h"), fill(hexword, &mut hash.0), line_ending)(input)?;
-    Ok((rest, hash))
+        delimited(tag("#h"), fill(hexword, &mut words), eol)(input)?;
+    Ok((rest, Hash::from_words(&words)))
 }
 
-pub(super) fn parse(input: &str) -> Result<UncheckedList> {
-    map(
-        tuple((
-            preceded(ignore, updated),
-            preceded(ignore, expires),
-            preceded(ignore, leapsecs),
-            preceded(ignore, hash),
-        )),
-        |(updated, expires, leapsecs, hash)| UncheckedList {
-            updated,
-            expires,
-            leapsecs,
-            hash,
-        },
-    )(input)
+fn hash256(input: &str) -> Result<'_, Hash> {
+    let mut words = [0; 8];
+    let (rest, ()) = delimited(
+        tuple((tag("#"), space1, tag("sha256"))),
+        fill(hexword, &mut words),
+        eol,
+    )(input)?;
+    Ok((rest, Hash::from_words(&words)))
+}
+
+pub(super) fn parse(input: &str) -> Result<'_, UncheckedList> {
+    let (input, mut directives) = ignore(input)?;
+    let (input, updated) = updated(input)?;
+    let (input, more) = ignore(input)?;
+    directives.extend(more);
+    let (input, expires) = expires(input)?;
+    let (input, more) = ignore(input)?;
+    directives.extend(more);
+    let (input, leapsecs) = leapsecs(input)?;
+    let (input, more) = ignore(input)?;
+    directives.extend(more);
+    let (input, hash) = hash(input)?;
+    let (input, hash256) = opt(hash256)(input)?;
+    let (input, more) = trailer(input)?;
+    directives.extend(more);
+    let list =
+        UncheckedList { updated, expires, leapsecs, hash, hash256, directives };
+    Ok((input, list))
 }
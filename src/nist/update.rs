@@ -0,0 +1,163 @@
+use std::convert::TryFrom;
+
+use super::{format, read_best_at, read_bytes_at};
+use super::{FileStore, Policy, Source, Store, NTPD_WARNING_DAYS};
+use crate::*;
+
+// how often the Updater looks for a new list when the current one is
+// close to expiring, in seconds
+pub const REFRESH_SECS: i64 = 86400;
+
+// how long the Updater waits after its first failure to fetch a list,
+// in seconds; it doubles after each failure, up to REFRESH_SECS
+pub const RETRY_SECS: i64 = 300;
+
+// Keep a valid leap second list for a long-running daemon, cached in a
+// Store and refreshed from a few Sources with read_best_at().
+//
+// Call poll() whenever it is convenient, or at next_poll(). It loads
+// the cache the first time, and fetches a new list when the cached one
+// is within NTPD_WARNING_DAYS of expiring, at most once every
+// REFRESH_SECS, and backing off after failures. A new list is only
+// installed if it extends the current one, so a corrupt or replayed
+// list is never installed.
+#[derive(Debug)]
+pub struct Updater<S: Store = FileStore> {
+    pub store: S,
+    pub sources: Vec<Source>,
+    pub policy: Policy,
+    list: Option<LeapSecs>,
+    // POSIX time of the next refresh
+    next: i64,
+    failures: u32,
+}
+
+impl<S: Store> Updater<S> {
+    pub fn new(store: S, sources: &[Source]) -> Updater<S> {
+        Updater {
+            store,
+            sources: sources.to_vec(),
+            policy: Policy::default(),
+            list: None,
+            next: i64::MIN,
+            failures: 0,
+        }
+    }
+
+    // the current list, which might have expired if refreshes failed
+    pub fn list(&self) -> Option<&LeapSecs> {
+        self.list.as_ref()
+    }
+
+    // when poll() will next try to refresh the list
+    pub fn next_poll(&self) -> Unix {
+        Unix(self.next)
+    }
+
+    // failed refreshes since the last successful one
+    pub fn failures(&self) -> u32 {
+        self.failures
+    }
+
+    // Get the current list, refreshing it if it is due. A failed
+    // refresh is only an error if there is no list that is still
+    // valid `now`.
+    pub fn poll(&mut self, now: Unix) -> anyhow::Result<&LeapSecs> {
+        let today = MJD::try_from(now)?;
+        if self.list.is_none() {
+            self.load(now, today);
+        }
+        let mut error = None;
+        if now.0 >= self.next {
+            match self.refresh(today) {
+                Ok(()) => {
+                    self.failures = 0;
+                    self.next = self.schedule(now);
+                }
+                Err(err) => {
+                    let shift = self.failures.min(16);
+                    self.failures += 1;
+                    let delay = (RETRY_SECS << shift).min(REFRESH_SECS);
+                    self.next = now.0 + delay;
+                    event!(
+                        warn,
+                        "refresh failed, retry in {}s: {:#}",
+                        delay,
+                        err
+                    );
+                    error = Some(err);
+                }
+            }
+        }
+        match &self.list {
+            Some(list) if !list.is_expired_at(today) => Ok(list),
+            Some(list) => Err(error.unwrap_or_else(|| {
+                let expired = Gregorian::from(list.expires());
+                event!(warn, "leap seconds list expired on {}", expired);
+                anyhow::anyhow!("leap seconds list expired {}", expired)
+            })),
+            None => Err(error.unwrap_or_else(|| {
+                anyhow::anyhow!("no valid leap seconds list")
+            })),
+        }
+    }
+
+    // a cached list that is missing, corrupt, or expired is ignored
+    fn load(&mut self, now: Unix, today: MJD) {
+        let data = match self.store.load() {
+            Ok(Some(data)) => data,
+            _ => return,
+        };
+        match read_bytes_at(&data, today) {
+            Ok(list) => {
+                let provenance = self.store.provenance();
+                event!(debug, "using cached {}", provenance);
+                self.list = Some(list.with_provenance(provenance));
+                self.next = self.schedule(now);
+            }
+            Err(err) => event!(info, "ignoring cached list: {}", err),
+        }
+    }
+
+    fn refresh(&mut self, today: MJD) -> anyhow::Result<()> {
+        let best = read_best_at(&self.sources, self.policy, today)?;
+        if best.list.is_expired_at(today) {
+            anyhow::bail!("no unexpired list from {}", best.source);
+        }
+        if let Some(current) = &self.list {
+            // fails if the list is corrupt or replayed
+            best.list.verify_history(current)?;
+            if best.list.expires() <= current.expires() {
+                event!(debug, "no newer list from {}", best.source);
+                return Ok(());
+            }
+        }
+        let expires = Gregorian::from(best.list.expires());
+        event!(
+            info,
+            "refreshed list from {}, expires {}",
+            best.source,
+            expires
+        );
+        self.store.store(format(&best.list)?.as_bytes())?;
+        if let Some(provenance) = best.list.provenance() {
+            self.store.store_provenance(provenance)?;
+        }
+        self.list = Some(best.list);
+        Ok(())
+    }
+
+    // there cannot be a leap second that the current list does not
+    // know about until after it expires, so there is no need to look
+    // for a new list until it is close to expiring
+    fn schedule(&self, now: Unix) -> i64 {
+        let soon = now.0 + REFRESH_SECS;
+        match &self.list {
+            Some(list) => {
+                let warning = list.expires() - NTPD_WARNING_DAYS;
+                soon.max(Unix::from(warning).0)
+            }
+            None => soon,
+        }
+    }
+}
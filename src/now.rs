@@ -0,0 +1,126 @@
+//! The current time in TAI
+//! =======================
+//!
+//! [`LeapSecs::now_tai()`][] and [`LeapSecs::now_utc_labeled()`][]
+//! read the system clock and use the list to work out TAI, for
+//! example,
+//!
+//!     # use leapsecs::*;
+//!     # use std::str::FromStr;
+//!     # let list = LeapSecs::from_str("6+6+999?")?;
+//!     let tai = list.now_tai()?;
+//!     let utc = list.now_utc_labeled()?;
+//!     assert!(tai >= utc.posix + i64::from(utc.dtai));
+//!     println!("{}", utc);
+//!     # Ok::<(), Error>(())
+//!
+//! These functions assume that the system clock is POSIX time, which
+//! counts seconds since 1970 as if every day had 86400 seconds. The
+//! clock has to repeat a second or smear time around a leap second,
+//! so TAI can be out by up to a second at those times.
+//!
+//! This requires the `std` feature.
+
+use crate::*;
+use std::time::SystemTime;
+
+/// A UTC date and time labeled with the difference between UTC and
+/// TAI, from [`LeapSecs::now_utc_labeled()`][].
+///
+/// It is displayed in ISO 8601 format with a `dtai` label, like
+///
+/// ```text
+/// 2021-01-07T12:34:56.789Z dtai=37
+/// ```
+///
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct LabeledUtc {
+    /// The POSIX timestamp
+    pub posix: i64,
+    /// Nanoseconds after the POSIX timestamp
+    pub nanos: u32,
+    /// The UTC date
+    pub date: Gregorian,
+    /// The difference between UTC and TAI on this date
+    pub dtai: i16,
+}
+
+impl LabeledUtc {
+    /// Get the time in TAI seconds since 1970, not counting the
+    /// nanoseconds
+    pub fn tai(&self) -> i64 {
+        self.posix + i64::from(self.dtai)
+    }
+}
+
+impl core::fmt::Display for LabeledUtc {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let secs = self.posix.rem_euclid(86400);
+        let (h, m, s) = (secs / 3600, secs / 60 % 60, secs % 60);
+        let ms = self.nanos / 1_000_000;
+        write!(f, "{}T{:02}:{:02}:{:02}.{:03}Z", self.date, h, m, s, ms)?;
+        write!(f, " dtai={}", self.dtai)
+    }
+}
+
+impl LeapSecs {
+    // label a POSIX time, checking that the list covers it
+    fn label(&self, posix: i64, nanos: u32) -> Result<LabeledUtc> {
+        let date = Gregorian::try_from(Unix(posix))?;
+        let dtai = self.dtai_at(date)?;
+        Ok(LabeledUtc { posix, nanos, date, dtai })
+    }
+
+    /// Get the current time in TAI seconds since 1970.
+    ///
+    /// Fails with [`Error::Uncovered`][] if the list has expired.
+    ///
+    pub fn now_tai(&self) -> Result<i64> {
+        Ok(self.now_utc_labeled()?.tai())
+    }
+
+    /// Get the current UTC date and time, labeled with the difference
+    /// between UTC and TAI.
+    ///
+    /// Fails with [`Error::Uncovered`][] if the list has expired.
+    ///
+    pub fn now_utc_labeled(&self) -> Result<LabeledUtc> {
+        let now = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH);
+        // panic if we are in a tardis, like MJD::today()
+        let now = now.unwrap();
+        self.label(i64::try_from(now.as_secs())?, now.subsec_nanos())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test() {
+        let text = history!("59?");
+        let list = LeapSecs::from_str_lenient(text).unwrap();
+        let label = list.label(1_483_228_799, 999_000_000).unwrap();
+        let want = "2016-12-31T23:59:59.999Z dtai=36";
+        assert_eq!(want, label.to_string());
+        let label = list.label(1_483_228_800, 0).unwrap();
+        assert_eq!(1_483_228_837, label.tai());
+        let expires = Unix::from(list.expires()).0;
+        assert_eq!(37, list.label(expires + 86399, 0).unwrap().dtai);
+        match list.label(expires + 86400, 0) {
+            Err(Error::Uncovered(coverage)) => assert!(coverage.is_expired()),
+            other => panic!("expected Uncovered, got {:?}", other),
+        }
+        match list.label(0, 0) {
+            Err(Error::Uncovered(coverage)) => assert!(!coverage.is_expired()),
+            other => panic!("expected Uncovered, got {:?}", other),
+        }
+        assert!(list.now_tai().is_err());
+
+        let list = LeapSecs::from_str(&text.replace("59?", "999?")).unwrap();
+        let utc = list.now_utc_labeled().unwrap();
+        assert_eq!(37, utc.dtai);
+        assert!(list.now_tai().unwrap() >= utc.tai());
+    }
+}
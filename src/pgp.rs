@@ -0,0 +1,732 @@
+//! OpenPGP signatures on the NIST leap seconds list
+//! ================================================
+//!
+//! The `leap-seconds.list` file can be published with a detached
+//! OpenPGP signature in a `.asc` file alongside it. This module
+//! verifies such a signature against one or more public keys that
+//! the caller trusts, using [`verify()`][]. The
+//! [`nist::read_verified()`][crate::nist::read_verified] function
+//! fetches a list and its signature and checks them together, with
+//! the `fetch` feature.
+//!
+//! This is a small subset of OpenPGP (RFC 4880), enough to check
+//! version 4 signatures made by RSA or Ed25519 keys with SHA-2, or
+//! with SHA-1 and short RSA keys if [`verify_with()`][] allows them.
+//! Keys and signatures can be binary or ASCII-armored. There is no
+//! web of trust: a signature is good if it verifies with any of the
+//! given keys or their bound subkeys.
+//!
+//! Key expiry and revocation are not checked, so a key that has
+//! expired or been revoked is still trusted. Callers must remove
+//! such keys from the list they pass in.
+//!
+//! Requires the `pgp` feature.
+
+use ring::digest;
+use ring::signature::{self, RsaPublicKeyComponents, UnparsedPublicKey};
+
+use crate::*;
+
+// packet tags
+const SIGNATURE: u8 = 2;
+const PUBLIC_KEY: u8 = 6;
+const PUBLIC_SUBKEY: u8 = 14;
+
+// public key algorithms
+const RSA: u8 = 1;
+const RSA_SIGN: u8 = 3;
+const EDDSA: u8 = 22;
+
+// the curve OID for Ed25519 in EdDSA keys
+const ED25519_OID: &[u8] =
+    &[0x2B, 0x06, 0x01, 0x04, 0x01, 0xDA, 0x47, 0x0F, 0x01];
+
+// signature types
+const BINARY: u8 = 0x00;
+const TEXT: u8 = 0x01;
+const SUBKEY_BINDING: u8 = 0x18;
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8]> {
+        if len > self.0.len() {
+            return Err(Error::Pgp("short"));
+        }
+        let (data, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(data)
+    }
+
+    fn byte(&mut self) -> Result<u8> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<usize> {
+        Ok((self.byte()? as usize) << 8 | self.byte()? as usize)
+    }
+
+    fn u32(&mut self) -> Result<usize> {
+        Ok(self.u16()? << 16 | self.u16()?)
+    }
+
+    // a multiprecision integer, without its leading zeros
+    fn mpi(&mut self) -> Result<&'a [u8]> {
+        let bits = self.u16()?;
+        self.bytes(bits.div_ceil(8))
+    }
+
+    // a packet's tag and body
+    fn packet(&mut self) -> Result<(u8, &'a [u8])> {
+        let head = self.byte()?;
+        if head & 0x80 == 0 {
+            return Err(Error::Pgp("not a packet"));
+        }
+        if head & 0x40 != 0 {
+            let len = match self.byte()? {
+                len @ 0..=191 => len as usize,
+                len @ 192..=223 => {
+                    ((len as usize - 192) << 8) + self.byte()? as usize + 192
+                }
+                255 => self.u32()?,
+                _ => return Err(Error::Pgp("unsupported partial length")),
+            };
+            Ok((head & 0x3F, self.bytes(len)?))
+        } else {
+            let len = match head & 3 {
+                0 => self.byte()? as usize,
+                1 => self.u16()?,
+                2 => self.u32()?,
+                _ => self.0.len(),
+            };
+            Ok((head >> 2 & 0x0F, self.bytes(len)?))
+        }
+    }
+}
+
+// left-pad a big-endian number to a fixed size
+fn pad(mpi: &[u8], len: usize) -> Result<Vec<u8>> {
+    if mpi.len() > len {
+        return Err(Error::Pgp("number too large"));
+    }
+    let mut out = vec![0; len - mpi.len()];
+    out.extend_from_slice(mpi);
+    Ok(out)
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Key {
+    Rsa { n: Vec<u8>, e: Vec<u8> },
+    Ed25519([u8; 32]),
+}
+
+impl Key {
+    // returns None for keys of other kinds, which are skipped
+    fn parse(body: &[u8]) -> Result<Option<Key>> {
+        let mut input = Reader(body);
+        if input.byte()? != 4 {
+            return Ok(None);
+        }
+        let _created = input.u32()?;
+        match input.byte()? {
+            RSA | RSA_SIGN => {
+                let n = input.mpi()?.to_vec();
+                let e = input.mpi()?.to_vec();
+                Ok(Some(Key::Rsa { n, e }))
+            }
+            EDDSA => {
+                let len = input.byte()? as usize;
+                if input.bytes(len)? != ED25519_OID {
+                    return Ok(None);
+                }
+                let point = input.mpi()?;
+                match point.split_first() {
+                    Some((0x40, key)) if key.len() == 32 => {
+                        let mut out = [0; 32];
+                        out.copy_from_slice(key);
+                        Ok(Some(Key::Ed25519(out)))
+                    }
+                    _ => Err(Error::Pgp("bad Ed25519 key")),
+                }
+            }
+            _ => Ok(None),
+        }
+    }
+}
+
+/// An OpenPGP public key, including its subkeys.
+///
+/// A subkey is only trusted if it has a subkey binding signature
+/// made by the primary key, so that extra subkeys appended to a
+/// copy of the key cannot sign anything. Binding signatures must
+/// use SHA-2, whatever the [`VerifyOptions`][].
+///
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PublicKey {
+    keys: Vec<Key>,
+}
+
+impl PublicKey {
+    /// Parse a binary OpenPGP public key, as exported by
+    /// `gpg --export`.
+    ///
+    /// Returns [`Error::Pgp`][] if it is malformed or has no RSA or
+    /// Ed25519 keys.
+    ///
+    pub fn from_bytes(bytes: &[u8]) -> Result<PublicKey> {
+        let mut input = Reader(bytes);
+        let mut keys = vec![];
+        // the packet bodies of the current primary key and of the
+        // subkey that is waiting for its binding signature
+        let mut primary: Option<(&[u8], Key)> = None;
+        let mut subkey: Option<(&[u8], Key)> = None;
+        while !input.0.is_empty() {
+            match input.packet()? {
+                (PUBLIC_KEY, body) => {
+                    primary = Key::parse(body)?.map(|key| (body, key));
+                    keys.extend(primary.as_ref().map(|(_, key)| key.clone()));
+                    subkey = None;
+                }
+                (PUBLIC_SUBKEY, body) => {
+                    subkey = Key::parse(body)?.map(|key| (body, key));
+                }
+                (SIGNATURE, sig) => {
+                    if let (Some(primary), Some((body, key))) =
+                        (&primary, &subkey)
+                    {
+                        if is_binding(primary, body, sig) {
+                            keys.push(key.clone());
+                            subkey = None;
+                        }
+                    }
+                }
+                _ => (),
+            }
+        }
+        if keys.is_empty() {
+            return Err(Error::Pgp("no usable keys"));
+        }
+        Ok(PublicKey { keys })
+    }
+
+    /// Parse an ASCII-armored OpenPGP public key, as exported by
+    /// `gpg --export --armor`.
+    ///
+    pub fn from_armor(text: &str) -> Result<PublicKey> {
+        PublicKey::from_bytes(&dearmor(text)?)
+    }
+}
+
+// a key packet as it is hashed into a signature over the key
+fn key_prefix(message: &mut Vec<u8>, body: &[u8]) {
+    message.push(0x99);
+    message.extend_from_slice(&(body.len() as u16).to_be_bytes());
+    message.extend_from_slice(body);
+}
+
+// check that `sig` is a good subkey binding signature for the subkey
+// with packet `body`, made by the `primary` key; malformed or
+// unsupported signatures just fail to bind the subkey
+fn is_binding(primary: &(&[u8], Key), body: &[u8], sig: &[u8]) -> bool {
+    let (primary_body, primary_key) = primary;
+    let sig = match Signature::parse(sig) {
+        Ok(sig) if sig.kind == SUBKEY_BINDING => sig,
+        _ => return false,
+    };
+    let mut message = vec![];
+    key_prefix(&mut message, primary_body);
+    key_prefix(&mut message, body);
+    let keys = core::iter::once(primary_key);
+    let options = VerifyOptions::default();
+    sig.verify(message, keys, &options).unwrap_or(false)
+}
+
+// CRC-24 from RFC 4880 section 6.1
+fn crc24(bytes: &[u8]) -> u32 {
+    let mut crc = 0xB704CE;
+    for &byte in bytes {
+        crc ^= (byte as u32) << 16;
+        for _ in 0..8 {
+            crc <<= 1;
+            if crc & 0x1000000 != 0 {
+                crc ^= 0x1864CFB;
+            }
+        }
+    }
+    crc & 0xFFFFFF
+}
+
+// remove ASCII armor, checking the CRC if there is one
+fn dearmor(text: &str) -> Result<Vec<u8>> {
+    let mut lines = text
+        .lines()
+        .map(str::trim_end)
+        .skip_while(|line| !line.starts_with("-----BEGIN PGP "));
+    if lines.next().is_none() {
+        return Err(Error::Pgp("armor not found"));
+    }
+    // skip the armor headers
+    let mut lines = lines.skip_while(|line| !line.is_empty()).skip(1);
+    let mut base64 = String::new();
+    let mut crc = None;
+    for line in &mut lines {
+        if line.starts_with("-----END PGP ") {
+            let bytes = dns::base64_decode(&base64)?;
+            return match crc {
+                Some(crc) if crc != crc24(&bytes) => Err(Error::Pgp("bad CRC")),
+                _ => Ok(bytes),
+            };
+        } else if let Some(sum) = line.strip_prefix('=') {
+            let sum = dns::base64_decode(sum)?;
+            crc = Some(sum.iter().fold(0, |crc, &b| crc << 8 | b as u32));
+        } else {
+            base64.push_str(line);
+        }
+    }
+    Err(Error::Pgp("armor not terminated"))
+}
+
+// a text signature is over the data with CRLF line endings
+fn canonical_text(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len());
+    let mut prev = 0;
+    for &byte in data {
+        if byte == b'\n' && prev != b'\r' {
+            out.push(b'\r');
+        }
+        out.push(byte);
+        prev = byte;
+    }
+    out
+}
+
+/// Which weak algorithms [`verify_with()`][] accepts.
+///
+/// By default, signatures using SHA-1, which has practical collision
+/// attacks, fail with [`Error::Pgp`][], and RSA keys shorter than
+/// 2048 bits cannot make good signatures. These options allow them
+/// for old keys that cannot be replaced.
+///
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct VerifyOptions {
+    /// Allow signatures that use SHA-1.
+    pub sha1: bool,
+    /// Allow RSA keys from 1024 to 2047 bits.
+    pub short_rsa: bool,
+}
+
+/// Check a detached OpenPGP signature on some `data` against a
+/// list of trusted `keys`, rejecting weak algorithms as described
+/// under [`VerifyOptions`][].
+///
+/// The `signature` can be binary or ASCII-armored. If it contains
+/// more than one signature packet, it is good if any of them is.
+///
+/// Returns [`Error::Pgp`][] if the signature is malformed or uses an
+/// unsupported algorithm, or [`Error::Signature`][] if it does not
+/// verify with any of the keys.
+///
+pub fn verify(data: &[u8], signature: &[u8], keys: &[PublicKey]) -> Result<()> {
+    verify_with(data, signature, keys, &VerifyOptions::default())
+}
+
+/// Check a detached OpenPGP signature like [`verify()`][], with
+/// `options` that allow weak algorithms.
+///
+pub fn verify_with(
+    data: &[u8],
+    signature: &[u8],
+    keys: &[PublicKey],
+    options: &VerifyOptions,
+) -> Result<()> {
+    let armored;
+    let mut input = match core::str::from_utf8(signature) {
+        Ok(text) if text.contains("-----BEGIN PGP SIGNATURE-----") => {
+            armored = dearmor(text)?;
+            Reader(&armored)
+        }
+        _ => Reader(signature),
+    };
+    let mut found = false;
+    while !input.0.is_empty() {
+        if let (SIGNATURE, body) = input.packet()? {
+            found = true;
+            let sig = Signature::parse(body)?;
+            let message = match sig.kind {
+                BINARY => data.to_vec(),
+                TEXT => canonical_text(data),
+                _ => return Err(Error::Pgp("unsupported signature type")),
+            };
+            let keys = keys.iter().flat_map(|key| &key.keys);
+            if sig.verify(message, keys, options)? {
+                return Ok(());
+            }
+        }
+    }
+    if found {
+        Err(Error::Signature)
+    } else {
+        Err(Error::Pgp("no signature"))
+    }
+}
+
+// a version 4 signature packet
+struct Signature<'a> {
+    kind: u8,
+    algorithm: u8,
+    hash: u8,
+    // the hashed part of the packet
+    trailer: &'a [u8],
+    // the signature values
+    values: Reader<'a>,
+}
+
+impl<'a> Signature<'a> {
+    fn parse(body: &'a [u8]) -> Result<Signature<'a>> {
+        let mut input = Reader(body);
+        if input.byte()? != 4 {
+            return Err(Error::Pgp("unsupported signature version"));
+        }
+        let kind = input.byte()?;
+        let algorithm = input.byte()?;
+        let hash = input.byte()?;
+        let hashed = input.u16()?;
+        input.bytes(hashed)?;
+        let trailer = &body[..6 + hashed];
+        let unhashed = input.u16()?;
+        input.bytes(unhashed)?;
+        let _left16 = input.u16()?;
+        Ok(Signature { kind, algorithm, hash, trailer, values: input })
+    }
+
+    // check the signature over `message` with any of the `keys`
+    fn verify<'k, I>(
+        mut self,
+        mut message: Vec<u8>,
+        mut keys: I,
+        options: &VerifyOptions,
+    ) -> Result<bool>
+    where
+        I: Iterator<Item = &'k Key>,
+    {
+        message.extend_from_slice(self.trailer);
+        message.extend_from_slice(&[4, 0xFF]);
+        message.extend_from_slice(&(self.trailer.len() as u32).to_be_bytes());
+
+        let short = options.short_rsa;
+        let (digest, rsa): (_, &signature::RsaParameters) = match self.hash {
+            2 if !options.sha1 => {
+                return Err(Error::Pgp("SHA-1 signatures are not allowed"))
+            }
+            2 if short => (
+                &digest::SHA1_FOR_LEGACY_USE_ONLY,
+                &signature::RSA_PKCS1_1024_8192_SHA1_FOR_LEGACY_USE_ONLY,
+            ),
+            2 => (
+                &digest::SHA1_FOR_LEGACY_USE_ONLY,
+                &signature::RSA_PKCS1_2048_8192_SHA1_FOR_LEGACY_USE_ONLY,
+            ),
+            8 if short => (
+                &digest::SHA256,
+                &signature::RSA_PKCS1_1024_8192_SHA256_FOR_LEGACY_USE_ONLY,
+            ),
+            8 => (&digest::SHA256, &signature::RSA_PKCS1_2048_8192_SHA256),
+            9 => (&digest::SHA384, &signature::RSA_PKCS1_2048_8192_SHA384),
+            10 if short => (
+                &digest::SHA512,
+                &signature::RSA_PKCS1_1024_8192_SHA512_FOR_LEGACY_USE_ONLY,
+            ),
+            10 => (&digest::SHA512, &signature::RSA_PKCS1_2048_8192_SHA512),
+            _ => return Err(Error::Pgp("unsupported hash algorithm")),
+        };
+
+        let input = &mut self.values;
+        match self.algorithm {
+            RSA | RSA_SIGN => {
+                let s = input.mpi()?;
+                Ok(keys
+                    .filter_map(|key| match key {
+                        Key::Rsa { n, e } => Some((n, e)),
+                        _ => None,
+                    })
+                    .any(|(n, e)| match pad(s, n.len()) {
+                        Ok(s) => RsaPublicKeyComponents { n, e }
+                            .verify(rsa, &message, &s)
+                            .is_ok(),
+                        Err(_) => false,
+                    }))
+            }
+            EDDSA => {
+                let mut sig = pad(input.mpi()?, 32)?;
+                sig.extend(pad(input.mpi()?, 32)?);
+                // EdDSA signs the digest rather than the message
+                let digest = digest::digest(digest, &message);
+                Ok(keys.any(|key| match key {
+                    Key::Ed25519(public) => {
+                        UnparsedPublicKey::new(&signature::ED25519, public)
+                            .verify(digest.as_ref(), &sig)
+                            .is_ok()
+                    }
+                    _ => false,
+                }))
+            }
+            _ => Err(Error::Pgp("unsupported public key algorithm")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+
+    const ED25519_KEY: &str = "
+-----BEGIN PGP PUBLIC KEY BLOCK-----
+
+mDMEatLtDhYJKwYBBAHaRw8BAQdAEPwKvyjHRxixXA2V4usV2G46vcXIbTYgmtFZ
+F3KCQIC0HVRlc3QgRWQyNTUxOSA8ZWRAZXhhbXBsZS5jb20+iJAEExYIADgWIQRu
+rmqQoUDbDE4JB49qV4bKdrWfngUCatLtDgIbAwULCQgHAgYVCgkICwIEFgIDAQIe
+AQIXgAAKCRBqV4bKdrWfnnxFAP4gKfDgTZ0zC5jJ8ULi3nBL2eurJGghDos6t/T5
+ROg0EQD/cf+ZHG/zHsHE99tVSE39RvVKlMlrD+IvEYYK0IT0ZgY=
+=0REl
+-----END PGP PUBLIC KEY BLOCK-----
+";
+
+    const RSA_KEY: &str = "
+-----BEGIN PGP PUBLIC KEY BLOCK-----
+
+mQENBGrS7Q4BCACh5h1cekKSJmrEaySsGgajnETSayTk4eVMMDwFnYdj1WK7yG3B
+YDOqePwQYy4PTMO4lA3eQxqYgjAIGLFDgStaIWPhHh2i8486QAcHMaNANtAn64v8
+pAgNP0hqm5XTSptHQ2ukzhCQJPLjy/QOUxkVJ0OPDbJoIuWZvlmNavjMYx3XNW0t
+idLnkyiIMkzQX6rXN+o8qC3lhB2JYoujN5y0W2UOjihycFsAjOsJRAXtVBcfmxsQ
+jtzoFD/MYIBcnvpiHJh8ENhTMv/MrPM62AUExyZvtcPGJNX6PvtbySp37RPx6IW4
+TzXvAwLkXDqS5KtK050IbQa/3Dd3GteIc1mrABEBAAG0GlRlc3QgUlNBIDxyc2FA
+ZXhhbXBsZS5jb20+iQFOBBMBCgA4FiEEhY/NslE2yb9O8wyPhXg+1zOpAzkFAmrS
+7Q4CGwMFCwkIBwIGFQoJCAsCBBYCAwECHgECF4AACgkQhXg+1zOpAznLtgf5AeMq
+uTrLNNlRxItOB9M882NwAjeWvckoOBrdgifNlRi50bz/x1xxaueo80yQfhWbYiil
+EySlLMhagtINAfD0WnzFCOlFaeb8ilDbPmAesPk4v7BeEVbq2rl6L+zr1b5FbPlP
+u6a7cN5xgI3VXQ77EK69A0bh1mXNTH286iwjp9VeSR8cpkORz0JjurVulSfSojeF
+tpMtZVk9Muc8BafRUfO5Qgzck+578DVF7r34mA7Ag+F0It2V/3GYdNggYzWgib7m
+XQq5woSkB5G40BxL8XUCthRjU1FUjOMiAU34vbesrVx27P7LzC8p6fT7hWNM751I
+mxYzKIOJ2WUZ0Snbgw==
+=236s
+-----END PGP PUBLIC KEY BLOCK-----
+";
+
+    // binary signature by the Ed25519 key
+    const ED25519_SIG: &str = "
+-----BEGIN PGP SIGNATURE-----
+
+iIUEABYIAC0WIQRurmqQoUDbDE4JB49qV4bKdrWfngUCatLtHQ8cZWRAZXhhbXBs
+ZS5jb20ACgkQaleGyna1n55a4gEAnu0lzXsJPLSvL4CtEg2Jff7NzE9IBQxS0xBe
+u7orvTwA/16fTiwO+yUra/3WCkB9VfWwnd+SN3lslJZVRTsRZ/gO
+=Iu3N
+-----END PGP SIGNATURE-----
+";
+
+    // text signature by the RSA key
+    const RSA_SIG: &str = "
+-----BEGIN PGP SIGNATURE-----
+
+iQFEBAEBCAAuFiEEhY/NslE2yb9O8wyPhXg+1zOpAzkFAmrS7R0QHHJzYUBleGFt
+cGxlLmNvbQAKCRCFeD7XM6kDOSjdCACfNuWvGIksaM9kTWql+vKZ1/ylIdeiqX+e
+Ozrl1RYqzFmJwhii5ZWnRGr3z3oGdZ8ITDwHrfW59qUXnhy8tErU6uXhWPAb73y8
+HgEWKoophfIznWmVIj7Vd+ios+d6qf1beueVCOe167GrAPQuX7j1JJrQmMpIDR8b
+ZmYqcezppjxTj550BUfONJR1y6NR46LLWKpiQNOUVnuWMK+vfFnD3UtnbjWLMZTk
+lmHJydMaPRftEgjDI9X9tCBYr65cviM6isoCjiCxFNhyFMrN17KLYSsVsDMlFGME
+Il+xnKMs1i++7txH5cdtRhRsG0SBFlRW3h/mGZIBN7LFgCeGRv50
+=vNO7
+-----END PGP SIGNATURE-----
+";
+
+    // a certification-only Ed25519 key with an Ed25519 signing subkey
+    const SUBKEY_KEY: &str = "
+-----BEGIN PGP PUBLIC KEY BLOCK-----
+
+mDMEatMlSBYJKwYBBAHaRw8BAQdAYsWZBdlKKqK/bA6tqVU6KHzkfWyygp5p53QF
+PHZkg2y0HVRlc3QgU3Via2V5IDxzdWJAZXhhbXBsZS5jb20+iJAEExYIADgWIQT/
+NQet88phNq/dGjF5/H+Dkm2DEAUCatMlSAIbAQULCQgHAgYVCgkICwIEFgIDAQIe
+AQIXgAAKCRB5/H+Dkm2DEJL6AP4ywT4MXB+uF5jHNktkeznIwyv6dd3M1J/pZnjI
+5pUF1AD/cQMrkjaayk3VNF7rX2B9TZCoeloAcvDD4+r2r1JGuAK4MwRq0yVIFgkr
+BgEEAdpHDwEBB0A7k4zwkEglC2DMln72nTFxQDbYF6im6WD0cZIfO78q74jvBBgW
+CAAgFiEE/zUHrfPKYTav3Roxefx/g5JtgxAFAmrTJUgCGwIAgQkQefx/g5JtgxB2
+IAQZFggAHRYhBNjHd9nLMrBlo+ZGanNJu2u06v4ZBQJq0yVIAAoJEHNJu2u06v4Z
+7fMA/ibu1BjW1Pwgy7ebN13OVAsrps5Ysb7+DkKGJeROCck7AQDkz6+qzIfXOhLd
+wFei19CWuR/hQQLDMPa55UgOfuXaB+6/AP4wFuVVf6KoLKYLvcJ7+TIfji8KkM4s
+cTnjL6UeBzc23QEA4QgwRpGf2wTl8j3v5vYqWUOPJN0y1qf6u/2QC2eN1QU=
+=oD8U
+-----END PGP PUBLIC KEY BLOCK-----
+";
+
+    // signature by the signing subkey
+    const SUBKEY_SIG: &str = "
+-----BEGIN PGP SIGNATURE-----
+
+iIYEABYIAC4WIQTYx3fZyzKwZaPmRmpzSbtrtOr+GQUCatMlSxAcc3ViQGV4YW1w
+bGUuY29tAAoJEHNJu2u06v4ZKkAA/iJJKJyay4VyubLKxppeY4f1mRj+64sdGSnQ
+D24OGK0QAQCLpVUa6lVqwROTYZFcEnQsUrTejXMX980fyK+efN+NDA==
+=Am92
+-----END PGP SIGNATURE-----
+";
+
+    // signature by the signing subkey using SHA-1
+    const SHA1_SIG: &str = "
+-----BEGIN PGP SIGNATURE-----
+
+iIYEABYCAC4WIQTYx3fZyzKwZaPmRmpzSbtrtOr+GQUCatMlThAcc3ViQGV4YW1w
+bGUuY29tAAoJEHNJu2u06v4Zc6oA/ieGDAgE0D6cOMA5J4ceBwQ6bLeut12VOj0e
+nCxPSsgFAP9cuHRV/NoK2DIlt6TIy8PiHESzY5rmNEQmGzgQae48AQ==
+=xhCr
+-----END PGP SIGNATURE-----
+";
+
+    // a 1024-bit RSA key
+    const SHORT_KEY: &str = "
+-----BEGIN PGP PUBLIC KEY BLOCK-----
+
+mI0EatMlSAEEAKivqOG2mhZ4ujSv/QjM3sEVk9FfbApC4PnbusTx0b4kEMmoGWtV
+L7AT+UMiEIsYUjNI7tDdFjsGY92pr+twAG23IoOGnTaT0dLvRgExaPDsYaFYncyq
+3zsY7S1fVFj5QY0waVWESkzsLBJ1FmnQnX/w105eNribj/jDy8Vo9SLHABEBAAG0
+HlRlc3QgU2hvcnQgPHNob3J0QGV4YW1wbGUuY29tPojOBBMBCgA4FiEE6vXHWoaf
+YWPtNy62XeTND/Aw0Z0FAmrTJUgCGwMFCwkIBwIGFQoJCAsCBBYCAwECHgECF4AA
+CgkQXeTND/Aw0Z0XpQP7BFcTwnafqSdGKWBZg28jyLPwgxwxfY0d4kF6VwXk3h3x
+KGVabUNuxmd+RjAtp0u5Kwry3elsCMukCjYeLdnjykY8hwBmP9GxZ9AptaLAIVVg
+4YFc2vmxntH/RLaHdxfmFwzlQOb6AzhNKD9+2V5NABXZMU+FhjmKAtbgRtXNPeY=
+=rRB4
+-----END PGP PUBLIC KEY BLOCK-----
+";
+
+    // signature by the 1024-bit RSA key
+    const SHORT_SIG: &str = "
+-----BEGIN PGP SIGNATURE-----
+
+iMYEAAEIADAWIQTq9cdahp9hY+03LrZd5M0P8DDRnQUCatMlSxIcc2hvcnRAZXhh
+bXBsZS5jb20ACgkQXeTND/Aw0Z1EAQP/aw0trbI9hP1xFoscRm7HSgSriK2rjO3B
+QP+v/NUUKcv0JA1tkbTSxe8C46XWLvo4xzdnFKMFgXAA2XzmXLAKTHE+NAUfuhDK
+X15H0qvwtPfMtzte/MhTmBPIGR9XOhyHEgH1jN/AQw09quGi3G9fGk2YSAsC/jom
+0+4aFbkMxAk=
+=ii1G
+-----END PGP SIGNATURE-----
+";
+
+    fn list() -> (LeapSecs, String) {
+        let text = history!("999?");
+        let list = LeapSecs::from_str_lenient(text).unwrap();
+        let list = list.with_updated(Gregorian(2021, 1, 7).mjd());
+        let data = nist::format(&list).unwrap();
+        (list, data)
+    }
+
+    #[test]
+    fn test() {
+        let (list, data) = list();
+        let ed = pgp::PublicKey::from_armor(ED25519_KEY).unwrap();
+        let rsa = pgp::PublicKey::from_armor(RSA_KEY).unwrap();
+        let both = [ed.clone(), rsa.clone()];
+        for &sig in &[ED25519_SIG, RSA_SIG] {
+            pgp::verify(data.as_bytes(), sig.as_bytes(), &both).unwrap();
+            let got = nist::read_bytes_verified(
+                data.as_bytes(),
+                sig.as_bytes(),
+                &both,
+            );
+            assert_eq!(list, got.unwrap());
+        }
+        let crlf = data.replace('\n', "\r\n");
+        assert!(pgp::verify(crlf.as_bytes(), RSA_SIG.as_bytes(), &both).is_ok());
+        assert!(pgp::verify(crlf.as_bytes(), ED25519_SIG.as_bytes(), &both)
+            .is_err());
+
+        let wrong =
+            pgp::verify(data.as_bytes(), ED25519_SIG.as_bytes(), &[rsa]);
+        assert!(matches!(wrong, Err(Error::Signature)));
+        let wrong = pgp::verify(data.as_bytes(), RSA_SIG.as_bytes(), &[ed]);
+        assert!(matches!(wrong, Err(Error::Signature)));
+        let forged = data.replace("999", "998");
+        let forged = pgp::verify(forged.as_bytes(), RSA_SIG.as_bytes(), &both);
+        assert!(matches!(forged, Err(Error::Signature)));
+
+        let garbled = ED25519_SIG.replacen('i', "j", 1);
+        let garbled = pgp::verify(data.as_bytes(), garbled.as_bytes(), &both);
+        assert!(matches!(garbled, Err(Error::Pgp(_))));
+        assert!(matches!(
+            pgp::PublicKey::from_armor(ED25519_SIG),
+            Err(Error::Pgp(_))
+        ));
+    }
+
+    // the binary packets of an armored key, except its signatures
+    fn unsigned(armor: &str) -> Vec<u8> {
+        let bytes = super::dearmor(armor).unwrap();
+        let mut input = super::Reader(&bytes);
+        let mut out = vec![];
+        while !input.0.is_empty() {
+            let before = input.0;
+            let (tag, _) = input.packet().unwrap();
+            let packet = &before[..before.len() - input.0.len()];
+            if tag != super::SIGNATURE {
+                out.extend_from_slice(packet);
+            }
+        }
+        out
+    }
+
+    #[test]
+    fn subkeys() {
+        let (_, data) = list();
+        let data = data.as_bytes();
+        let key = pgp::PublicKey::from_armor(SUBKEY_KEY).unwrap();
+        pgp::verify(data, SUBKEY_SIG.as_bytes(), &[key]).unwrap();
+
+        // without its binding signature the subkey is not trusted
+        let bytes = unsigned(SUBKEY_KEY);
+        let unbound = pgp::PublicKey::from_bytes(&bytes).unwrap();
+        let err = pgp::verify(data, SUBKEY_SIG.as_bytes(), &[unbound]);
+        assert!(matches!(err, Err(Error::Signature)));
+
+        // nor can it be grafted onto another key
+        let mut bytes = super::dearmor(ED25519_KEY).unwrap();
+        let subkey = unsigned(SUBKEY_KEY);
+        let mut input = super::Reader(&subkey);
+        input.packet().unwrap();
+        bytes.extend_from_slice(input.0);
+        let grafted = pgp::PublicKey::from_bytes(&bytes).unwrap();
+        let err = pgp::verify(data, SUBKEY_SIG.as_bytes(), &[grafted]);
+        assert!(matches!(err, Err(Error::Signature)));
+    }
+
+    #[test]
+    fn weak() {
+        let (_, data) = list();
+        let data = data.as_bytes();
+        let key = [pgp::PublicKey::from_armor(SUBKEY_KEY).unwrap()];
+        let err = pgp::verify(data, SHA1_SIG.as_bytes(), &key);
+        assert!(matches!(err, Err(Error::Pgp(_))));
+        let sha1 = pgp::VerifyOptions { sha1: true, ..Default::default() };
+        pgp::verify_with(data, SHA1_SIG.as_bytes(), &key, &sha1).unwrap();
+
+        let key = [pgp::PublicKey::from_armor(SHORT_KEY).unwrap()];
+        let err = pgp::verify(data, SHORT_SIG.as_bytes(), &key);
+        assert!(matches!(err, Err(Error::Signature)));
+        let short =
+            pgp::VerifyOptions { short_rsa: true, ..Default::default() };
+        pgp::verify_with(data, SHORT_SIG.as_bytes(), &key, &short).unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "fetch")]
+    fn read_verified() {
+        let (list, data) = list();
+        let keys = [pgp::PublicKey::from_armor(ED25519_KEY).unwrap()];
+        let name = temp_path("verified.list");
+        let url = format!("file://{}", name.to_str().unwrap());
+        std::fs::write(&name, &data).unwrap();
+        std::fs::write(format!("{}.asc", name.to_str().unwrap()), ED25519_SIG)
+            .unwrap();
+        assert_eq!(list, nist::read_verified(&url, &keys).unwrap());
+        std::fs::write(&name, data.replace("999", "998")).unwrap();
+        assert!(nist::read_verified(&url, &keys).is_err());
+        std::fs::remove_file(&name).unwrap();
+        std::fs::remove_file(format!("{}.asc", name.to_str().unwrap()))
+            .unwrap();
+    }
+}
@@ -0,0 +1,173 @@
+//! UTC before 1972
+//! ===============
+//!
+//! From 1961 to 1971, UTC was kept close to UT2 by changing the length
+//! of the UTC second ("rubber seconds") as well as by small steps,
+//! so the difference between UTC and TAI was a fractional number of
+//! seconds that changed continuously. This module has the official
+//! table of offsets and drift rates for that era, as published by
+//! the US Naval Observatory in `tai-utc.dat`, for example,
+//!
+//!     # use leapsecs::*;
+//!     let dtai = pre1972::dtai_at(Gregorian(1961, 1, 1))?;
+//!     assert_eq!(dtai, 1.422818);
+//!     let dtai = pre1972::dtai_at(Gregorian(1971, 12, 31))?;
+//!     assert!((dtai - 9.889).abs() < 0.001);
+//!     # Ok::<(), Error>(())
+//!
+//! The table is fixed history, so it is compiled in rather than
+//! carried in the leap seconds list. [`dtai_with_list()`][] combines
+//! it with a [`LeapSecs`][] to cover all of UTC from 1961 onwards.
+//!
+//! This requires the `pre1972` feature.
+
+use crate::*;
+use core::convert::TryFrom;
+
+/// One row of the table of TAI − UTC before 1972.
+///
+/// From `start` until the start of the next row, TAI − UTC is
+/// `offset + (mjd - base) * rate` seconds, where `mjd` is the
+/// fractional MJD in UTC.
+///
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Drift {
+    /// The date when this row takes effect
+    pub start: MJD,
+    /// TAI − UTC at `base`, in seconds
+    pub offset: f64,
+    /// The reference date for the drift
+    pub base: MJD,
+    /// The drift in seconds per day
+    pub rate: f64,
+}
+
+impl Drift {
+    /// Get TAI − UTC at a fractional `mjd` in UTC, without checking
+    /// that it is within this row's period.
+    ///
+    pub fn dtai_at_mjd(&self, mjd: f64) -> f64 {
+        let days = mjd - f64::from(self.base - MJD::from(0));
+        self.offset + days * self.rate
+    }
+}
+
+const fn row(start: Gregorian, offset: f64, base: i32, rate: f64) -> Drift {
+    let base = Gregorian(base, 1, 1).mjd();
+    Drift { start: start.mjd(), offset, base, rate }
+}
+
+/// The rows of the table, in order; the last one ends on
+/// [`UTC_START_1972`][], when TAI − UTC became exactly 10 seconds.
+///
+pub const TABLE: [Drift; 13] = [
+    row(Gregorian(1961, 1, 1), 1.422818, 1961, 0.001296),
+    row(Gregorian(1961, 8, 1), 1.372818, 1961, 0.001296),
+    row(Gregorian(1962, 1, 1), 1.845858, 1962, 0.0011232),
+    row(Gregorian(1963, 11, 1), 1.945858, 1962, 0.0011232),
+    row(Gregorian(1964, 1, 1), 3.24013, 1965, 0.001296),
+    row(Gregorian(1964, 4, 1), 3.34013, 1965, 0.001296),
+    row(Gregorian(1964, 9, 1), 3.44013, 1965, 0.001296),
+    row(Gregorian(1965, 1, 1), 3.54013, 1965, 0.001296),
+    row(Gregorian(1965, 3, 1), 3.64013, 1965, 0.001296),
+    row(Gregorian(1965, 7, 1), 3.74013, 1965, 0.001296),
+    row(Gregorian(1965, 9, 1), 3.84013, 1965, 0.001296),
+    row(Gregorian(1966, 1, 1), 4.31317, 1966, 0.002592),
+    row(Gregorian(1968, 2, 1), 4.21317, 1966, 0.002592),
+];
+
+/// The first day of UTC
+pub const UTC_START_1961: MJD = Gregorian(1961, 1, 1).mjd();
+
+/// Find the row of the table that covers a fractional `mjd`
+fn find(mjd: f64) -> Option<&'static Drift> {
+    let day = |drift: &Drift| f64::from(drift.start - MJD::from(0));
+    if mjd >= f64::from(UTC_START_1972 - MJD::from(0)) {
+        return None;
+    }
+    TABLE.iter().rev().find(|drift| day(drift) <= mjd)
+}
+
+/// Get TAI − UTC in seconds at the start of a `date` from 1961 to
+/// 1971.
+///
+/// Fails with [`Error::RubberEra`][] outside that period.
+///
+pub fn dtai_at(date: Gregorian) -> Result<f64> {
+    let mjd = f64::from(date.mjd() - MJD::from(0));
+    let drift = find(mjd).ok_or(Error::RubberEra(date))?;
+    Ok(drift.dtai_at_mjd(mjd))
+}
+
+/// Get TAI − UTC in seconds at a [`Unix`][] timestamp from 1961 to
+/// 1971, including the drift during the day.
+///
+/// Fails with [`Error::RubberEra`][] outside that period.
+///
+pub fn dtai_at_unix(unix: Unix) -> Result<f64> {
+    let days = unix.0 as f64 / 86400.0;
+    let mjd = days + f64::from(UNIX_EPOCH_MJD - MJD::from(0));
+    match find(mjd) {
+        Some(drift) => Ok(drift.dtai_at_mjd(mjd)),
+        None => {
+            let date = MJD::try_from(unix)?;
+            Err(Error::RubberEra(Gregorian::from(date)))
+        }
+    }
+}
+
+/// Get TAI − UTC in seconds at a [`Unix`][] timestamp from 1961
+/// onwards, using this module's table before 1972 and the `list`
+/// after that.
+///
+///     # use leapsecs::*;
+///     # use std::str::FromStr;
+///     let list = LeapSecs::from_str("6+6+999?")?;
+///     let dtai = pre1972::dtai_with_list(&list, Unix(78_796_800))?;
+///     assert_eq!(dtai, 11.0);
+///     let dtai = pre1972::dtai_with_list(&list, Unix(0))?;
+///     assert!((dtai - 8.000082).abs() < 1e-6);
+///     # Ok::<(), Error>(())
+///
+#[cfg(feature = "alloc")]
+pub fn dtai_with_list(list: &LeapSecs, unix: Unix) -> Result<f64> {
+    if unix < Unix::from(UTC_START_1972) {
+        dtai_at_unix(unix)
+    } else {
+        Ok(f64::from(list.dtai_at_unix(unix)?))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::pre1972::*;
+
+    #[test]
+    fn test() {
+        // the steps at the start of each row, from tai-utc.dat
+        for (prev, next) in TABLE.iter().zip(&TABLE[1..]) {
+            let mjd = f64::from(next.start - MJD::from(0));
+            let step = next.dtai_at_mjd(mjd) - prev.dtai_at_mjd(mjd);
+            assert!(step.abs() < 0.11, "{:?} {}", next, step);
+        }
+        let last = TABLE[12].dtai_at_mjd(41317.0);
+        assert!((last - 9.8922).abs() < 0.0001);
+
+        let start = Unix::from(UTC_START_1961);
+        assert_eq!(1.422818, dtai_at_unix(start).unwrap());
+        let noon = dtai_at_unix(Unix(start.0 + 43200)).unwrap();
+        assert!((noon - 1.422818 - 0.000648).abs() < 1e-9);
+        let err = dtai_at_unix(Unix(start.0 - 1)).unwrap_err();
+        assert!(matches!(err, Error::RubberEra(Gregorian(1960, 12, 31))));
+        let end = Unix::from(UTC_START_1972);
+        assert!(dtai_at_unix(Unix(end.0 - 1)).is_ok());
+        assert!(dtai_at_unix(end).is_err());
+        assert!(dtai_at(Gregorian(1972, 1, 1)).is_err());
+        let feb68 = dtai_at(Gregorian(1968, 2, 1)).unwrap();
+        assert!((feb68 - 4.21317 - 0.002592 * 761.0).abs() < 1e-9);
+        let max = (i64::from(i32::MAX) - 40587) * 86400;
+        for &unix in &[i64::MIN, -max, max, max + 86400, i64::MAX] {
+            assert!(dtai_at_unix(Unix(unix)).is_err(), "{}", unix);
+        }
+    }
+}
@@ -0,0 +1,151 @@
+//! Python bindings
+//! ===============
+//!
+//! With the `python` feature, the crate can be built as a Python
+//! extension module called `leapsecs`, which has a `LeapSecs` class
+//! that wraps [`LeapSecs`][], for example,
+//!
+//! ```python
+//! import leapsecs
+//! leaps = leapsecs.LeapSecs.from_nist(open("leap-seconds.list").read())
+//! print(leaps.expires(), leaps.dtai("2017-01-01"))
+//! ```
+//!
+//! Dates are ISO 8601 strings, which `datetime.date.fromisoformat()`
+//! can parse. Errors are raised as `ValueError`.
+//!
+//! To build the module, use [maturin](https://www.maturin.rs/) or
+//!
+//! ```sh
+//! cargo rustc --release --features python,pyo3/extension-module \
+//!     --crate-type cdylib
+//! ```
+//!
+//! and rename the library to `leapsecs.so`.
+
+use crate::*;
+use core::convert::TryFrom;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+
+fn value_error(err: Error) -> PyErr {
+    PyValueError::new_err(err.to_string())
+}
+
+fn parse_date(date: &str) -> PyResult<Gregorian> {
+    let date = crate::parse_date(date).map_err(value_error)?;
+    if Gregorian::from(date.mjd()) != date {
+        return Err(value_error(Error::Date(date)));
+    }
+    Ok(date)
+}
+
+/// A leap second list, for Python.
+///
+#[pyclass(name = "LeapSecs", frozen)]
+#[derive(Debug)]
+pub struct PyLeapSecs(LeapSecs);
+
+#[pymethods]
+impl PyLeapSecs {
+    /// Parse the compact text format.
+    #[staticmethod]
+    fn from_text(text: &str) -> PyResult<Self> {
+        let list = text.trim_end().parse().map_err(value_error)?;
+        Ok(PyLeapSecs(list))
+    }
+
+    /// Parse the compact binary format.
+    #[staticmethod]
+    fn from_bytes(data: &[u8]) -> PyResult<Self> {
+        let list = LeapSecs::try_from(data).map_err(value_error)?;
+        Ok(PyLeapSecs(list))
+    }
+
+    /// Parse the NIST `leap-seconds.list` format.
+    #[staticmethod]
+    fn from_nist(text: &str) -> PyResult<Self> {
+        let list = nist::read_str(text).map_err(value_error)?;
+        Ok(PyLeapSecs(list))
+    }
+
+    /// Format the list in the compact text format.
+    fn to_text(&self) -> String {
+        self.0.to_string()
+    }
+
+    /// Format the list in the compact binary format.
+    fn to_bytes<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new(py, &Vec::from(&self.0))
+    }
+
+    /// Format the list in the NIST `leap-seconds.list` format.
+    fn to_nist(&self) -> PyResult<String> {
+        nist::format(&self.0).map_err(value_error)
+    }
+
+    /// Get the difference between UTC and TAI on a date.
+    fn dtai(&self, date: &str) -> PyResult<i16> {
+        self.0.dtai_at(parse_date(date)?).map_err(value_error)
+    }
+
+    /// Get the difference between UTC and TAI at a POSIX timestamp.
+    fn dtai_at_unix(&self, timestamp: i64) -> PyResult<i16> {
+        self.0.dtai_at_unix(Unix(timestamp)).map_err(value_error)
+    }
+
+    /// Get the expiry date of the list.
+    fn expires(&self) -> String {
+        Gregorian::from(self.0.expires()).to_string()
+    }
+
+    /// Returns true if the list has expired.
+    fn is_expired(&self) -> bool {
+        self.0.is_expired_at(SystemClock)
+    }
+
+    fn __len__(&self) -> usize {
+        self.0.len()
+    }
+
+    fn __str__(&self) -> String {
+        self.0.to_string()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("LeapSecs.from_text({:?})", self.0.to_string())
+    }
+}
+
+/// The `leapsecs` Python module.
+///
+#[pymodule]
+fn leapsecs(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyLeapSecs>()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::python::*;
+
+    #[test]
+    fn test() {
+        let text = history!("999?");
+        let list = PyLeapSecs::from_text(text).unwrap();
+        assert_eq!(text, list.to_text());
+        assert_eq!(29, list.__len__());
+        assert_eq!("2100-04-28", list.expires());
+        assert!(!list.is_expired());
+        assert_eq!(36, list.dtai("2016-12-31").unwrap());
+        assert_eq!(37, list.dtai("2017-01-01").unwrap());
+        assert_eq!(37, list.dtai_at_unix(1_483_228_800).unwrap());
+        assert!(list.dtai("2017-02-30").is_err());
+        assert!(list.dtai("1971-12-31").is_err());
+        let nist = PyLeapSecs::from_nist(&list.to_nist().unwrap()).unwrap();
+        assert_eq!(list.0, nist.0);
+        let bytes = Vec::from(&list.0);
+        assert_eq!(list.0, PyLeapSecs::from_bytes(&bytes).unwrap().0);
+        assert!(PyLeapSecs::from_text("6+6+").is_err());
+    }
+}
@@ -0,0 +1,276 @@
+//! QR codes
+//! ========
+//!
+//! A leap second list in compact binary format is small enough to
+//! fit in a QR code, so it can be carried to timing equipment that
+//! is not connected to a network, for example,
+//!
+//!     # use leapsecs::*;
+//!     # use std::str::FromStr;
+//!     let list = LeapSecs::from_str("6+6+12+12+12+12+12+12+12+\
+//!         18+12+12+24+30+24+12+18+12+12+18+18+18+84+36+42+36+18+999?")?;
+//!     let svg = qr::svg(&list)?;
+//!     assert!(svg.starts_with("<?xml"));
+//!     let payload = qr::payload(&list);
+//!     assert_eq!(list, qr::from_payload(&payload)?);
+//!     # Ok::<(), Error>(())
+//!
+//! The payload of the QR code is the compact binary format followed
+//! by its CRC-16, most significant byte first. This is the same
+//! checksum as in the compact text format, see
+//! [`LeapSecs::text_checksum()`][], so that a scanned list that has
+//! been damaged or truncated is rejected.
+//!
+//! With the `signed` feature, the payload can instead be a COSE
+//! envelope from [`cose::sign()`][crate::cose::sign] followed by its
+//! CRC-16, made by [`signed_payload()`][].
+//!
+//! At the other end, [`update()`][] checks a scanned payload and
+//! merges it with the list that the equipment already has.
+//!
+//! Requires the `qr` feature.
+
+use qrcode::render::svg;
+use qrcode::{Color, EcLevel, QrCode};
+
+use crate::*;
+
+// size of each module in a PNG image, in pixels
+const PNG_MODULE: usize = 8;
+// the margin around the code, in modules
+const QUIET_ZONE: usize = 4;
+
+/// Get the payload of a QR code for the list: its compact binary
+/// format followed by its CRC-16.
+///
+pub fn payload(list: &LeapSecs) -> Vec<u8> {
+    frame(Vec::from(list))
+}
+
+/// Get the payload of a QR code for a signed list: a COSE envelope
+/// made by [`cose::sign()`][crate::cose::sign] followed by its CRC-16.
+///
+/// Requires the `signed` feature.
+///
+#[cfg(feature = "signed")]
+pub fn signed_payload(
+    list: &LeapSecs,
+    key: &ring::signature::Ed25519KeyPair,
+) -> Vec<u8> {
+    frame(cose::sign(list, key))
+}
+
+fn frame(mut bytes: Vec<u8>) -> Vec<u8> {
+    let sum = txt::crc16(bytes.iter().copied());
+    bytes.extend_from_slice(&sum.to_be_bytes());
+    bytes
+}
+
+// check the CRC-16 and return the bytes inside the frame
+fn unframe(bytes: &[u8]) -> Result<&[u8]> {
+    if bytes.len() < 2 {
+        return Err(Error::Truncated);
+    }
+    let (bytes, sum) = bytes.split_at(bytes.len() - 2);
+    let sum = u16::from_be_bytes([sum[0], sum[1]]);
+    let calculated = txt::crc16(bytes.iter().copied());
+    if sum != calculated {
+        return Err(Error::TextChecksum(sum, calculated));
+    }
+    Ok(bytes)
+}
+
+/// Parse the payload of a QR code made by this module.
+///
+/// Fails with [`Error::TextChecksum`][] if the checksum does not
+/// match, otherwise the list is checked as if by
+/// [`LeapSecBuilder::finish()`][].
+///
+pub fn from_payload(bytes: &[u8]) -> Result<LeapSecs> {
+    from_payload_at(bytes, SystemClock)
+}
+
+/// Parse the payload of a QR code made by this module, checking for
+/// expiry using the given clock instead of the system clock.
+///
+pub fn from_payload_at<C: Clock>(bytes: &[u8], clock: C) -> Result<LeapSecs> {
+    LeapSecs::try_from_bytes_at(unframe(bytes)?, clock)
+}
+
+/// Parse the payload of a QR code made by [`signed_payload()`][],
+/// checking the signature with a raw Ed25519 `public_key` as in
+/// [`cose::verify()`][crate::cose::verify].
+///
+/// Requires the `signed` feature.
+///
+#[cfg(feature = "signed")]
+pub fn from_signed_payload(
+    bytes: &[u8],
+    public_key: &[u8],
+) -> Result<LeapSecs> {
+    cose::verify(unframe(bytes)?, public_key)
+}
+
+/// Merge a list from a scanned payload with the `local` list that
+/// is already installed, returning the list to keep.
+///
+/// The scanned list replaces the local list if it
+/// [extends][LeapSecs::extends] it. If the scanned list is older,
+/// the local list is kept. Otherwise the lists disagree about the
+/// past, and this fails with [`Error::History`][] as in
+/// [`LeapSecs::verify_history()`][].
+///
+/// The scanned list is not checked for expiry, because equipment
+/// that is not on a network may not know the date; instead, the
+/// list that is kept is never older than the `local` list.
+///
+pub fn update(local: &LeapSecs, payload: &[u8]) -> Result<LeapSecs> {
+    let scanned = LeapSecs::try_from_bytes_lenient(unframe(payload)?)?;
+    merge(local, scanned)
+}
+
+/// Like [`update()`][], for a payload made by [`signed_payload()`][].
+///
+/// Requires the `signed` feature.
+///
+#[cfg(feature = "signed")]
+pub fn update_signed(
+    local: &LeapSecs,
+    payload: &[u8],
+    public_key: &[u8],
+) -> Result<LeapSecs> {
+    let bytes = unframe(payload)?;
+    let scanned = cose::verify_lenient(bytes, public_key)?;
+    merge(local, scanned)
+}
+
+fn merge(local: &LeapSecs, scanned: LeapSecs) -> Result<LeapSecs> {
+    if local.extends(&scanned) {
+        Ok(local.clone())
+    } else {
+        scanned.verify_history(local)?;
+        Ok(scanned)
+    }
+}
+
+fn encode(payload: &[u8]) -> Result<QrCode> {
+    QrCode::with_error_correction_level(payload, EcLevel::M)
+        .map_err(|e| Error::QrCode(e.to_string()))
+}
+
+/// Render the list as a QR code in an SVG document.
+///
+pub fn svg(list: &LeapSecs) -> Result<String> {
+    render_svg(&payload(list))
+}
+
+/// Render a payload, such as from [`signed_payload()`][], as a QR
+/// code in an SVG document.
+///
+pub fn render_svg(payload: &[u8]) -> Result<String> {
+    let code = encode(payload)?;
+    Ok(code.render::<svg::Color<'_>>().build())
+}
+
+/// Render the list as a QR code in a greyscale PNG image, with
+/// 8 pixels per module.
+///
+pub fn png(list: &LeapSecs) -> Result<Vec<u8>> {
+    render_png(&payload(list))
+}
+
+/// Render a payload, such as from [`signed_payload()`][], as a QR
+/// code in a PNG image, like [`png()`][].
+///
+pub fn render_png(payload: &[u8]) -> Result<Vec<u8>> {
+    let code = encode(payload)?;
+    let width = code.width();
+    let size = (width + 2 * QUIET_ZONE) * PNG_MODULE;
+    let mut pixels = vec![0xFF; size * size];
+    for (i, color) in code.to_colors().into_iter().enumerate() {
+        if color == Color::Light {
+            continue;
+        }
+        let x = (i % width + QUIET_ZONE) * PNG_MODULE;
+        let y = (i / width + QUIET_ZONE) * PNG_MODULE;
+        for row in y..y + PNG_MODULE {
+            pixels[row * size + x..][..PNG_MODULE].fill(0x00);
+        }
+    }
+    let mut image = Vec::new();
+    let mut encoder = png::Encoder::new(&mut image, size as u32, size as u32);
+    encoder.set_color(png::ColorType::Grayscale);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+        .write_header()
+        .and_then(|mut writer| writer.write_image_data(&pixels))
+        .map_err(|e| Error::QrCode(e.to_string()))?;
+    Ok(image)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+
+    #[test]
+    fn test() {
+        let text = history!("59?");
+        let list = LeapSecs::from_str_lenient(text).unwrap();
+        let today = Gregorian(2021, 1, 1).mjd();
+        let payload = qr::payload(&list);
+        assert_eq!(list, qr::from_payload_at(&payload, today).unwrap());
+        assert!(matches!(qr::from_payload(&payload), Err(Error::Expired(_))));
+
+        let mut damaged = payload.clone();
+        *damaged.last_mut().unwrap() ^= 1;
+        let err = qr::from_payload_at(&damaged, today);
+        assert!(matches!(err, Err(Error::TextChecksum(..))));
+        assert!(qr::from_payload_at(&payload[..1], today).is_err());
+
+        let svg = qr::svg(&list).unwrap();
+        assert!(svg.contains("<svg"));
+        let png = qr::png(&list).unwrap();
+        let decoder = png::Decoder::new(&png[..]);
+        let reader = decoder.read_info().unwrap();
+        let info = reader.info();
+        assert_eq!(info.width, info.height);
+        assert_eq!(0, info.width % 8);
+    }
+    #[test]
+    fn update() {
+        let old = LeapSecs::from_str_lenient("6+6+12+59?").unwrap();
+        let new = LeapSecs::from_str_lenient("6+6+12+60+12?").unwrap();
+        let bad = LeapSecs::from_str_lenient("6+6+13+59?").unwrap();
+        let got = qr::update(&old, &qr::payload(&new)).unwrap();
+        assert_eq!(new, got);
+        let got = qr::update(&new, &qr::payload(&old)).unwrap();
+        assert_eq!(new, got);
+        let err = qr::update(&old, &qr::payload(&bad));
+        assert!(matches!(err, Err(Error::History(..))));
+        let mut damaged = qr::payload(&new);
+        damaged[0] ^= 1;
+        let err = qr::update(&old, &damaged);
+        assert!(matches!(err, Err(Error::TextChecksum(..))));
+    }
+
+    #[cfg(feature = "signed")]
+    #[test]
+    fn signed() {
+        use ring::signature::{Ed25519KeyPair, KeyPair};
+
+        let key = Ed25519KeyPair::from_seed_unchecked(&[42; 32]).unwrap();
+        let public = key.public_key().as_ref();
+        let old = LeapSecs::from_str_lenient("6+6+12+59?").unwrap();
+        let new = LeapSecs::from_str_lenient("6+6+12+60+12?").unwrap();
+        let payload = qr::signed_payload(&new, &key);
+        assert!(qr::render_svg(&payload).is_ok());
+        let got = qr::update_signed(&old, &payload, public).unwrap();
+        assert_eq!(new, got);
+        let other = Ed25519KeyPair::from_seed_unchecked(&[7; 32]).unwrap();
+        let other = other.public_key().as_ref();
+        let err = qr::update_signed(&old, &payload, other);
+        assert!(matches!(err, Err(Error::Signature)));
+        let err = qr::from_signed_payload(&payload, public);
+        assert!(matches!(err, Err(Error::Expired(_))));
+    }
+}
@@ -0,0 +1,123 @@
+//! Time scales
+//! ===========
+//!
+//! The satellite navigation systems each keep their own time scale,
+//! which differs from TAI or UTC by a whole number of seconds.
+//! [`LeapSecs::offset_between()`][] works out the difference between
+//! any two [`TimeScale`][]s on a date, for example,
+//!
+//!     # use leapsecs::*;
+//!     # use std::str::FromStr;
+//!     use scale::TimeScale::*;
+//!     let list = LeapSecs::from_str("6+6+12+12+12+12+12+12+12+\
+//!         18+12+12+24+30+24+12+18+12+12+18+18+18+84+36+42+36+18+999?")?;
+//!     let date = Gregorian(2017, 1, 1).mjd();
+//!     assert_eq!(list.offset_between(Utc, Tai, date)?, 37);
+//!     assert_eq!(list.offset_between(Utc, Gps, date)?, 18);
+//!     assert_eq!(list.offset_between(Gps, Bdt, date)?, -14);
+//!     assert_eq!(list.offset_between(Glonass, Utc, date)?, -10800);
+//!     # Ok::<(), Error>(())
+//!
+//! The offsets between TAI, GPS time, and BeiDou time are fixed, so
+//! they do not depend on the list. UTC and GLONASS time change with
+//! each leap second, so the offsets involving them fail like
+//! [`LeapSecs::dtai_at()`][] if the list does not cover the date.
+
+use crate::*;
+
+/// TAI − BDT, which has been fixed since the BeiDou epoch in 2006
+pub const TAI_MINUS_BDT: i16 = 33;
+
+/// GLONASS time is UTC(SU), which is three hours ahead of UTC
+pub const GLONASS_MINUS_UTC: i32 = 3 * 3600;
+
+/// A time scale whose offset from TAI is a whole number of seconds
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub enum TimeScale {
+    /// International Atomic Time
+    Tai,
+    /// Coordinated Universal Time
+    Utc,
+    /// GPS time, see the [`gps`][] module
+    Gps,
+    /// BeiDou time
+    Bdt,
+    /// GLONASS time, which is Moscow time and has leap seconds
+    Glonass,
+}
+
+impl TimeScale {
+    // this time scale minus TAI, in seconds
+    fn minus_tai(self, list: &LeapSecs, at: MJD) -> Result<i32> {
+        Ok(match self {
+            TimeScale::Tai => 0,
+            TimeScale::Utc => -i32::from(list.dtai_at(Gregorian::from(at))?),
+            TimeScale::Gps => -i32::from(gps::TAI_MINUS_GPS),
+            TimeScale::Bdt => -i32::from(TAI_MINUS_BDT),
+            TimeScale::Glonass => {
+                TimeScale::Utc.minus_tai(list, at)? + GLONASS_MINUS_UTC
+            }
+        })
+    }
+}
+
+impl core::fmt::Display for TimeScale {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(match self {
+            TimeScale::Tai => "TAI",
+            TimeScale::Utc => "UTC",
+            TimeScale::Gps => "GPS",
+            TimeScale::Bdt => "BDT",
+            TimeScale::Glonass => "GLONASS",
+        })
+    }
+}
+
+impl LeapSecs {
+    /// Get the number of seconds to add to a time in the scale `from`
+    /// to get the same time in the scale `to`, on the UTC date `at`.
+    ///
+    /// Around midnight UTC on the date of a leap second, the date in
+    /// another time scale can differ from the UTC date, so `at` must
+    /// be the UTC date.
+    ///
+    pub fn offset_between(
+        &self,
+        from: TimeScale,
+        to: TimeScale,
+        at: MJD,
+    ) -> Result<i32> {
+        Ok(to.minus_tai(self, at)? - from.minus_tai(self, at)?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::scale::*;
+    use alloc::string::ToString;
+    use TimeScale::*;
+
+    #[test]
+    fn test() {
+        let list = LeapSecs::from_str_lenient("6+6+12+59?").unwrap();
+        let date = Gregorian(1972, 7, 1).mjd();
+        let all = [Tai, Utc, Gps, Bdt, Glonass];
+        for &a in &all {
+            assert_eq!(0, list.offset_between(a, a, date).unwrap());
+            for &b in &all {
+                let ab = list.offset_between(a, b, date).unwrap();
+                let ba = list.offset_between(b, a, date).unwrap();
+                assert_eq!(ab, -ba, "{} {}", a, b);
+            }
+        }
+        assert_eq!(11, list.offset_between(Utc, Tai, date).unwrap());
+        assert_eq!(8, list.offset_between(Gps, Utc, date).unwrap());
+        assert_eq!(10789, list.offset_between(Tai, Glonass, date).unwrap());
+
+        // fixed offsets do not need the list to cover the date
+        let later = Gregorian(2100, 1, 1).mjd();
+        assert_eq!(-14, list.offset_between(Gps, Bdt, later).unwrap());
+        assert!(list.offset_between(Utc, Gps, later).is_err());
+        assert_eq!("GLONASS", Glonass.to_string());
+    }
+}
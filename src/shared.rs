@@ -0,0 +1,145 @@
+//! A list shared between threads
+//! =============================
+//!
+//! A server can keep its leap second list in a [`SharedLeapSecs`][],
+//! so that a background thread can install a new list while worker
+//! threads carry on using the old one. Workers call
+//! [`SharedLeapSecs::get()`][] to get a reference to the current list,
+//! which stays valid after the list is replaced, for example,
+//!
+//!     # use leapsecs::*;
+//!     # use std::str::FromStr;
+//!     let shared = SharedLeapSecs::new(LeapSecs::from_str("6+6+12+998?")?);
+//!     let old = shared.get();
+//!     assert!(shared.replace_if_newer(LeapSecs::from_str("6+6+12+999?")?)?);
+//!     assert!(!shared.replace_if_newer(LeapSecs::from_str("6+6+12+999?")?)?);
+//!     assert_eq!(old.expires() + 31, shared.get().expires());
+//!     # Ok::<(), Error>(())
+//!
+//! With the `fetch` feature, the background thread can use a
+//! [`nist::Updater`][] to keep the list up to date, by calling
+//! [`SharedLeapSecs::refresh()`][] at the updater's
+//! [`nist::Updater::next_poll()`][] time, so that fetching a new
+//! list does not hold up the workers.
+//!
+//! This requires the `std` feature.
+
+use crate::*;
+use std::sync::{Arc, PoisonError, RwLock};
+
+/// A leap second list that can be replaced while other threads are
+/// using it.
+///
+#[derive(Debug)]
+pub struct SharedLeapSecs(RwLock<Arc<LeapSecs>>);
+
+impl SharedLeapSecs {
+    /// Share a list.
+    pub fn new(list: LeapSecs) -> SharedLeapSecs {
+        SharedLeapSecs(RwLock::new(Arc::new(list)))
+    }
+
+    /// Get the current list.
+    ///
+    /// This only holds the lock long enough to clone the [`Arc`][],
+    /// so it is cheap enough to call for every request.
+    ///
+    pub fn get(&self) -> Arc<LeapSecs> {
+        // the lock only protects a pointer, so it cannot be poisoned
+        // in an inconsistent state
+        let list = self.0.read().unwrap_or_else(PoisonError::into_inner);
+        Arc::clone(&list)
+    }
+
+    /// Replace the current list if the new one expires later.
+    ///
+    /// Returns `true` if the list was replaced, or `false` if the new
+    /// list expires on the same date. Fails with [`Error::History`][]
+    /// if the new list disagrees with the current one or is older, as
+    /// checked by [`LeapSecs::verify_history()`][], so that a corrupt
+    /// or replayed list is never installed.
+    ///
+    pub fn replace_if_newer(&self, list: LeapSecs) -> Result<bool> {
+        let mut current =
+            self.0.write().unwrap_or_else(PoisonError::into_inner);
+        list.verify_history(&current)?;
+        if list.expires() <= current.expires() {
+            return Ok(false);
+        }
+        *current = Arc::new(list);
+        Ok(true)
+    }
+
+    /// Get the expiry date of the current list.
+    pub fn expires(&self) -> MJD {
+        self.get().expires()
+    }
+
+    /// Returns true if the current list has expired.
+    pub fn is_expired(&self) -> bool {
+        self.get().is_expired_at(SystemClock)
+    }
+
+    /// Returns true if the current list will have expired `days` days
+    /// from now, see [`LeapSecs::expires_within()`][].
+    ///
+    pub fn expires_within(&self, days: i32) -> bool {
+        self.get().expires_within(days)
+    }
+
+    /// Poll the `updater`, and install its list if it is newer.
+    ///
+    /// Returns `true` if the list was replaced. Fails if the updater
+    /// has no list that is valid `now`, or if its list disagrees with
+    /// the current one.
+    ///
+    /// This requires the `fetch` feature.
+    ///
+    #[cfg(feature = "fetch")]
+    pub fn refresh<S: nist::Store>(
+        &self,
+        updater: &mut nist::Updater<S>,
+        now: Unix,
+    ) -> anyhow::Result<bool> {
+        let list = updater.poll(now)?.clone();
+        Ok(self.replace_if_newer(list)?)
+    }
+}
+
+impl From<LeapSecs> for SharedLeapSecs {
+    fn from(list: LeapSecs) -> SharedLeapSecs {
+        SharedLeapSecs::new(list)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+    use std::str::FromStr;
+    use std::sync::Arc;
+
+    #[test]
+    fn test() {
+        let prefix = "6+6+12+12+12+12+12+12+12+18+12+12+24+30+24+";
+        let list = |gaps| LeapSecs::from_str(&format!("{}{}", prefix, gaps));
+        let shared = Arc::new(SharedLeapSecs::new(list("997?").unwrap()));
+        let worker = {
+            let shared = Arc::clone(&shared);
+            let old = shared.get();
+            std::thread::spawn(move || {
+                while Arc::ptr_eq(&old, &shared.get()) {
+                    std::thread::yield_now();
+                }
+                old.expires()
+            })
+        };
+        assert!(!shared.replace_if_newer(list("997?").unwrap()).unwrap());
+        assert!(shared.replace_if_newer(list("998+6?").unwrap()).unwrap());
+        let old = worker.join().unwrap();
+        assert!(old < shared.expires());
+        let err = shared.replace_if_newer(list("999?").unwrap());
+        assert!(matches!(err, Err(Error::History(..))));
+        assert!(!shared.is_expired());
+        assert!(!shared.expires_within(28));
+    }
+}
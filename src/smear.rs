@@ -0,0 +1,117 @@
+//! Leap smear schedules
+//! ====================
+//!
+//! Instead of inserting or deleting a second, a smeared clock runs
+//! slightly slow or fast for a while around each leap second, so that
+//! it never has to step. This module generates a schedule that says
+//! how much of each leap second has been smeared at regular intervals
+//! around it, for example,
+//!
+//!     # use leapsecs::*;
+//!     # use std::str::FromStr;
+//!     let list = LeapSecs::from_str("6+6+999?")?;
+//!     let google = smear::Smear::GOOGLE;
+//!     let schedule: Vec<_> = google.schedule(&list).collect();
+//!     assert_eq!(schedule[0], (78_753_600, 0.0));
+//!     assert_eq!(schedule[12], (78_796_800, 0.5));
+//!     assert_eq!(schedule[24], (78_840_000, 1.0));
+//!     # Ok::<(), Error>(())
+//!
+//! Timestamps are POSIX time, in seconds since 1970 not counting leap
+//! seconds, and the leap second happens at the end of the day before
+//! the [`LeapSec::date()`][]. Offsets are in seconds, positive for a
+//! positive leap second and negative for a negative one. The smeared
+//! clock is the unsmeared clock minus the offset.
+//!
+//! This requires the `std` feature.
+
+use crate::*;
+use std::f64::consts::PI;
+
+/// The rate at which a leap second is smeared
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Shape {
+    /// Constant rate, which is the simplest
+    Linear,
+    /// Half a cosine wave, so the rate changes smoothly at the start
+    /// and end of the smear
+    Cosine,
+}
+
+/// The parameters of a leap smear
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Smear {
+    /// The rate of the smear
+    pub shape: Shape,
+    /// The length of the smear in seconds, centred on the leap second
+    pub duration: i64,
+    /// The interval between entries in the schedule, in seconds
+    pub step: i64,
+}
+
+impl Smear {
+    /// Google's 24 hour linear smear from noon to noon, with hourly
+    /// entries in the schedule
+    pub const GOOGLE: Smear =
+        Smear { shape: Shape::Linear, duration: 86400, step: 3600 };
+
+    /// Get the fraction of a leap second that has been smeared after
+    /// `elapsed` seconds of the smear
+    ///
+    pub fn fraction(&self, elapsed: i64) -> f64 {
+        let x = elapsed.clamp(0, self.duration) as f64 / self.duration as f64;
+        match self.shape {
+            Shape::Linear => x,
+            Shape::Cosine => (1.0 - (PI * x).cos()) / 2.0,
+        }
+    }
+
+    /// Get the smear schedule for every leap second in the list, as
+    /// an iterator of `(timestamp, offset)` pairs.
+    ///
+    /// Each smear has entries at its start and end, and every
+    /// [`Smear::step`][] seconds in between.
+    ///
+    pub fn schedule<'a>(
+        &'a self,
+        list: &'a LeapSecs,
+    ) -> impl Iterator<Item = (i64, f64)> + 'a {
+        let leaps = list.iter().filter_map(|leap| match leap.sign() {
+            Leap::Neg => Some((leap.mjd(), -1.0)),
+            Leap::Pos => Some((leap.mjd(), 1.0)),
+            Leap::Zero | Leap::Exp => None,
+        });
+        leaps.flat_map(move |(mjd, sign)| {
+            let start = Unix::from(mjd).0 - self.duration / 2;
+            let steps = (self.duration + self.step - 1) / self.step;
+            (0..=steps).map(move |i| {
+                let elapsed = (i * self.step).min(self.duration);
+                (start + elapsed, sign * self.fraction(elapsed))
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+    use smear::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test() {
+        let list = LeapSecs::from_str("6+6-999?").unwrap();
+        let cosine = Smear { shape: Shape::Cosine, duration: 1000, step: 300 };
+        let schedule: Vec<_> = cosine.schedule(&list).collect();
+        assert_eq!(10, schedule.len());
+        let midnight =
+            (Gregorian(1972, 7, 1).mjd() - UNIX_EPOCH_MJD) as i64 * 86400;
+        assert_eq!((midnight - 500, 0.0), schedule[0]);
+        assert_eq!((midnight + 500, 1.0), schedule[4]);
+        assert_eq!(-1.0, schedule[9].1);
+        assert!((cosine.fraction(500) - 0.5).abs() < 1e-9);
+        for pair in schedule[..5].windows(2) {
+            assert!(pair[0].0 < pair[1].0 && pair[0].1 < pair[1].1);
+        }
+    }
+}
@@ -0,0 +1,193 @@
+//! Test fixtures
+//! =============
+//!
+//! With the `testing` feature, this module provides canned leap
+//! second lists for testing code that handles leap seconds, so that
+//! you do not need to copy a list into your own tests. Each
+//! [`Fixture`][] has the list in each of the crate's formats, for
+//! example,
+//!
+//!     # use leapsecs::*;
+//!     let fixture = testing::historical_at(Gregorian(2017, 1, 1).mjd());
+//!     assert_eq!(fixture.list.expires(), Gregorian(2016, 12, 28).mjd());
+//!     assert_eq!(fixture.text, "6+6+12+12+12+12+12+12+12+\
+//!         18+12+12+24+30+24+12+18+12+12+18+18+18+84+36+42+36+17?");
+//!     assert_eq!(fixture.list, nist::read_str_lenient(&fixture.nist)?);
+//!     # Ok::<(), anyhow::Error>(())
+//!
+//! To test a leap second that has not happened, such as a negative
+//! one, [`hypothetical()`][] adds a made-up leap second to the real
+//! list.
+//!
+//! Apart from [`about_to_expire()`][], the fixtures do not depend on
+//! the system clock, but some of them have expired, so they must be
+//! parsed with the lenient functions.
+
+use crate::*;
+
+// the leap seconds up to 2017, with an expiry date far in the future
+const HISTORY: &str = history!("999?");
+
+// a made-up list with a negative leap second at the end of June 1981
+const NEGATIVE: &str = "6+6+12+12+12+12+12+12+12+18-999?";
+
+/// A leap second list in each of the formats supported by the crate
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Fixture {
+    /// The list itself
+    pub list: LeapSecs,
+    /// The compact text format
+    pub text: String,
+    /// The compact text format with explicit dates
+    pub dated: String,
+    /// The compact binary format
+    pub binary: Vec<u8>,
+    /// The NIST `leap-seconds.list` format
+    pub nist: String,
+    /// Comma-separated values
+    pub csv: String,
+    /// A TZif file like `right/UTC`
+    pub tzif: Vec<u8>,
+}
+
+impl From<LeapSecs> for Fixture {
+    fn from(list: LeapSecs) -> Fixture {
+        // the NIST format needs an updated date, which must not come
+        // from the system clock, so use the date of the last entry
+        // before the expiry date, which is never after it
+        let last = list.iter().rev().find(|leap| !leap.is_expiry());
+        let updated = list.updated().or_else(|| last.map(|leap| leap.mjd()));
+        let list = list.with_updated(updated.unwrap());
+        // the fixtures are always well-formed
+        let nist = nist::format(&list).unwrap();
+        let mut csv = Vec::new();
+        list.to_csv(&mut csv).unwrap();
+        Fixture {
+            text: list.to_string(),
+            dated: txt::dated::format(&list),
+            binary: Vec::from(&list),
+            nist,
+            csv: String::from_utf8(csv).unwrap(),
+            tzif: tzif::write(&list).unwrap(),
+            list,
+        }
+    }
+}
+
+// panic if one of the constants above is broken
+fn parse(text: &str) -> LeapSecs {
+    LeapSecs::from_str_lenient(text).unwrap()
+}
+
+/// The real list of leap seconds as it was known on `date`, expiring
+/// on the last 28th of a month on or before `date`.
+///
+/// See [`LeapSecs::truncate_after()`][] for the details. Panics if
+/// `date` is before 1972-02-28.
+///
+pub fn historical_at(date: MJD) -> Fixture {
+    Fixture::from(parse(HISTORY).truncate_after(date).unwrap())
+}
+
+/// The real list of leap seconds, with no more to come before it
+/// expires in 2100.
+///
+pub fn empty_future() -> Fixture {
+    Fixture::from(parse(HISTORY))
+}
+
+/// A made-up list with a negative leap second at the end of June
+/// 1981, expiring in 2064.
+///
+pub fn negative_leap() -> Fixture {
+    Fixture::from(parse(NEGATIVE))
+}
+
+/// The real list of leap seconds with a made-up leap second at the
+/// start of `date`, which must be between 2017 and 2100.
+///
+/// See [`LeapSecs::with_hypothetical()`][] for the details. The list
+/// expires in 2100, like [`empty_future()`][]. Fails if `date` is
+/// not the first of a month.
+///
+pub fn hypothetical(date: Gregorian, sign: Leap) -> Result<Fixture> {
+    Ok(Fixture::from(parse(HISTORY).with_hypothetical(date, sign)?))
+}
+
+/// The real list of leap seconds, expiring on the next 28th of a
+/// month after today, so that it has not expired but will within a
+/// month.
+///
+pub fn about_to_expire() -> Fixture {
+    about_to_expire_at(SystemClock)
+}
+
+/// Like [`about_to_expire()`][], but the list expires after the
+/// given [`Clock`][]'s date.
+///
+pub fn about_to_expire_at<C: Clock>(clock: C) -> Fixture {
+    let Gregorian(year, month, day) = Gregorian::from(clock.today());
+    let (year, month) = match (day < 28, month) {
+        (true, _) => (year, month),
+        (false, 12) => (year + 1, 1),
+        (false, _) => (year, month + 1),
+    };
+    historical_at(Gregorian(year, month, 28).mjd())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::testing::*;
+
+    #[test]
+    fn test() {
+        let future = hypothetical(Gregorian(2030, 1, 1), Leap::Neg).unwrap();
+        for fixture in &[
+            empty_future(),
+            negative_leap(),
+            about_to_expire(),
+            future.clone(),
+        ] {
+            let list = &fixture.list;
+            assert_eq!(
+                list,
+                &LeapSecs::from_str_lenient(&fixture.text).unwrap()
+            );
+            let binary = LeapSecs::try_from_bytes_lenient(&fixture.binary);
+            assert_eq!(list, &binary.unwrap());
+            assert_eq!(list, &nist::read_str_lenient(&fixture.nist).unwrap());
+            let dated = txt::dated::parse_lenient(&fixture.dated);
+            assert_eq!(list, &dated.unwrap());
+            assert_eq!(
+                list,
+                &LeapSecs::from_csv_lenient(&fixture.csv).unwrap()
+            );
+            assert_eq!(fixture.tzif, tzif::patch(&fixture.tzif, list).unwrap());
+        }
+        assert_eq!(Leap::Neg, future.list[28].sign());
+        assert_eq!(36, future.list[28].dtai().unwrap());
+        assert!(future.dated.ends_with(" 2017-01+ 2030-01- 2100-04?"));
+        assert!(future.csv.contains("\n2030-01-01,62502,4102444800,36,"));
+        let old = historical_at(Gregorian(2020, 1, 1).mjd()).list;
+        let late = old.with_hypothetical(Gregorian(2021, 1, 1), Leap::Pos);
+        assert_eq!(Gregorian(2021, 6, 28).mjd(), late.unwrap().expires());
+        assert!(hypothetical(Gregorian(2016, 7, 1), Leap::Neg).is_err());
+        assert!(hypothetical(Gregorian(2030, 1, 2), Leap::Neg).is_err());
+        assert_eq!(Gregorian(2100, 4, 28).mjd(), empty_future().list.expires());
+        assert_eq!(Leap::Neg, negative_leap().list[10].sign());
+        let old = historical_at(Gregorian(1980, 1, 1).mjd());
+        assert!(old.nist.starts_with("#\tupdated 1979-01-01\n"));
+        assert_eq!(Gregorian(1979, 1, 1).mjd(), old.list.updated().unwrap());
+
+        for &(today, expires) in &[
+            (Gregorian(2020, 3, 27), Gregorian(2020, 3, 28)),
+            (Gregorian(2020, 3, 28), Gregorian(2020, 4, 28)),
+            (Gregorian(2020, 12, 31), Gregorian(2021, 1, 28)),
+        ] {
+            let list = about_to_expire_at(today.mjd()).list;
+            assert_eq!(expires.mjd(), list.expires());
+            assert!(!list.is_expired_at(today.mjd()));
+            assert!(list.expires_within_at(32, today.mjd()));
+        }
+    }
+}
@@ -4,75 +4,394 @@
 //! This module implements a number of standard traits for the
 //! [`LeapSecs`][] type:
 //!
-//!   * [`std::str::FromStr`][] parses a leap second list in compact
+//!   * [`core::str::FromStr`][] parses a leap second list in compact
 //!     text format, returning
 //!     `Result<`[`LeapSecs`][crate::LeapSecs]`, `[`Error`][enum@Error]`>`.
 //!
-//!   * [`std::fmt::Display`][] prints a leap second list in compact
-//!     text format.
+//!   * [`core::fmt::Display`][] prints a leap second list in compact
+//!     text format. The alternate form `{:#}` prints a table with one
+//!     line per entry, giving its date, sign, DTAI, MJD, and NTP
+//!     timestamp.
 //!
-//!   * [`std::fmt::LowerHex`][] and [`std::fmt::UpperHex`][] print a
+//!   * [`core::fmt::LowerHex`][] and [`core::fmt::UpperHex`][] print a
 //!     hexdump of a leap second list in compact binary format. There
 //!     is no parser for the opposite conversion.
+//!
+//! [`TxtWriter`][] generates the compact text format as entries are
+//! pushed, without building a [`LeapSecs`][] first.
+//!
+//! The text may be followed by an optional checksum, which is
+//! described under [`LeapSecs::text_checksum()`][].
+//!
+//! [`LeapSecs::from_str_at()`][] is like `FromStr` but checks for
+//! expiry using the given [`Clock`][], and
+//! [`LeapSecs::from_str_lenient()`][] accepts expired lists.
+//! [`parse_with()`][] accepts whitespace and leading zeros, or
+//! insists on the canonical form checked by [`is_canonical()`][].
+//! [`parse()`][] is like `FromStr` but its [`TxtError`][] says where
+//! in the text the error was found.
+//!
+//! The [`dated`][] submodule has a variant of the format that gives
+//! the year and month of each entry instead of the gaps between them.
 
 use crate::*;
+use alloc::string::ToString;
+use core::fmt::Write;
 
-impl std::str::FromStr for LeapSecs {
+pub mod dated;
+
+impl core::str::FromStr for LeapSecs {
     type Err = Error;
 
     fn from_str(s: &str) -> Result<LeapSecs> {
-        let mut list = LeapSecs::builder();
-        let mut digits = 0;
-        let mut gap = 0;
-        for c in s.chars() {
-            enum What {
-                Zero,
-                Digit(i32),
-                Sign(Leap),
-                Other,
-            }
-            use What::*;
-
-            let what = match c {
-                '0' => Zero,
-                '1'..='9' => Digit(c as i32 - '0' as i32),
-                '-' => Sign(Leap::Neg),
-                '+' => Sign(Leap::Pos),
-                '?' => Sign(Leap::Exp),
-                _ => Other,
-            };
+        let list = parse_builder(s, ParseOptions::default())?.finish()?;
+        Ok(list.with_literal(Format::Text))
+    }
+}
 
-            match (digits, what) {
-                (0..=2, Digit(n)) => {
-                    digits += 1;
-                    gap = gap * 10 + n;
-                }
-                (1..=2, Zero) => {
-                    digits += 1;
-                    gap *= 10;
-                }
-                (1..=3, Sign(sign)) => {
-                    list.push_gap(gap, sign)?;
-                    digits = 0;
-                    gap = 0;
-                }
-                (0, _) => return Err(Error::FromStr("[1-9]", c)),
-                (1..=2, _) => return Err(Error::FromStr("[0-9?+-]", c)),
-                (3, _) => return Err(Error::FromStr("[?+-]", c)),
-                _ => panic!("screwed up counting digits"),
-            };
+/// An error from [`parse()`][], with its position in the text.
+///
+/// It converts into the underlying [`enum@Error`][] with `?`.
+///
+#[derive(thiserror::Error, Debug)]
+#[error("{error} at offset {offset}")]
+pub struct TxtError {
+    /// What went wrong
+    pub error: Error,
+    /// The byte offset of the character where the error was found, or
+    /// the length of the text if it ended too soon or the list failed
+    /// a check after parsing
+    pub offset: usize,
+    /// The number of entries in the list before the error, counting
+    /// the start of UTC in 1972
+    pub entries: usize,
+}
+
+impl From<TxtError> for Error {
+    fn from(err: TxtError) -> Error {
+        err.error
+    }
+}
+
+/// A syntax error in the compact text format, which is the
+/// [`source()`][std::error::Error::source] of an [`Error::Txt`][].
+///
+/// Each variant has the byte offset in the text where the error was
+/// found.
+///
+#[derive(thiserror::Error, Clone, Debug, Eq, PartialEq)]
+pub enum TxtParseError {
+    /// A gap has more digits than [`ParseOptions::wide_gaps`][] allows
+    #[error("gap has too many digits at offset {offset}")]
+    GapTooLong {
+        /// The offset of the first extra digit
+        offset: usize,
+    },
+    /// The text ends without an expiry date
+    #[error("missing expiry date at offset {offset}")]
+    MissingExpiry {
+        /// The length of the text
+        offset: usize,
+    },
+    /// The text ends with a gap that has no sign after it
+    #[error("gap without a sign at offset {offset}")]
+    TrailingDigits {
+        /// The length of the text
+        offset: usize,
+    },
+    /// A character that is not allowed here
+    #[error("expected {expected}, found {found:?} at offset {offset}")]
+    UnexpectedChar {
+        /// The offset of the character
+        offset: usize,
+        /// What was allowed, like a regex character class
+        expected: &'static str,
+        /// The character that was found instead
+        found: char,
+    },
+}
+
+impl TxtParseError {
+    /// Get the byte offset in the text where the error was found
+    pub fn offset(&self) -> usize {
+        match *self {
+            TxtParseError::GapTooLong { offset }
+            | TxtParseError::MissingExpiry { offset }
+            | TxtParseError::TrailingDigits { offset }
+            | TxtParseError::UnexpectedChar { offset, .. } => offset,
+        }
+    }
+}
+
+fn unexpected(offset: usize, expected: &'static str, found: char) -> Error {
+    Error::Txt(TxtParseError::UnexpectedChar { offset, expected, found })
+}
+
+/// Parse a leap second list in compact text format, like
+/// [`core::str::FromStr`][], but on failure say where the problem
+/// is, so that tools can point at it:
+///
+///     # use leapsecs::*;
+///     let text = "6+6+12x+999?";
+///     let err = txt::parse(text).unwrap_err();
+///     assert_eq!((err.offset, err.entries), (6, 3));
+///     assert_eq!(format!("{}\n{:>2$}", text, "^", err.offset + 1),
+///                "6+6+12x+999?\n      ^");
+///
+pub fn parse(s: &str) -> core::result::Result<LeapSecs, TxtError> {
+    let list = parse_builder(s, ParseOptions::default())?;
+    let entries = list.entries().len();
+    match list.finish() {
+        Ok(list) => Ok(list.with_literal(Format::Text)),
+        Err(error) => Err(TxtError { error, offset: s.len(), entries }),
+    }
+}
+
+/// Options for [`parse_with()`][] that relax the compact text syntax.
+///
+/// The default options are strict, the same as [`core::str::FromStr`][].
+///
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct ParseOptions {
+    /// Allow whitespace, including newlines, before and after each
+    /// gap, so that a long list can be wrapped in a config file or
+    /// split into DNS TXT chunks.
+    pub whitespace: bool,
+    /// Allow leading zeros on each gap, like `06+`.
+    pub leading_zeros: bool,
+    /// Reject anything that is not in canonical form, as described
+    /// under [`is_canonical()`][], with [`Error::NonCanonical`][].
+    /// This overrides the whitespace and leading zeros options.
+    pub canonical: bool,
+    /// Allow gaps of 4 digits, up to 9999 months, for lists made
+    /// with [`LeapSecBuilder::with_max_gap()`][].
+    pub wide_gaps: bool,
+}
+
+/// Parse a leap second list in compact text format, with more
+/// relaxed syntax than [`core::str::FromStr`][] according to the
+/// `options`. For example,
+///
+///     # use leapsecs::*;
+///     # use std::str::FromStr;
+///     let options = txt::ParseOptions {
+///         whitespace: true,
+///         leading_zeros: true,
+///         ..Default::default()
+///     };
+///     let list = txt::parse_with("06+ 06+\n 999?\n", options)?;
+///     assert_eq!(list, LeapSecs::from_str("6+6+999?")?);
+///     # Ok::<(), Error>(())
+///
+/// The list is checked as if by [`LeapSecBuilder::finish()`][].
+///
+pub fn parse_with(s: &str, options: ParseOptions) -> Result<LeapSecs> {
+    Ok(parse_builder(s, options)?.finish()?.with_literal(Format::Text))
+}
+
+type Parsed<T> = core::result::Result<T, TxtError>;
+
+/// Returns true if `s` is a leap second list in the canonical compact
+/// text format.
+///
+/// There is exactly one canonical spelling of each list, which is
+/// what [`core::fmt::Display`][] prints: no whitespace, no leading
+/// zeros, and no checksum. For example,
+///
+///     # use leapsecs::*;
+///     assert!(txt::is_canonical("6+6+999?"));
+///     assert!(!txt::is_canonical("6+06+999?"));
+///     assert!(!txt::is_canonical("6+6+999?#6060"));
+///
+/// This does not check whether the list has expired.
+///
+pub fn is_canonical(s: &str) -> bool {
+    let options = ParseOptions { canonical: true, ..Default::default() };
+    parse_builder(s, options).is_ok()
+}
+
+fn parse_builder(s: &str, options: ParseOptions) -> Parsed<LeapSecBuilder> {
+    if options.canonical {
+        return parse_canonical(s, options);
+    }
+    let (gaps, sum) = match s.split_once('#') {
+        Some((gaps, sum)) if options.whitespace => {
+            let skip = gaps.len() + 1 + sum.len() - sum.trim_start().len();
+            (gaps, Some((skip, sum.trim())))
+        }
+        Some((gaps, sum)) => (gaps, Some((gaps.len() + 1, sum))),
+        None => (s, None),
+    };
+    let list = parse_gaps(gaps, options)?;
+    let entries = list.entries().len();
+    if let Some((offset, sum)) = sum {
+        let at = |error| TxtError { error, offset, entries };
+        let sum = parse_checksum(sum, offset)
+            .map_err(|(offset, error)| TxtError { offset, ..at(error) })?;
+        let calculated = list.clone().finish_lenient().map_err(at)?;
+        let calculated = calculated.text_checksum();
+        if sum != calculated {
+            return Err(at(Error::TextChecksum(sum, calculated)));
+        }
+    }
+    Ok(list)
+}
+
+// parse leniently, then check that printing the list gives the same
+// text, so the error points at the first difference
+fn parse_canonical(s: &str, options: ParseOptions) -> Parsed<LeapSecBuilder> {
+    let lenient = ParseOptions {
+        whitespace: true,
+        leading_zeros: true,
+        canonical: false,
+        ..options
+    };
+    let list = parse_builder(s, lenient)?;
+    let entries = list.entries().len();
+    let at = |error, offset| TxtError { error, offset, entries };
+    let canonical = list.clone().finish_lenient().map_err(|e| at(e, 0))?;
+    let canonical = canonical.to_string();
+    match s.bytes().zip(canonical.bytes()).position(|(a, b)| a != b) {
+        Some(offset) => Err(at(Error::NonCanonical, offset)),
+        None if s.len() != canonical.len() => {
+            Err(at(Error::NonCanonical, canonical.len()))
         }
+        None => Ok(list),
+    }
+}
 
-        if digits != 0 {
-            Err(Error::Truncated)
-        } else {
-            list.finish()
+// the checksum starts at `base` in the whole text, and on error,
+// return the offset as well
+fn parse_checksum(
+    s: &str,
+    base: usize,
+) -> core::result::Result<u16, (usize, Error)> {
+    let mut sum = 0;
+    for (i, c) in s.char_indices() {
+        let at = base + i;
+        match (i, c.to_digit(16)) {
+            (0..=3, Some(n)) => sum = sum << 4 | n as u16,
+            (0..=3, None) => return Err((at, unexpected(at, "[0-9a-f]", c))),
+            _ => return Err((at, unexpected(at, "end of checksum", c))),
         }
     }
+    if s.len() != 4 {
+        return Err((base + s.len(), Error::Truncated));
+    }
+    Ok(sum)
 }
 
-impl std::fmt::Display for LeapSecs {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+fn parse_gaps(s: &str, options: ParseOptions) -> Parsed<LeapSecBuilder> {
+    let (width, mut list) = if options.wide_gaps {
+        (4, LeapSecs::builder().with_max_gap(9999))
+    } else {
+        (3, LeapSecs::builder())
+    };
+    let mut digits = 0;
+    let mut zeros = false;
+    let mut gap = 0;
+    for (offset, c) in s.char_indices() {
+        let at = |error, list: &LeapSecBuilder| {
+            let entries = list.entries().len();
+            TxtError { error, offset, entries }
+        };
+        enum What {
+            Zero,
+            Digit(i32),
+            Sign(Leap),
+            Space,
+            Other,
+        }
+        use What::*;
+
+        let what = match c {
+            '0' => Zero,
+            '1'..='9' => Digit(c as i32 - '0' as i32),
+            '-' => Sign(Leap::Neg),
+            '+' => Sign(Leap::Pos),
+            '?' => Sign(Leap::Exp),
+            _ if c.is_whitespace() && options.whitespace => Space,
+            _ => Other,
+        };
+
+        match (digits, what) {
+            (0, Space) if !zeros => (),
+            (0, Zero) if options.leading_zeros => zeros = true,
+            (0, Sign(sign)) if zeros => {
+                // a gap of zero, which push_gap() will reject
+                list.push_gap(0, sign).map_err(|e| at(e, &list))?;
+            }
+            (_, Digit(n)) if digits < width => {
+                digits += 1;
+                gap = gap * 10 + n;
+            }
+            (1.., Zero) if digits < width => {
+                digits += 1;
+                gap *= 10;
+            }
+            (1.., Sign(sign)) => {
+                list.push_gap(gap, sign).map_err(|e| at(e, &list))?;
+                digits = 0;
+                zeros = false;
+                gap = 0;
+            }
+            (0, _) => return Err(at(unexpected(offset, "[1-9]", c), &list)),
+            (_, _) if digits < width => {
+                return Err(at(unexpected(offset, "[0-9?+-]", c), &list))
+            }
+            (_, Zero | Digit(_)) => {
+                let error = TxtParseError::GapTooLong { offset };
+                return Err(at(Error::Txt(error), &list));
+            }
+            (_, _) => return Err(at(unexpected(offset, "[?+-]", c), &list)),
+        };
+    }
+
+    let offset = s.len();
+    let error = if digits != 0 || zeros {
+        TxtParseError::TrailingDigits { offset }
+    } else if list.check_finished().is_err() {
+        TxtParseError::MissingExpiry { offset }
+    } else {
+        return Ok(list);
+    };
+    let entries = list.entries().len();
+    Err(TxtError { error: Error::Txt(error), offset, entries })
+}
+
+// one line of the `{:#}` table, like `LeapSec`'s Display
+// but with fixed-width columns
+fn table_line(
+    f: &mut core::fmt::Formatter<'_>,
+    leap: &LeapSec,
+) -> core::fmt::Result {
+    let sign = match leap.sign() {
+        Leap::Zero => "  ",
+        Leap::Neg => "-1",
+        Leap::Pos => "+1",
+        Leap::Exp => "??",
+    };
+    write!(f, "{} {}", leap.date(), sign)?;
+    match leap.dtai() {
+        Ok(dtai) => write!(f, " DTAI {:3}", dtai)?,
+        Err(_) => write!(f, "         ")?,
+    }
+    let mjd = leap.mjd();
+    let day = mjd - MJD::from(0);
+    write!(f, "  MJD {}  NTP {}", day, NTP::from(mjd))
+}
+
+impl core::fmt::Display for LeapSecs {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        if f.alternate() {
+            for (i, leap) in self.iter().enumerate() {
+                if i > 0 {
+                    writeln!(f)?;
+                }
+                table_line(f, leap)?;
+            }
+            return Ok(());
+        }
         for leap in self {
             match leap.sign() {
                 Leap::Zero => (),
@@ -85,8 +404,80 @@ impl std::fmt::Display for LeapSecs {
     }
 }
 
-impl std::fmt::LowerHex for LeapSecs {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+/// A streaming encoder for the compact text format.
+///
+/// Entries are pushed as with a [`LeapSecBuilder`][], and with the
+/// same checks, but the text is written to a [`core::fmt::Write`][]
+/// object as soon as each entry is complete, without keeping the
+/// whole list.
+///
+///     # use leapsecs::*;
+///     let mut writer = txt::TxtWriter::new(String::new());
+///     writer.push_date(Gregorian(1972, 1, 1), 10)?;
+///     writer.push_date(Gregorian(1972, 7, 1), 11)?;
+///     writer.push_exp(Gregorian(1972, 12, 28))?;
+///     assert_eq!(writer.finish()?, "6+5?");
+///     # Ok::<(), Error>(())
+///
+#[derive(Clone, Debug)]
+pub struct TxtWriter<W> {
+    out: W,
+    list: Window,
+}
+
+impl<W: Write> TxtWriter<W> {
+    /// Get a new [`TxtWriter`][] that writes to `out`
+    pub fn new(out: W) -> TxtWriter<W> {
+        TxtWriter { out, list: Window::default() }
+    }
+
+    /// Add an entry to the list, like [`LeapSecBuilder::push_gap()`][]
+    ///
+    pub fn push_gap(&mut self, gap: i32, sign: Leap) -> Result<()> {
+        self.list.push_gap(gap, sign)?;
+        self.encode()
+    }
+
+    /// Add an entry to the list, like [`LeapSecBuilder::push_date()`][]
+    ///
+    pub fn push_date(&mut self, date: Gregorian, dtai: i16) -> Result<()> {
+        self.list.push_date(date, dtai)?;
+        self.encode()
+    }
+
+    /// Add the expiry date, like [`LeapSecBuilder::push_exp()`][]
+    ///
+    pub fn push_exp(&mut self, date: Gregorian) -> Result<()> {
+        self.list.push_exp(date)?;
+        self.encode()
+    }
+
+    /// Return the output object.
+    ///
+    /// Fails with [`Error::Truncated`][] if the list does not end
+    /// with an expiry date.
+    ///
+    pub fn finish(self) -> Result<W> {
+        self.list.check_finished()?;
+        Ok(self.out)
+    }
+
+    fn encode(&mut self) -> Result<()> {
+        if let Some(leap) = self.list.finished() {
+            let sign = match leap.sign() {
+                Leap::Zero => return Ok(()),
+                Leap::Neg => '-',
+                Leap::Pos => '+',
+                Leap::Exp => '?',
+            };
+            write!(self.out, "{}{}", leap.gap(), sign)?;
+        }
+        Ok(())
+    }
+}
+
+impl core::fmt::LowerHex for LeapSecs {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         for byte in self.iter_bytes() {
             write!(f, "{:02x}", byte)?
         }
@@ -94,8 +485,8 @@ impl std::fmt::LowerHex for LeapSecs {
     }
 }
 
-impl std::fmt::UpperHex for LeapSecs {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl core::fmt::UpperHex for LeapSecs {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         for byte in self.iter_bytes() {
             write!(f, "{:02X}", byte)?
         }
@@ -103,21 +494,210 @@ impl std::fmt::UpperHex for LeapSecs {
     }
 }
 
-#[cfg(test)]
+// CRC-16/CCITT-FALSE
+pub(crate) fn crc16(bytes: impl Iterator<Item = u8>) -> u16 {
+    let mut crc = 0xFFFF;
+    for byte in bytes {
+        crc ^= (byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 { crc << 1 ^ 0x1021 } else { crc << 1 };
+        }
+    }
+    crc
+}
+
+impl LeapSecs {
+    /// Get the checksum used by the compact text format.
+    ///
+    /// The checksum is optional. It is written after the list as a `#`
+    /// followed by four hex digits, for example,
+    ///
+    ///     # use leapsecs::*;
+    ///     # use std::str::FromStr;
+    ///     let list = LeapSecs::from_str("6+6+999?")?;
+    ///     let text = format!("{}#{:04x}", list, list.text_checksum());
+    ///     assert_eq!(text, "6+6+999?#6060");
+    ///     assert_eq!(list, LeapSecs::from_str(&text)?);
+    ///     # Ok::<(), Error>(())
+    ///
+    /// When the checksum is present, the parser checks that it matches
+    /// the list and fails with [`Error::TextChecksum`][] if it does
+    /// not. This is to detect mangled copies of the list.
+    ///
+    /// The checksum is the CRC-16/CCITT-FALSE of the compact binary
+    /// format.
+    ///
+    pub fn text_checksum(&self) -> u16 {
+        crc16(self.iter_bytes())
+    }
+
+    /// Parse a leap second list in compact text format, checking for
+    /// expiry using the given [`Clock`][].
+    ///
+    pub fn from_str_at<C: Clock>(s: &str, clock: C) -> Result<LeapSecs> {
+        let list = parse_builder(s, ParseOptions::default())?;
+        Ok(list.finish_at(clock)?.with_literal(Format::Text))
+    }
+
+    /// Parse a leap second list in compact text format, without
+    /// failing if it has expired. See [`LeapSecs::was_expired()`][].
+    ///
+    pub fn from_str_lenient(s: &str) -> Result<LeapSecs> {
+        let list = parse_builder(s, ParseOptions::default())?;
+        Ok(list.finish_lenient()?.with_literal(Format::Text))
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
 mod test {
     use crate::*;
     use std::str::FromStr;
 
     #[test]
     fn test() {
-        let text = "6+6+12+12+12+12+12+12+12+18+12+12+24+30+24+\
-                    12+18+12+12+18+18+18+84+36+42+36+18+59?";
-        let parsed = LeapSecs::from_str(text).unwrap();
+        let text = history!("59?");
+        let today = Gregorian(2021, 1, 1).mjd();
+        let parsed = LeapSecs::from_str_at(text, today).unwrap();
         let output = format!("{}", parsed);
         assert_eq!(text, output);
+        let lenient = LeapSecs::from_str_lenient(text).unwrap();
+        assert!(lenient.was_expired());
+        assert!(!parsed.was_expired());
+        assert_eq!(lenient, parsed);
         let input = "9+9-99+99-999+999?";
-        let parsed = LeapSecs::from_str(&input).unwrap();
+        let parsed = LeapSecs::from_str(input).unwrap();
         let output = format!("{}", parsed);
         assert_eq!(input, output);
+        let mut writer = txt::TxtWriter::new(String::new());
+        for leap in parsed.iter() {
+            match leap.dtai() {
+                Ok(dtai) => writer.push_date(leap.date(), dtai).unwrap(),
+                Err(_) => writer.push_exp(leap.date()).unwrap(),
+            }
+        }
+        assert_eq!(input, writer.finish().unwrap());
+        let table = format!("{:#}", LeapSecs::from_str("6+6-999?").unwrap());
+        let want = "\
+            1972-01-01    DTAI  10  MJD 41317  NTP 2272060800\n\
+            1972-07-01 +1 DTAI  11  MJD 41499  NTP 2287785600\n\
+            1973-01-01 -1 DTAI  10  MJD 41683  NTP 2303683200\n\
+            2056-04-28 ??           MJD 72116  NTP 4933094400";
+        assert_eq!(want, table);
+    }
+
+    #[test]
+    fn checksum() {
+        assert_eq!(0x29B1, super::crc16(b"123456789".iter().copied()));
+        let input = "9+9-99+99-999+999?";
+        let list = LeapSecs::from_str(input).unwrap();
+        let sum = format!("{}#{:04X}", list, list.text_checksum());
+        assert_eq!(list, LeapSecs::from_str(&sum).unwrap());
+        let bad = sum.replace("9-99", "9-98");
+        assert!(matches!(
+            LeapSecs::from_str(&bad),
+            Err(Error::TextChecksum(..))
+        ));
+        assert!(LeapSecs::from_str(&sum[..sum.len() - 1]).is_err());
+        assert!(LeapSecs::from_str(&format!("{}0", sum)).is_err());
+    }
+
+    #[test]
+    fn options() {
+        let strict = txt::ParseOptions::default();
+        let both = txt::ParseOptions {
+            whitespace: true,
+            leading_zeros: true,
+            ..strict
+        };
+        let list = LeapSecs::from_str("6+6+999?").unwrap();
+        let sum = format!("{:04x}", list.text_checksum());
+        let text = format!(" 6+\r\n06+\t0999? # {}\n", sum);
+        assert_eq!(list, txt::parse_with(&text, both).unwrap());
+        assert!(txt::parse_with(&text, strict).is_err());
+        assert!(LeapSecs::from_str("06+6+999?").is_err());
+        assert!(LeapSecs::from_str("6+ 6+999?").is_err());
+        let zeros = txt::ParseOptions { leading_zeros: true, ..strict };
+        assert!(txt::parse_with("6+6+999? ", zeros).is_err());
+        assert!(matches!(
+            txt::parse_with("6+00+999?", zeros),
+            Err(Error::Gap(..))
+        ));
+        let space = txt::ParseOptions { whitespace: true, ..strict };
+        assert!(txt::parse_with("6+06+999?", space).is_err());
+        assert!(txt::parse_with("6+ 6 +999?", space).is_err());
+        assert!(txt::parse_with("6+6+99 9?", space).is_err());
+
+        let canonical = txt::ParseOptions { canonical: true, ..both };
+        assert_eq!(list, txt::parse_with("6+6+999?", canonical).unwrap());
+        for &(text, offset) in &[
+            ("6+06+999?", 2),
+            (" 6+6+999?", 0),
+            ("6+6+999?\n", 8),
+            ("6+6+999?#6060", 8),
+        ] {
+            let err = txt::parse_with(text, canonical).unwrap_err();
+            assert!(matches!(err, Error::NonCanonical), "{}", text);
+            assert!(!txt::is_canonical(text));
+            let err = super::parse_builder(text, canonical).unwrap_err();
+            assert_eq!(offset, err.offset, "{}", text);
+        }
+        assert!(txt::is_canonical("6+6+12?"));
+
+        let wide = txt::ParseOptions { wide_gaps: true, ..strict };
+        assert!(LeapSecs::from_str_lenient("6+6+1200?").is_err());
+        let list = txt::parse_with("6+6+1200?", wide).unwrap();
+        assert_eq!(1200, list[list.len() - 1].gap());
+        assert_eq!("6+6+1200?", list.to_string());
+        let sum = format!("{}#{:04X}", list, list.text_checksum());
+        assert_eq!(list, txt::parse_with(&sum, wide).unwrap());
+        assert!(txt::parse_with("6+6+12000?", wide).is_err());
+        let canonical = txt::ParseOptions { canonical: true, ..wide };
+        assert!(txt::parse_with("6+6+1200?", canonical).is_ok());
+    }
+
+    #[test]
+    fn positions() {
+        for &(text, offset, entries) in &[
+            ("x", 0, 0),
+            ("6+6+", 4, 3),
+            ("6+6+99", 6, 3),
+            ("6+0+999?", 2, 2),
+            ("6+1000?", 5, 2),
+            ("6+6+999?#12x4", 11, 4),
+            ("6+6+999?#123", 12, 4),
+            ("6+6+999?#1234", 9, 4),
+            ("6+6+12?", 7, 4),
+        ] {
+            let err = txt::parse(text).map(|_| ()).unwrap_err();
+            assert_eq!(
+                (offset, entries),
+                (err.offset, err.entries),
+                "{}",
+                text
+            );
+        }
+        let list = txt::parse("6+6+999?#6060").unwrap();
+        assert_eq!(list, LeapSecs::from_str("6+6+999?").unwrap());
+        let err = txt::parse("6+6-6+").unwrap_err();
+        let missing = txt::TxtParseError::MissingExpiry { offset: 6 };
+        assert!(matches!(&err.error, Error::Txt(e) if *e == missing));
+        assert!(matches!(Error::from(err), Error::Txt(_)));
+        for &(text, offset) in &[
+            ("6+6+99", 6),
+            ("6+1000?", 5),
+            ("6+6+999?#12x4", 11),
+            ("6+6+9x9?", 5),
+        ] {
+            match LeapSecs::from_str(text) {
+                Err(Error::Txt(err)) => assert_eq!(offset, err.offset()),
+                other => panic!("{} {:?}", text, other),
+            }
+        }
+        let err = LeapSecs::from_str("6+6+9x9?").unwrap_err();
+        let source = std::error::Error::source(&err).unwrap();
+        assert_eq!(
+            "expected [0-9?+-], found 'x' at offset 5",
+            source.to_string()
+        );
     }
 }
@@ -0,0 +1,142 @@
+//! Compact text format with explicit dates
+//! =======================================
+//!
+//! The gaps in the compact text format are hard to check by eye, so
+//! this variant gives the year and month of each entry instead. Each
+//! leap second is written as the month in which the new DTAI takes
+//! effect, followed by its sign, and the list ends with the month of
+//! its expiry date followed by `?`, for example,
+//!
+//!     # use leapsecs::*;
+//!     # use std::str::FromStr;
+//!     let list = LeapSecs::from_str("6+6+12+999?")?;
+//!     let text = txt::dated::format(&list);
+//!     assert_eq!(text, "1972-07+ 1973-01+ 1974-01+ 2057-04?");
+//!     assert_eq!(list, txt::dated::parse(&text)?);
+//!     # Ok::<(), Error>(())
+//!
+//! Entries are separated by spaces when formatted, and by any
+//! whitespace when parsed, so a list can be split across lines.
+//! Like the gap-based format, the list implicitly starts in 1972
+//! with DTAI 10, and expires on the 28th of the last month.
+
+use crate::*;
+use alloc::string::String;
+use core::fmt::Write;
+
+const EXPECTED: &str = "YYYY-MM followed by +, -, or ?";
+
+/// Format a list in the dated text format.
+pub fn format(list: &LeapSecs) -> String {
+    let mut out = String::new();
+    for leap in list.iter().skip(1) {
+        let sign = match leap.sign() {
+            Leap::Neg => '-',
+            Leap::Pos => '+',
+            Leap::Zero | Leap::Exp => '?',
+        };
+        let Gregorian(year, month, _) = leap.date();
+        if !out.is_empty() {
+            out.push(' ');
+        }
+        // writing to a String cannot fail
+        write!(out, "{:04}-{:02}{}", year, month, sign).unwrap();
+    }
+    out
+}
+
+/// Parse a list in the dated text format, checking it as if by
+/// [`LeapSecBuilder::finish()`][].
+///
+pub fn parse(s: &str) -> Result<LeapSecs> {
+    parse_builder(s)?.finish()
+}
+
+/// Parse a list in the dated text format, without failing if it has
+/// expired. See [`LeapSecs::was_expired()`][].
+///
+pub fn parse_lenient(s: &str) -> Result<LeapSecs> {
+    parse_builder(s)?.finish_lenient()
+}
+
+/// Parse a list in the dated text format into a [`LeapSecBuilder`][]
+/// without checking it.
+///
+/// Fails with [`Error::FromStr`][] if an entry is malformed, with
+/// [`Error::Date`][] if its month is out of range, and with
+/// [`Error::Gap`][] if the entries are out of order.
+///
+pub fn parse_builder(s: &str) -> Result<LeapSecBuilder> {
+    let mut list = LeapSecs::builder();
+    let mut prev = 0;
+    for word in s.split_whitespace() {
+        let (month, sign) = parse_entry(word)?;
+        list.push_gap(month - prev, sign)?;
+        prev = month;
+    }
+    Ok(list)
+}
+
+// get the month number since 1972 and the sign of one entry
+fn parse_entry(word: &str) -> Result<(i32, Leap)> {
+    let bad = |c| Error::FromStr(EXPECTED, c);
+    let last = word.chars().last().unwrap_or(' ');
+    let sign = match last {
+        '-' => Leap::Neg,
+        '+' => Leap::Pos,
+        '?' => Leap::Exp,
+        _ => return Err(bad(last)),
+    };
+    let date = &word[..word.len() - 1];
+    if let Some(c) = date.chars().find(|&c| c != '-' && !c.is_ascii_digit()) {
+        return Err(bad(c));
+    }
+    let (year, month) = match date.split_once('-') {
+        Some((year, month)) if year.len() == 4 && month.len() == 2 => {
+            let number = |n: &str| n.parse().map_err(|_| bad('-'));
+            (number(year)?, number(month)?)
+        }
+        _ => return Err(bad(last)),
+    };
+    if !(1..=12).contains(&month) {
+        return Err(Error::Date(Gregorian(year, month, 1)));
+    }
+    Ok(((year - 1972) * 12 + month - 1, sign))
+}
+
+#[cfg(all(test, feature = "std"))]
+mod test {
+    use crate::txt::dated;
+    use crate::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test() {
+        let text = history!("999?");
+        let list = LeapSecs::from_str(text).unwrap();
+        let dated = dated::format(&list);
+        assert!(dated.starts_with("1972-07+ 1973-01+ 1974-01+ "));
+        assert!(dated.ends_with(" 2012-07+ 2015-07+ 2017-01+ 2100-04?"));
+        assert_eq!(list, dated::parse(&dated).unwrap());
+        let lines = dated.replace(' ', "\n");
+        assert_eq!(list, dated::parse(&lines).unwrap());
+
+        let negative = LeapSecs::from_str_lenient("6+6-5?").unwrap();
+        assert_eq!("1972-07+ 1973-01- 1973-06?", dated::format(&negative));
+        assert!(dated::parse("1972-07+ 1973-01- 1973-06?").is_err());
+        let lenient = dated::parse_lenient("1972-07+ 1973-01- 1973-06?");
+        assert_eq!(negative, lenient.unwrap());
+
+        for (bad, want) in &[
+            ("1972-07 1973-06?", "FromStr"),
+            ("1972-7+ 1973-06?", "FromStr"),
+            ("1972/07+ 1973-06?", "FromStr"),
+            ("1972-13+ 1973-06?", "Date"),
+            ("1973-01+ 1972-07+ 1973-06?", "Gap"),
+        ] {
+            let err = dated::parse_lenient(bad).unwrap_err();
+            assert!(format!("{:?}", err).starts_with(want), "{}", bad);
+        }
+        assert!(dated::parse_lenient("").is_err());
+    }
+}
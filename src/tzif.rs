@@ -0,0 +1,413 @@
+//! TZif leap second records
+//! ========================
+//!
+//! The `right/` time zones in the tz database are TZif files (RFC 8536)
+//! whose clock counts leap seconds, with a table of leap second
+//! records saying when each correction takes effect. This module
+//! makes those records from a [`LeapSecs`][] list, so that `right/`
+//! files can be updated from a freshly fetched list without the tzdb
+//! toolchain.
+//!
+//!   * [`write()`][] makes a complete TZif version 2 file equivalent
+//!     to `right/UTC`, as made by `zic -L leapseconds`.
+//!
+//!   * [`patch()`][] replaces the leap second records in an existing
+//!     TZif file, and moves its transitions to match.
+//!
+//!   * [`leap_records()`][] gets the raw records.
+//!
+//!   * [`LeapSecs::to_tzdata_posix_string()`][] summarizes the list
+//!     for consumers that only know about POSIX time.
+//!
+//! For example,
+//!
+//!     # use leapsecs::*;
+//!     # use std::str::FromStr;
+//!     let list = LeapSecs::from_str("6+6+12+999?")?;
+//!     let records = tzif::leap_records(&list);
+//!     assert_eq!(records, [(78796800, 1), (94694401, 2), (126230402, 3)]);
+//!     let utc = tzif::write(&list)?;
+//!     assert_eq!(&utc[..5], b"TZif2");
+//!     # Ok::<(), Error>(())
+//!
+//! Like `zic`, these files have a no-op transition when the list
+//! expires, and an empty TZ string footer, which means that local
+//! time is unknown after the expiry. [`patch()`][] does not extend
+//! a file's time zone rules, so a `right/` file for a zone with
+//! daylight saving time still stops at the expiry date of the list
+//! it was made with; [`patch()`][] fixes the leap seconds before
+//! that.
+
+use crate::*;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::fmt::Write;
+
+// the length of the fixed-size part of a header
+const HEADER: usize = 44;
+
+// the length of a local time type record
+const TTINFO: usize = 6;
+
+/// Get the leap second records for a TZif file, as pairs of the
+/// time the correction takes effect and the total correction.
+///
+/// Times count seconds since 1970 including earlier leap seconds,
+/// like the clock in a `right/` time zone. A positive leap second
+/// takes effect at the leap second itself, and a negative one at
+/// the second that is skipped. Corrections are DTAI − 10.
+///
+pub fn leap_records(list: &LeapSecs) -> Vec<(i64, i32)> {
+    posix_records(list)
+        .into_iter()
+        .scan(0, |prev, (posix, corr)| {
+            let time = posix + i64::from(*prev);
+            *prev = corr;
+            Some((time, corr))
+        })
+        .collect()
+}
+
+// the POSIX time when each correction takes effect
+fn posix_records(list: &LeapSecs) -> Vec<(i64, i32)> {
+    list.iter()
+        .filter_map(|leap| {
+            let skip = match leap.sign() {
+                Leap::Pos => 0,
+                Leap::Neg => 1,
+                Leap::Zero | Leap::Exp => return None,
+            };
+            let posix = Unix::from(leap.mjd()).0 - skip;
+            Some((posix, i32::from(leap.dtai().ok()? - 10)))
+        })
+        .collect()
+}
+
+// the correction in effect at `time`, given records sorted by time
+fn correction(records: &[(i64, i32)], time: i64) -> i32 {
+    let i = records.partition_point(|&(when, _)| when <= time);
+    i.checked_sub(1).map_or(0, |i| records[i].1)
+}
+
+/// Make a TZif version 2 file for UTC with leap seconds, like
+/// `right/UTC` in the tz database.
+///
+/// Times after 2038 are left out of the version 1 part of the file,
+/// which has 32-bit times.
+///
+pub fn write(list: &LeapSecs) -> Result<Vec<u8>> {
+    let leaps = leap_records(list);
+    let expires = Unix::from(list.expires()).0;
+    let expires = expires + i64::from(correction(&leaps, expires));
+    let block = Block {
+        times: alloc::vec![expires],
+        types: alloc::vec![0],
+        ttinfo: alloc::vec![0; TTINFO],
+        chars: b"UTC\0".to_vec(),
+        leaps,
+        isstd: Vec::new(),
+        isut: Vec::new(),
+    };
+    let mut out = Vec::new();
+    block.v1().write(&mut out, b'2', 4)?;
+    block.write(&mut out, b'2', 8)?;
+    out.extend_from_slice(b"\n\n");
+    Ok(out)
+}
+
+/// Replace the leap second records in a TZif file with the ones
+/// from `list`, and move its transitions to account for any
+/// difference.
+///
+/// The file must be version 2 or later. Times after 2038 are left
+/// out of the version 1 part of the file, like [`write()`][]. Fails
+/// with [`Error::Tzif`][] if it is malformed.
+///
+pub fn patch(tzif: &[u8], list: &LeapSecs) -> Result<Vec<u8>> {
+    let version = match tzif.get(4) {
+        Some(&version) if version >= b'2' => version,
+        Some(b'\0') => return Err(Error::Tzif("version 1 is not supported")),
+        _ => return Err(Error::Tzif("unknown version")),
+    };
+    let (v1, rest) = Block::read(tzif, 4)?;
+    let (v2, footer) = Block::read(rest, 8)?;
+    if footer.first() != Some(&b'\n') {
+        return Err(Error::Tzif("missing footer"));
+    }
+    let old = v2.leaps.clone();
+    let new = leap_records(list);
+    let posix = posix_records(list);
+    let mut out = Vec::new();
+    for (mut block, size) in alloc::vec![(v1, 4), (v2, 8)] {
+        for time in block.times.iter_mut() {
+            *time = time
+                .checked_sub(i64::from(correction(&old, *time)))
+                .and_then(|utc| {
+                    utc.checked_add(i64::from(correction(&posix, utc)))
+                })
+                .ok_or(Error::Tzif("transition time out of range"))?;
+        }
+        block.leaps = new.clone();
+        match size {
+            4 => block.v1().write(&mut out, version, size)?,
+            _ => block.write(&mut out, version, size)?,
+        }
+    }
+    out.extend_from_slice(footer);
+    Ok(out)
+}
+
+impl LeapSecs {
+    /// Summarize the list for a consumer that does not know about
+    /// leap seconds, as a single line of space-separated `key=value`
+    /// fields:
+    ///
+    ///   * `dtai`: the difference between UTC and TAI on the `date`
+    ///   * `since`: when that DTAI value took effect
+    ///   * `next`: the DTAI after the next leap second, or `none`
+    ///   * `from`: when the next DTAI takes effect (omitted if none)
+    ///   * `expires`: the list's expiry date
+    ///
+    /// For example,
+    ///
+    /// ```text
+    /// dtai=37 since=2017-01-01 next=none expires=2021-12-28
+    /// ```
+    ///
+    /// A POSIX time consumer needs to refresh this summary before the
+    /// `from` or `expires` date, whichever is earlier.
+    ///
+    /// Returns [`Error::TooEarly`][] if the `date` is before 1972, and
+    /// [`Error::Expired`][] if it is after the list's expiry date. The
+    /// list is still valid on its expiry date.
+    ///
+    pub fn to_tzdata_posix_string(&self, date: Gregorian) -> Result<String> {
+        let expires = Gregorian::from(self.expires());
+        if date > expires {
+            return Err(Error::Expired(expires));
+        }
+        let i = self
+            .iter()
+            .rposition(|leap| !leap.is_expiry() && leap.date() <= date)
+            .ok_or(Error::TooEarly(date))?;
+        let (this, next) = (self[i], self[i + 1]);
+        let mut out = String::new();
+        write!(out, "dtai={} since={} ", this.dtai()?, this.date())?;
+        match next.sign() {
+            Leap::Neg | Leap::Pos => {
+                write!(out, "next={} from={} ", next.dtai()?, next.date())?
+            }
+            Leap::Zero | Leap::Exp => write!(out, "next=none ")?,
+        }
+        write!(out, "expires={}", expires)?;
+        Ok(out)
+    }
+}
+
+// a TZif data block, with the header counts implied by its contents
+struct Block {
+    times: Vec<i64>,
+    types: Vec<u8>,
+    ttinfo: Vec<u8>,
+    chars: Vec<u8>,
+    leaps: Vec<(i64, i32)>,
+    isstd: Vec<u8>,
+    isut: Vec<u8>,
+}
+
+// split `len` bytes off the front of `data`
+fn take(data: &[u8], len: usize) -> Result<(&[u8], &[u8])> {
+    match len <= data.len() {
+        true => Ok(data.split_at(len)),
+        false => Err(Error::Tzif("truncated")),
+    }
+}
+
+// read a big-endian integer of `size` bytes
+fn int(data: &[u8], size: usize) -> i64 {
+    let sign = if data[0] & 0x80 != 0 { -1 } else { 0 };
+    data[..size].iter().fold(sign, |n, &b| n << 8 | i64::from(b))
+}
+
+impl Block {
+    // read a header and data block with times of `size` bytes
+    fn read(data: &[u8], size: usize) -> Result<(Block, &[u8])> {
+        let (header, data) = take(data, HEADER)?;
+        if &header[..4] != b"TZif" {
+            return Err(Error::Tzif("bad magic number"));
+        }
+        let count = |i: usize| usize::try_from(int(&header[20 + i * 4..], 4));
+        let (isutcnt, isstdcnt, leapcnt) = (count(0)?, count(1)?, count(2)?);
+        let (timecnt, typecnt, charcnt) = (count(3)?, count(4)?, count(5)?);
+        let (times, data) = take(data, timecnt * size)?;
+        let (types, data) = take(data, timecnt)?;
+        let (ttinfo, data) = take(data, typecnt * TTINFO)?;
+        let (chars, data) = take(data, charcnt)?;
+        let (leaps, data) = take(data, leapcnt * (size + 4))?;
+        let (isstd, data) = take(data, isstdcnt)?;
+        let (isut, data) = take(data, isutcnt)?;
+        let block = Block {
+            times: times.chunks(size).map(|t| int(t, size)).collect(),
+            types: types.to_vec(),
+            ttinfo: ttinfo.to_vec(),
+            chars: chars.to_vec(),
+            leaps: leaps
+                .chunks(size + 4)
+                .map(|r| (int(r, size), int(&r[size..], 4) as i32))
+                .collect(),
+            isstd: isstd.to_vec(),
+            isut: isut.to_vec(),
+        };
+        Ok((block, data))
+    }
+
+    // a copy for the version 1 part of the file, without the times
+    // that do not fit in 32 bits
+    fn v1(&self) -> Block {
+        let fits = |time: i64| i32::try_from(time).is_ok();
+        let (times, types) = self
+            .times
+            .iter()
+            .zip(&self.types)
+            .filter(|(&time, _)| fits(time))
+            .unzip();
+        Block {
+            times,
+            types,
+            ttinfo: self.ttinfo.clone(),
+            chars: self.chars.clone(),
+            leaps: self.leaps.iter().filter(|r| fits(r.0)).copied().collect(),
+            isstd: self.isstd.clone(),
+            isut: self.isut.clone(),
+        }
+    }
+
+    // append a header and data block with times of `size` bytes
+    fn write(&self, out: &mut Vec<u8>, version: u8, size: usize) -> Result<()> {
+        let time = |out: &mut Vec<u8>, time: i64| -> Result<()> {
+            match size {
+                4 => out.extend_from_slice(&i32::try_from(time)?.to_be_bytes()),
+                _ => out.extend_from_slice(&time.to_be_bytes()),
+            }
+            Ok(())
+        };
+        out.extend_from_slice(b"TZif");
+        out.push(version);
+        out.extend_from_slice(&[0; 15]);
+        for count in &[
+            self.isut.len(),
+            self.isstd.len(),
+            self.leaps.len(),
+            self.times.len(),
+            self.ttinfo.len() / TTINFO,
+            self.chars.len(),
+        ] {
+            out.extend_from_slice(&u32::try_from(*count)?.to_be_bytes());
+        }
+        for &t in &self.times {
+            time(out, t)?;
+        }
+        out.extend_from_slice(&self.types);
+        out.extend_from_slice(&self.ttinfo);
+        out.extend_from_slice(&self.chars);
+        for &(t, corr) in &self.leaps {
+            time(out, t)?;
+            out.extend_from_slice(&corr.to_be_bytes());
+        }
+        out.extend_from_slice(&self.isstd);
+        out.extend_from_slice(&self.isut);
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod test {
+    use crate::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test() {
+        let text = history!("113?");
+        let list = LeapSecs::from_str_lenient(text).unwrap();
+        let utc = tzif::write(&list).unwrap();
+        // the same as right/UTC from tzdata 2025b
+        assert_eq!(utc.len(), 664);
+        assert_eq!(&utc[..5], b"TZif2");
+        assert_eq!(&utc[44..48], &1_782_604_827_i32.to_be_bytes());
+        let records = tzif::leap_records(&list);
+        assert_eq!(records.len(), 27);
+        assert_eq!(records[26], (1_483_228_826, 27));
+        assert!(utc.ends_with(b"\x00\x00\x00\x1b\n\n"));
+
+        // patch an older file with a negative leap second
+        let old =
+            LeapSecs::from_str_lenient("6+6+12+12+12+12+12+12+12+17?").unwrap();
+        let old = tzif::write(&old).unwrap();
+        let new = "6+6+12+12+12+12+12+12+12+18-999?";
+        let new = LeapSecs::from_str_lenient(new).unwrap();
+        let patched = tzif::patch(&old, &new).unwrap();
+        assert_eq!(patched.len(), old.len() + 8 + 12);
+        let records = tzif::leap_records(&new);
+        assert_eq!(records[9], (362793609 - 1, 8));
+        // the expiry transition does not move
+        let expires: i32 = 362534400 + 9;
+        assert_eq!(&patched[44..48], &expires.to_be_bytes());
+        assert_eq!(tzif::patch(&patched, &new).unwrap(), patched);
+
+        let mut bad = utc.clone();
+        bad[4] = 0;
+        assert!(matches!(tzif::patch(&bad, &list), Err(Error::Tzif(_))));
+        assert!(matches!(tzif::patch(&utc[..600], &list), Err(Error::Tzif(_))));
+
+        // a leap second record at the start of time
+        let block = tzif::Block {
+            times: vec![i64::MIN],
+            types: vec![0],
+            ttinfo: vec![0; 6],
+            chars: b"UTC\0".to_vec(),
+            leaps: vec![(i64::MIN, 1)],
+            isstd: Vec::new(),
+            isut: Vec::new(),
+        };
+        let mut bad = Vec::new();
+        block.v1().write(&mut bad, b'2', 4).unwrap();
+        block.write(&mut bad, b'2', 8).unwrap();
+        bad.extend_from_slice(b"\n\n");
+        let err = tzif::patch(&bad, &list);
+        assert!(matches!(err, Err(Error::Tzif(_))), "{:?}", err);
+    }
+
+    #[test]
+    fn posix() {
+        let text = history!("60+939?");
+        let list = LeapSecs::from_str(text).unwrap();
+        for &(date, summary) in &[
+            (
+                Gregorian(1972, 1, 1),
+                "dtai=10 since=1972-01-01 next=11 from=1972-07-01 \
+                 expires=2100-04-28",
+            ),
+            (
+                Gregorian(2020, 1, 1),
+                "dtai=37 since=2017-01-01 next=38 from=2022-01-01 \
+                 expires=2100-04-28",
+            ),
+            (
+                Gregorian(2022, 1, 1),
+                "dtai=38 since=2022-01-01 next=none expires=2100-04-28",
+            ),
+        ] {
+            assert_eq!(summary, list.to_tzdata_posix_string(date).unwrap());
+        }
+        // the list is still valid on its expiry date
+        assert_eq!(
+            "dtai=38 since=2022-01-01 next=none expires=2100-04-28",
+            list.to_tzdata_posix_string(Gregorian(2100, 4, 28)).unwrap()
+        );
+        let late = list.to_tzdata_posix_string(Gregorian(2100, 4, 29));
+        assert!(matches!(late, Err(Error::Expired(_))));
+        let early = list.to_tzdata_posix_string(Gregorian(1971, 12, 31));
+        assert!(matches!(early, Err(Error::TooEarly(_))));
+    }
+}
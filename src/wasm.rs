@@ -0,0 +1,124 @@
+//! JavaScript bindings
+//! ===================
+//!
+//! With the `wasm` feature, the crate exports a `List` class to
+//! JavaScript using `wasm-bindgen`, so that web pages can parse,
+//! format, and look up leap second lists with the same code as this
+//! crate, for example,
+//!
+//! ```js
+//! const list = List.fromText(text, Date.now());
+//! console.log(list.expires(), list.dtaiAt(Date.now()));
+//! ```
+//!
+//! Times are in milliseconds since 1970, like `Date.now()`, and dates
+//! are ISO 8601 strings.
+//!
+//! The `wasm` feature does not imply `std`, because the `std` feature
+//! needs `curl`, which does not work on `wasm32-unknown-unknown`, and
+//! a system clock, which JavaScript provides instead. To build,
+//!
+//! ```sh
+//! cargo build --target wasm32-unknown-unknown \
+//!     --no-default-features --features wasm
+//! ```
+
+use crate::*;
+use alloc::string::{String, ToString};
+use wasm_bindgen::prelude::*;
+
+type JsResult<T> = core::result::Result<T, JsError>;
+
+// the date in a JavaScript time
+fn mjd(time: f64) -> MJD {
+    UNIX_EPOCH_MJD + (time / 86_400_000.0).floor() as i32
+}
+
+fn error(err: Error) -> JsError {
+    JsError::new(&err.to_string())
+}
+
+// fail if the list expired before `now`
+fn unexpired(list: LeapSecs, now: f64) -> Result<List> {
+    if list.is_expired_at(mjd(now)) {
+        Err(Error::Expired(Gregorian::from(list.expires())))
+    } else {
+        Ok(List(list))
+    }
+}
+
+/// A leap second list, for JavaScript.
+///
+#[wasm_bindgen]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct List(LeapSecs);
+
+#[wasm_bindgen]
+impl List {
+    /// Parse the compact text format, failing if the list expired
+    /// before `now`.
+    ///
+    #[wasm_bindgen(js_name = fromText)]
+    pub fn from_text(text: &str, now: f64) -> JsResult<List> {
+        let list = LeapSecs::from_str_lenient(text.trim_end());
+        list.and_then(|list| unexpired(list, now)).map_err(error)
+    }
+
+    /// Parse the compact binary format, failing if the list expired
+    /// before `now`.
+    ///
+    #[wasm_bindgen(js_name = fromBytes)]
+    pub fn from_bytes(bytes: &[u8], now: f64) -> JsResult<List> {
+        let list = LeapSecs::try_from_bytes_lenient(bytes);
+        list.and_then(|list| unexpired(list, now)).map_err(error)
+    }
+
+    /// Format the list in the compact text format.
+    ///
+    #[wasm_bindgen(js_name = toText)]
+    pub fn to_text(&self) -> String {
+        self.0.to_string()
+    }
+
+    /// Format the list in the compact binary format.
+    ///
+    #[wasm_bindgen(js_name = toBytes)]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        Vec::from(&self.0)
+    }
+
+    /// Get the list's expiry date.
+    ///
+    pub fn expires(&self) -> String {
+        Gregorian::from(self.0.expires()).to_string()
+    }
+
+    /// Get the difference between UTC and TAI at a time, or
+    /// `undefined` if the list does not cover it.
+    ///
+    #[wasm_bindgen(js_name = dtaiAt)]
+    pub fn dtai_at(&self, time: f64) -> Option<i16> {
+        self.0.dtai_at(Gregorian::from(mjd(time))).ok()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::wasm::*;
+
+    #[test]
+    fn test() {
+        let text = history!("59?");
+        let now = 1_609_459_200_000.0; // 2021-01-01
+        let list = List::from_text(text, now).unwrap();
+        assert_eq!(text, list.to_text());
+        assert_eq!(list, List::from_bytes(&list.to_bytes(), now).unwrap());
+        assert_eq!("2021-12-28", list.expires());
+        assert_eq!(Some(36), list.dtai_at(1_483_228_799_999.0));
+        assert_eq!(Some(37), list.dtai_at(1_483_228_800_000.0));
+        assert_eq!(None, list.dtai_at(-1.0));
+        let later = 1_640_995_200_000.0; // 2022-01-01
+        assert_eq!(None, list.dtai_at(later));
+        assert!(unexpired(list.0, later).is_err());
+    }
+}